@@ -0,0 +1,119 @@
+// ============================================================================
+// Handler Throughput Benchmarks
+// ============================================================================
+//
+// Starts the mock and measures end-to-end request latency over HTTP,
+// including JSON encoding, rules evaluation and storage.
+//
+//   cargo bench --bench handlers
+//
+//   BENCH_PORT   port for the spawned mock (default 3997)
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::json;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+fn message(mti: &str, stan: u64) -> serde_json::Value {
+    let mut message = json!({
+        "mti": mti, "de2": "5555555555554444", "de3": "000000", "de4": "100",
+        "de7": "1016120000", "de11": format!("{:06}", stan % 1_000_000), "de18": "5411",
+        "de22": "051", "de32": format!("{:06}", stan / 1_000_000),
+        "de48": "", "de49": "840", "de61": ""
+    });
+    if mti == "0400" {
+        message["de39"] = json!("00");
+        message["de90"] = json!("");
+    }
+    message
+}
+
+fn handlers(c: &mut Criterion) {
+    let port = std::env::var("BENCH_PORT").unwrap_or_else(|_| "3997".to_string());
+    let base = format!("http://127.0.0.1:{}", port);
+    let _server = Server(
+        Command::new(env!("CARGO_BIN_EXE_mastercard-api"))
+            .env("PORT", &port)
+            .env("LOG_LEVEL", "warn")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Failed to start mock server"),
+    );
+
+    let runtime = Runtime::new().unwrap();
+    let client = reqwest::Client::new();
+    let post = |path: &str, body: serde_json::Value| {
+        let request = client.post(format!("{}{}", base, path)).json(&body);
+        async move {
+            let response = request.send().await.expect("request failed");
+            assert!(response.status().is_success());
+            response.bytes().await.unwrap()
+        }
+    };
+
+    runtime.block_on(async {
+        let started = Instant::now();
+        while client.get(format!("{}/ready", base)).send().await.is_err() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "mock server did not become ready"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+
+    let next_stan = AtomicU64::new(1);
+    let mut group = c.benchmark_group("handlers");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("authorize", |b| {
+        b.to_async(&runtime).iter(|| {
+            let stan = next_stan.fetch_add(1, Ordering::Relaxed);
+            post("/authorize", message("0100", stan))
+        })
+    });
+
+    // Each iteration authorizes a fresh STAN untimed, then reverses it.
+    group.bench_function("reversal", |b| {
+        b.to_async(&runtime).iter_custom(|iterations| {
+            let stans: Vec<u64> = (0..iterations)
+                .map(|_| next_stan.fetch_add(1, Ordering::Relaxed))
+                .collect();
+            let post = &post;
+            async move {
+                let mut elapsed = Duration::ZERO;
+                for stan in stans {
+                    post("/authorize", message("0100", stan)).await;
+                    let started = Instant::now();
+                    post("/reversal", message("0400", stan)).await;
+                    elapsed += started.elapsed();
+                }
+                elapsed
+            }
+        })
+    });
+
+    group.bench_function("declined_mti", |b| {
+        b.to_async(&runtime)
+            .iter(|| post("/authorize", message("0200", 0)))
+    });
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(5));
+    targets = handlers
+}
+criterion_main!(benches);
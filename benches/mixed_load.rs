@@ -0,0 +1,169 @@
+// ============================================================================
+// Mixed Load Benchmark
+// ============================================================================
+//
+// Starts the mock on a local port and drives it with concurrent workers that
+// interleave authorizations with reversal lookups of earlier STANs, then
+// reports latency percentiles per message type.
+//
+//   cargo bench --bench mixed_load
+//
+//   BENCH_SECONDS      run time (default 10)
+//   BENCH_WORKERS      concurrent clients (default 32)
+//   BENCH_REVERSALS    percentage of requests that are reversals (default 30)
+//   BENCH_PORT         port for the spawned mock (default 3998)
+
+use rand::Rng;
+use serde_json::json;
+use std::env;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+fn authorization(stan: u64) -> serde_json::Value {
+    json!({
+        "mti": "0100", "de2": "5555555555554444", "de3": "000000", "de4": "100",
+        "de7": "1016120000", "de11": format!("{:06}", stan % 1_000_000), "de18": "5411",
+        "de32": format!("{:06}", stan / 1_000_000), "de48": "", "de49": "840", "de61": ""
+    })
+}
+
+fn reversal(stan: u64) -> serde_json::Value {
+    json!({
+        "mti": "0400", "de2": "5555555555554444", "de3": "000000", "de4": "100",
+        "de7": "1016120000", "de11": format!("{:06}", stan % 1_000_000), "de18": "5411",
+        "de22": "051", "de32": format!("{:06}", stan / 1_000_000), "de39": "00",
+        "de48": "", "de49": "840", "de61": "", "de90": ""
+    })
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+fn report(label: &str, mut latencies: Vec<Duration>, elapsed: Duration) {
+    latencies.sort();
+    println!(
+        "{:<14} {:>8} req {:>9.0} req/s   p50 {:>8.2?}   p95 {:>8.2?}   p99 {:>8.2?}   max {:>8.2?}",
+        label,
+        latencies.len(),
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        percentile(&latencies, 0.50),
+        percentile(&latencies, 0.95),
+        percentile(&latencies, 0.99),
+        latencies.last().copied().unwrap_or_default(),
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let seconds: u64 = env_or("BENCH_SECONDS", 10);
+    let workers: usize = env_or("BENCH_WORKERS", 32);
+    let reversal_pct: u32 = env_or("BENCH_REVERSALS", 30);
+    let port: u16 = env_or("BENCH_PORT", 3998);
+    let base = format!("http://127.0.0.1:{}", port);
+
+    let _server = Server(
+        Command::new(env!("CARGO_BIN_EXE_mastercard-api"))
+            .env("PORT", port.to_string())
+            .env("LOG_LEVEL", "warn")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Failed to start mock server"),
+    );
+
+    let client = reqwest::Client::new();
+    let ready = format!("{}/ready", base);
+    let started = Instant::now();
+    while !client
+        .get(&ready)
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+    {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "mock server did not become ready"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    println!(
+        "{} workers, {}s, {}% reversals against {}",
+        workers, seconds, reversal_pct, base
+    );
+
+    let next_stan = Arc::new(AtomicU64::new(1));
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let tasks: Vec<_> = (0..workers)
+        .map(|_| {
+            let client = client.clone();
+            let base = base.clone();
+            let next_stan = next_stan.clone();
+            tokio::spawn(async move {
+                let mut authorizations = Vec::new();
+                let mut reversals = Vec::new();
+                while Instant::now() < deadline {
+                    let issued = next_stan.load(Ordering::Relaxed);
+                    let reverse = issued > 1 && rand::thread_rng().gen_range(0..100) < reversal_pct;
+                    let (url, body) = if reverse {
+                        let stan = rand::thread_rng().gen_range(1..issued);
+                        (format!("{}/reversal", base), reversal(stan))
+                    } else {
+                        let stan = next_stan.fetch_add(1, Ordering::Relaxed);
+                        (format!("{}/authorize", base), authorization(stan))
+                    };
+
+                    let sent = Instant::now();
+                    let response = client.post(&url).json(&body).send().await;
+                    let latency = sent.elapsed();
+                    match response {
+                        Ok(response) if response.status().is_success() => {
+                            let _ = response.bytes().await;
+                        }
+                        Ok(response) => panic!("{} returned {}", url, response.status()),
+                        Err(e) => panic!("{} failed: {}", url, e),
+                    }
+                    if reverse {
+                        reversals.push(latency);
+                    } else {
+                        authorizations.push(latency);
+                    }
+                }
+                (authorizations, reversals)
+            })
+        })
+        .collect();
+
+    let mut authorizations = Vec::new();
+    let mut reversals = Vec::new();
+    for task in tasks {
+        let (a, r) = task.await.expect("worker panicked");
+        authorizations.extend(a);
+        reversals.extend(r);
+    }
+
+    let elapsed = Duration::from_secs(seconds);
+    report("authorization", authorizations, elapsed);
+    report("reversal", reversals, elapsed);
+}
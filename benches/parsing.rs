@@ -0,0 +1,88 @@
+// ============================================================================
+// Parsing Benchmarks
+// ============================================================================
+//
+//   cargo bench --bench parsing
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::json;
+
+use mastercard_api::de48::De48;
+use mastercard_api::iso8583::Iso8583Message;
+use mastercard_api::{card_generator, masking, wire};
+
+/// TCC, SLI, UCAF, fraud score and CVC2: a typical e-commerce DE48.
+const ECOMMERCE_DE48: &str = "T42032124328AAABBJg0VhI0VniQEjRWAAAAAAA=750409909203123";
+
+fn de48(c: &mut Criterion) {
+    let mut group = c.benchmark_group("de48");
+    group.bench_function("parse", |b| {
+        b.iter(|| De48::parse(black_box(ECOMMERCE_DE48)).unwrap())
+    });
+    let parsed = De48::parse(ECOMMERCE_DE48).unwrap();
+    group.bench_function("encode", |b| b.iter(|| black_box(&parsed).encode()));
+    group.bench_function("set_and_encode", |b| {
+        b.iter(|| {
+            let mut parsed = parsed.clone();
+            parsed.set("75", "0990");
+            parsed.encode()
+        })
+    });
+    group.finish();
+}
+
+/// An e-commerce 0100 as it goes over the network.
+fn authorization() -> Iso8583Message {
+    let mut message = Iso8583Message::new("0100");
+    let fields = [
+        (2, "5413330089604111"),
+        (3, "000000"),
+        (4, "000000001500"),
+        (7, "1016120000"),
+        (11, "000123"),
+        (18, "5411"),
+        (32, "123456"),
+        (48, ECOMMERCE_DE48),
+        (49, "840"),
+    ];
+    for (number, value) in fields {
+        message.set(number, value).unwrap();
+    }
+    message
+}
+
+// DE55 (ICC data) is carried as an opaque field; the mock has no TLV
+// parser to benchmark.
+fn wire(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wire");
+    let message = authorization();
+    group.bench_function("pack", |b| {
+        b.iter(|| wire::pack(black_box(&message)).unwrap())
+    });
+    let packed = wire::pack(&message).unwrap();
+    group.bench_function("unpack", |b| {
+        b.iter(|| wire::unpack(black_box(&packed)).unwrap())
+    });
+    group.finish();
+}
+
+fn masking(c: &mut Criterion) {
+    let message = json!({
+        "mti": "0100", "de2": "5413330089604111", "de3": "000000", "de4": "1500",
+        "de7": "1016120000", "de11": "000123", "de18": "5411", "de32": "123456",
+        "de35": "5413330089604111=25121010000000000000", "de48": ECOMMERCE_DE48,
+        "de49": "840", "de52": "1234", "de61": ""
+    });
+    c.bench_function("mask_payload", |b| {
+        b.iter(|| masking::mask_payload(black_box(message.clone())))
+    });
+}
+
+fn luhn(c: &mut Criterion) {
+    c.bench_function("luhn_check_digit", |b| {
+        b.iter(|| card_generator::luhn_check_digit(black_box("541333008960411")))
+    });
+}
+
+criterion_group!(benches, de48, wire, masking, luhn);
+criterion_main!(benches);
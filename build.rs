@@ -0,0 +1,10 @@
+fn main() {
+    // The gRPC stubs are generated with a bundled protoc, so building with
+    // the `grpc` feature needs no system protobuf install.
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::compile_protos("proto/mock.proto").expect("cannot compile proto/mock.proto");
+    }
+}
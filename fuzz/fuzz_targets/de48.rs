@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastercard_api::de48::De48;
+
+// DE48 subelements: two-digit tag, two-digit length, value. Anything that
+// parses must survive an encode/parse round trip.
+fuzz_target!(|data: &str| {
+    if let Ok(de48) = De48::parse(data) {
+        let reparsed = De48::parse(&de48.encode()).expect("encoded DE48 parses");
+        assert_eq!(reparsed.encode(), de48.encode());
+    }
+});
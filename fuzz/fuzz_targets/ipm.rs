@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastercard_api::clearing;
+
+// IPM files as POST /clearing/ipm reads them: 1014 blocking, RDW records,
+// the IPM field layout and the PDS in DE48. Anything that decodes must
+// encode and decode again to the same messages.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(messages) = clearing::decode_file(data) {
+        for message in &messages {
+            let _ = message.pds();
+        }
+        let encoded = clearing::encode_file(&messages, false).expect("decoded messages encode");
+        assert_eq!(clearing::decode_file(&encoded).ok(), Some(messages));
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastercard_api::iso8583::{self, Iso8583Message};
+use mastercard_api::AuthorizationRequest;
+
+// JSON bodies as the HTTP handlers receive them, checked against the field
+// registry the way the handlers check them.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = serde_json::from_slice::<Iso8583Message>(data) {
+        for (number, value) in message.fields.iter() {
+            if let Some(definition) = iso8583::field(number) {
+                let _ = definition.check(value.as_str());
+            }
+        }
+    }
+    let _ = serde_json::from_slice::<AuthorizationRequest>(data);
+});
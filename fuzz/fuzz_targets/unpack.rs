@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastercard_api::wire;
+
+// Bitmap and LLVAR/LLLVAR decoding of raw network bytes. Anything that
+// unpacks must pack again to the same bytes.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = wire::unpack(data) {
+        let packed = wire::pack(&message).expect("unpacked message packs");
+        assert_eq!(wire::unpack(&packed).as_ref().ok(), Some(&message));
+    }
+});
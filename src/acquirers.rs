@@ -0,0 +1,200 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::auth::constant_time_eq;
+use crate::currency;
+use crate::error::ApiError;
+use crate::faults::CutShort;
+use crate::iso8583;
+use crate::mcc;
+use crate::originals::OriginalKey;
+use crate::rate_limit::Limit;
+use crate::rules::{Action, Rule, RuleSet};
+use crate::webhooks::sign;
+use crate::AppState;
+
+// ============================================================================
+// Acquirer Profiles
+// ============================================================================
+//
+// One mock instance often serves several acquiring customers, each
+// identified by its ICA in DE32. A profile under `acquirers` in the rules
+// file gives one of them its own behavior:
+//
+// - rules and a default action, tried before the shared rules;
+// - a default currency, used when a message leaves DE49 empty;
+// - the MCCs its merchants may use, others declined with 58;
+// - a signing key: its requests must carry `X-Signature` (HMAC-SHA256 of
+//   the body, `sha256=<hex>`, as on webhooks) and responses are signed the
+//   same way. A batch is signed as a whole, so all its signed messages must
+//   share one key;
+// - its own rate limit, replacing the shared `rate_limit` for its DE32;
+// - the keys its reversals are matched to their originals by, in order
+//   (see `originals`).
+
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AcquirerProfile {
+    /// Shown in logs next to the DE32 value.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Used instead of the shared rules when none of `rules` match.
+    pub default: Option<Action>,
+    /// DE49 for messages that send it empty; alpha or numeric, numeric
+    /// once loaded.
+    pub default_currency: Option<String>,
+    /// DE18 values accepted from this acquirer; all when absent.
+    pub allowed_mccs: Option<Vec<String>>,
+    pub signing_key: Option<String>,
+    pub rate_limit: Option<Limit>,
+    /// Replaces REVERSAL_MATCH for this acquirer.
+    pub reversal_match: Option<Vec<OriginalKey>>,
+}
+
+impl AcquirerProfile {
+    /// Checks the profile's codes, normalising the default currency.
+    pub(crate) fn check(&mut self, de32: &str) -> Result<(), String> {
+        if let Some(code) = &self.default_currency {
+            let currency = currency::lookup(code)
+                .ok_or_else(|| format!("acquirer {}: unknown default_currency {:?}", de32, code))?;
+            self.default_currency = Some(currency.numeric.to_string());
+        }
+        if let Some(unknown) = self
+            .allowed_mccs
+            .iter()
+            .flatten()
+            .find(|code| mcc::lookup(code).is_none())
+        {
+            return Err(format!("acquirer {}: unknown MCC {:?}", de32, unknown));
+        }
+        Ok(())
+    }
+
+    /// Puts the default currency in an empty DE49.
+    pub fn fill_currency(&self, de49: &mut String) {
+        if let (true, Some(currency)) = (de49.is_empty(), &self.default_currency) {
+            *de49 = currency.clone();
+        }
+    }
+
+    pub fn mcc_permitted(&self, de18: &str) -> bool {
+        self.allowed_mccs
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|code| code == de18))
+    }
+}
+
+/// The profile name, or the DE32 value, for logs.
+pub fn label<'a>(de32: &'a str, profile: Option<&'a AcquirerProfile>) -> &'a str {
+    profile.and_then(|p| p.name.as_deref()).unwrap_or(de32)
+}
+
+// ============================================================================
+// Message Signatures
+// ============================================================================
+
+/// The signing key for a message body, or a batch (JSON array) of them.
+fn signing_key(rules: &RuleSet, body: &[u8]) -> Result<Option<String>, String> {
+    let key = |message: &Value| {
+        let message = message.as_object()?;
+        let de32 = iso8583::find_value(message, 32)
+            .or_else(|| iso8583::find_value(message.get("fields")?.as_object()?, 32))?
+            .as_str()?;
+        rules.acquirers.get(de32)?.signing_key.clone()
+    };
+    let keys: Vec<String> = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(messages)) => messages.iter().filter_map(key).collect(),
+        Ok(message) => key(&message).into_iter().collect(),
+        Err(_) => Vec::new(),
+    };
+    match keys.split_first() {
+        Some((first, rest)) if rest.iter().any(|other| other != first) => {
+            Err("batch mixes acquirers with different signing keys".to_string())
+        }
+        first => Ok(first.map(|(first, _)| first.clone())),
+    }
+}
+
+/// Verifies `X-Signature` on messages from acquirers with a signing key and
+/// signs the responses to them. Unsigned or badly signed messages get a
+/// 401 before any handler runs.
+pub async fn verify_signature(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let rules = state.rules();
+    if rules.acquirers.values().all(|p| p.signing_key.is_none()) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, state.max_body_bytes).await else {
+        return ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "body_too_large",
+            format!("request body exceeds {} bytes", state.max_body_bytes),
+        )
+        .into_response();
+    };
+    let key = match signing_key(&rules, &bytes) {
+        Ok(key) => key,
+        Err(message) => {
+            warn!("Rejected batch: {}", message);
+            return ApiError::new(StatusCode::UNAUTHORIZED, "invalid_signature", message)
+                .into_response();
+        }
+    };
+    let request = Request::from_parts(parts, Body::from(bytes.clone()));
+    let Some(key) = key else {
+        return next.run(request).await;
+    };
+
+    let presented = request
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !presented.is_some_and(|sig| constant_time_eq(sig.as_bytes(), sign(&key, &bytes).as_bytes()))
+    {
+        warn!(
+            "Rejected message with missing or invalid {}",
+            SIGNATURE_HEADER
+        );
+        return ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "invalid_signature",
+            format!("missing or invalid {} header", SIGNATURE_HEADER),
+        )
+        .into_response();
+    }
+
+    let response = next.run(request).await;
+    if response.extensions().get::<CutShort>().is_some() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    match to_bytes(body, usize::MAX).await {
+        Ok(body) => {
+            let signature = HeaderValue::from_str(&sign(&key, &body))
+                .expect("hex signature is a valid header value");
+            parts.headers.insert(SIGNATURE_HEADER, signature);
+            Response::from_parts(parts, Body::from(body))
+        }
+        // A connection fault: pass the broken body on as it is.
+        Err(e) => Response::from_parts(
+            parts,
+            Body::from_stream(futures::stream::once(async move { Err::<Vec<u8>, _>(e) })),
+        ),
+    }
+}
@@ -0,0 +1,670 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::amount;
+use crate::card_generator::{GenerateCards, GeneratedCard};
+use crate::cards::{CardProfile, CardStatus};
+use crate::de7;
+use crate::error::ApiJson;
+use crate::faults::Fault;
+use crate::latency::Latency;
+use crate::ledger::Account;
+use crate::masking;
+use crate::rules::RuleSet;
+use crate::store::StoreStats;
+use crate::{generate_rrn, transaction_key, AppState, ResponseCode, Transaction, TransactionState};
+
+// ============================================================================
+// Admin API
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardStatusBody {
+    pub status: CardStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardStatusResponse {
+    pub pan: String,
+    pub status: CardStatus,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/cards",
+    tag = "admin",
+    responses((status = 200, description = "Every card profile, ordered by PAN", body = Vec<CardProfile>)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_cards(State(state): State<Arc<AppState>>) -> Json<Vec<CardProfile>> {
+    Json(state.cards.profiles())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/cards/{pan}/status",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    responses((status = 200, body = CardStatusResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_card_status(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+) -> Json<CardStatusResponse> {
+    let status = state.cards.status(&pan);
+    Json(CardStatusResponse { pan, status })
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/cards/{pan}/status",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    request_body = CardStatusBody,
+    responses((status = 200, body = CardStatusResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn set_card_status(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+    ApiJson(body): ApiJson<CardStatusBody>,
+) -> Json<CardStatusResponse> {
+    info!(
+        "Admin: card {} status set to {:?}",
+        masking::pan_for_log(&pan),
+        body.status
+    );
+    state.cards.set_status(&pan, body.status);
+    Json(CardStatusResponse {
+        pan,
+        status: body.status,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PinResetResponse {
+    pub pan: String,
+    pub previous_failures: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct PinResetQuery {
+    /// Limit the reset to one acquirer's counter (DE32); all when absent.
+    pub acquirer_id: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/cards/{pan}/pin-reset",
+    tag = "admin",
+    params(("pan" = String, Path), PinResetQuery),
+    responses((status = 200, body = PinResetResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reset_pin_tries(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+    Query(query): Query<PinResetQuery>,
+) -> Json<PinResetResponse> {
+    let previous_failures = state
+        .cards
+        .reset_pin_failures(&pan, query.acquirer_id.as_deref());
+    info!(
+        "Admin: PIN retry counter reset for card {}",
+        masking::pan_for_log(&pan)
+    );
+    Json(PinResetResponse {
+        pan,
+        previous_failures,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct GenerateCardsRequest {
+    #[serde(flatten)]
+    pub spec: GenerateCards,
+    /// Opening balance for every generated card; deck default when absent.
+    pub balance: Option<i64>,
+    pub credit_limit: Option<i64>,
+    #[serde(default)]
+    pub status: CardStatus,
+}
+
+/// Mints Luhn-valid cards and registers each one as a card profile.
+#[utoipa::path(
+    post,
+    path = "/admin/cards/generate",
+    tag = "admin",
+    request_body = GenerateCardsRequest,
+    responses(
+        (status = 200, body = Vec<GeneratedCard>),
+        (status = 422, description = "Invalid BIN or PAN length", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn generate_cards(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<GenerateCardsRequest>,
+) -> Result<Json<Vec<GeneratedCard>>, AdminError> {
+    let cards = body
+        .spec
+        .generate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    for card in &cards {
+        let profile = CardProfile {
+            pan: card.pan.clone(),
+            balance: body.balance,
+            credit_limit: body.credit_limit,
+            status: body.status,
+            expiry: Some(card.expiry.clone()),
+            cvc2: Some(card.cvc2.clone()),
+            ..CardProfile::default()
+        };
+        state.cards.upsert(profile.clone());
+        state.ledger.seed_card(profile);
+    }
+
+    info!("Admin: generated {} cards", cards.len());
+    Ok(Json(cards))
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AccountResponse {
+    pub pan: String,
+    pub ledger_balance: i64,
+    pub credit_limit: i64,
+    pub held: i64,
+    pub available_balance: i64,
+    /// Outstanding authorization holds keyed by DE32:STAN.
+    pub holds: HashMap<String, i64>,
+}
+
+impl From<Account> for AccountResponse {
+    fn from(account: Account) -> Self {
+        AccountResponse {
+            held: account.held(),
+            available_balance: account.available_balance(),
+            pan: account.pan,
+            ledger_balance: account.ledger_balance,
+            credit_limit: account.credit_limit,
+            holds: account.holds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AccountUpdate {
+    pub balance: Option<i64>,
+    pub credit_limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BalanceAdjustment {
+    /// Minor units; negative to debit.
+    pub amount: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/accounts",
+    tag = "admin",
+    responses((status = 200, body = Vec<AccountResponse>)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_accounts(State(state): State<Arc<AppState>>) -> Json<Vec<AccountResponse>> {
+    Json(
+        state
+            .ledger
+            .accounts()
+            .into_iter()
+            .map(AccountResponse::from)
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/accounts/{pan}",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    responses((status = 200, body = AccountResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_account(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+) -> Json<AccountResponse> {
+    Json(state.ledger.account(&pan).into())
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/accounts/{pan}",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    request_body = AccountUpdate,
+    responses((status = 200, body = AccountResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn update_account(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+    ApiJson(body): ApiJson<AccountUpdate>,
+) -> Json<AccountResponse> {
+    info!(
+        "Admin: account {} updated (balance {:?}, credit limit {:?})",
+        masking::pan_for_log(&pan),
+        body.balance,
+        body.credit_limit
+    );
+    Json(
+        state
+            .ledger
+            .update(&pan, body.balance, body.credit_limit)
+            .into(),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/accounts/{pan}/adjust",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    request_body = BalanceAdjustment,
+    responses((status = 200, body = AccountResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn adjust_balance(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+    ApiJson(body): ApiJson<BalanceAdjustment>,
+) -> Json<AccountResponse> {
+    info!(
+        "Admin: account {} adjusted by {}",
+        masking::pan_for_log(&pan),
+        body.amount
+    );
+    Json(state.ledger.adjust(&pan, body.amount).into())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/accounts/{pan}",
+    tag = "admin",
+    params(("pan" = String, Path)),
+    responses(
+        (status = 204, description = "Account closed"),
+        (status = 404, description = "No such account"),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn close_account(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+) -> StatusCode {
+    if state.ledger.close(&pan) {
+        info!("Admin: account {} closed", masking::pan_for_log(&pan));
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+// ============================================================================
+// State Reset
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResetResponse {
+    pub cleared: Vec<&'static str>,
+}
+
+type AdminError = (StatusCode, String);
+
+/// Also forgets the STANs seen for duplicate detection.
+fn clear_transactions(state: &AppState) -> Result<(), AdminError> {
+    if let Some(stans) = &state.stans {
+        stans.clear();
+    }
+    state.clearing_matches.clear();
+    state
+        .authorized_transactions
+        .clear()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Re-reads CARDS_FILE and resets profiles, tokens and account balances
+/// from it.
+fn reload_cards(state: &AppState) -> Result<(), AdminError> {
+    let deck = state
+        .default_deck()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    state.cards.reload(&deck);
+    state.tokens.reload(&deck.tokens);
+    state.ledger.reset(deck);
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reset",
+    tag = "admin",
+    responses(
+        (status = 200, body = ResetResponse),
+        (status = 422, description = "CARDS_FILE could not be reloaded", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reset_all(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ResetResponse>, AdminError> {
+    reload_cards(&state)?;
+    clear_transactions(&state)?;
+    state.cards.reset_counters();
+    info!("Admin: full reset");
+    Ok(Json(ResetResponse {
+        cleared: vec!["transactions", "counters", "cards"],
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reset/transactions",
+    tag = "admin",
+    responses((status = 200, body = ResetResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reset_transactions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ResetResponse>, AdminError> {
+    clear_transactions(&state)?;
+    info!("Admin: transactions cleared");
+    Ok(Json(ResetResponse {
+        cleared: vec!["transactions"],
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reset/counters",
+    tag = "admin",
+    responses((status = 200, body = ResetResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reset_counters(State(state): State<Arc<AppState>>) -> Json<ResetResponse> {
+    state.cards.reset_counters();
+    info!("Admin: counters cleared");
+    Json(ResetResponse {
+        cleared: vec!["counters"],
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reset/cards",
+    tag = "admin",
+    responses(
+        (status = 200, body = ResetResponse),
+        (status = 422, description = "CARDS_FILE could not be reloaded", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reload_card_profiles(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ResetResponse>, AdminError> {
+    reload_cards(&state)?;
+    info!("Admin: card profiles reloaded");
+    Ok(Json(ResetResponse {
+        cleared: vec!["cards"],
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/store",
+    tag = "admin",
+    responses((status = 200, body = StoreStats)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn store_stats(State(state): State<Arc<AppState>>) -> Json<StoreStats> {
+    Json(state.authorized_transactions.stats())
+}
+
+// ============================================================================
+// Active Rules and Fault Toggles
+// ============================================================================
+//
+// The rule set in force, and the faults applied to messages no rule
+// matched, as in the config file's `[faults]` section. Changing the toggles
+// swaps the rule set like a snapshot restore does; they stay in force until
+// changed again or a snapshot is restored.
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FaultToggles {
+    /// Latency of authorizations whose rule sets none.
+    #[schema(value_type = Option<Object>)]
+    pub authorize_latency: Option<Latency>,
+    /// Latency of reversals.
+    #[schema(value_type = Option<Object>)]
+    pub reversal_latency: Option<Latency>,
+    /// Fault of the default action.
+    #[schema(value_type = Option<Object>)]
+    pub default_fault: Option<Fault>,
+}
+
+impl FaultToggles {
+    fn of(rules: &RuleSet) -> Self {
+        FaultToggles {
+            authorize_latency: rules.endpoint_latency.authorize.clone(),
+            reversal_latency: rules.endpoint_latency.reversal.clone(),
+            default_fault: rules.default.fault.clone(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/rules",
+    tag = "admin",
+    responses((status = 200, description = "The rule set in force, as in RULES_FILE", body = Object)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn active_rules(State(state): State<Arc<AppState>>) -> Json<RuleSet> {
+    Json(state.rules().as_ref().clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/faults",
+    tag = "admin",
+    responses((status = 200, body = FaultToggles)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_faults(State(state): State<Arc<AppState>>) -> Json<FaultToggles> {
+    Json(FaultToggles::of(&state.rules()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/faults",
+    tag = "admin",
+    request_body = FaultToggles,
+    responses(
+        (status = 200, body = FaultToggles),
+        (status = 422, description = "The rule set could not be rebuilt", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn set_faults(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<FaultToggles>,
+) -> Result<Json<FaultToggles>, AdminError> {
+    let toggles = state
+        .update_rules(|rules| {
+            rules.endpoint_latency.authorize = body.authorize_latency;
+            rules.endpoint_latency.reversal = body.reversal_latency;
+            rules.default.fault = body.default_fault;
+            Ok::<_, Infallible>(FaultToggles::of(rules))
+        })
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let Ok(toggles) = toggles;
+    info!("Admin: fault toggles set to {:?}", toggles);
+    Ok(Json(toggles))
+}
+
+// ============================================================================
+// State Seeding
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SeedRequest {
+    #[serde(default)]
+    pub cards: Vec<CardProfile>,
+    #[serde(default)]
+    pub transactions: Vec<SeedTransaction>,
+}
+
+/// A transaction to store as if the mock had processed it.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SeedTransaction {
+    pub pan: String,
+    pub amount: String,
+    pub stan: String,
+    /// DE32 the reversal or completion will carry.
+    pub acquirer_id: String,
+    /// Generated (`YDDDhh` + STAN) when absent.
+    pub rrn: Option<String>,
+    #[serde(default)]
+    pub timestamp: String,
+    #[serde(default = "default_seed_response_code")]
+    pub response_code: ResponseCode,
+    #[serde(default)]
+    pub state: TransactionState,
+    pub captured_amount: Option<String>,
+}
+
+fn default_seed_response_code() -> ResponseCode {
+    ResponseCode::Approved
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SeedResponse {
+    pub cards: usize,
+    /// RRNs of the seeded transactions, in request order.
+    pub transactions: Vec<String>,
+}
+
+/// Cards are applied first so that seeded authorizations hold against the
+/// seeded balances.
+#[utoipa::path(
+    post,
+    path = "/admin/seed",
+    tag = "admin",
+    request_body = SeedRequest,
+    responses(
+        (status = 200, body = SeedResponse),
+        (status = 422, description = "Invalid amount or insufficient funds for a hold", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn seed(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<SeedRequest>,
+) -> Result<Json<SeedResponse>, AdminError> {
+    let amounts = body
+        .transactions
+        .iter()
+        .map(|seed| {
+            amount::parse_minor_units(&seed.amount).ok_or_else(|| {
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("invalid amount {:?} for STAN {}", seed.amount, seed.stan),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    body.cards
+        .iter()
+        .try_for_each(CardProfile::check)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    let cards = body.cards.len();
+    for profile in body.cards {
+        state.cards.upsert(profile.clone());
+        state.ledger.seed_card(profile);
+    }
+
+    let mut rrns = Vec::with_capacity(body.transactions.len());
+    for (seed, amount) in body.transactions.into_iter().zip(amounts) {
+        let key = transaction_key(&seed.acquirer_id, &seed.stan);
+        if seed.state == TransactionState::Authorized {
+            state.ledger.hold(&seed.pan, &key, amount).map_err(|_| {
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("insufficient funds to hold STAN {}", seed.stan),
+                )
+            })?;
+        }
+        let recorded_at = state.clock.now();
+        let rrn = seed
+            .rrn
+            .unwrap_or_else(|| generate_rrn(&seed.stan, recorded_at));
+        // Seeds carry no DE18, so the default hold period applies.
+        let expires_at = (seed.state == TransactionState::Authorized)
+            .then(|| state.hold_expires_at("", recorded_at))
+            .flatten();
+        let transaction = Transaction {
+            pan: seed.pan,
+            amount: seed.amount,
+            stan: seed.stan.clone(),
+            transmitted_at: de7::parse(&seed.timestamp, recorded_at),
+            timestamp: seed.timestamp,
+            response_code: seed.response_code,
+            recorded_at,
+            rrn: rrn.clone(),
+            request: None,
+            response: None,
+            state: seed.state,
+            captured_amount: seed.captured_amount,
+            captures: Vec::new(),
+            refunded_amount: None,
+            refund_of: None,
+            terminal_id: None,
+            card_acceptor_id: None,
+            reversal_de90: None,
+            acquirer_id: seed.acquirer_id,
+            correlation_id: None,
+            token: None,
+            installments: None,
+            trace_id: None,
+            settlement_date: None,
+            expires_at,
+        };
+        state
+            .authorized_transactions
+            .insert(&key, transaction)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        rrns.push(rrn);
+    }
+
+    info!("Admin: seeded {} cards, {} transactions", cards, rrns.len());
+    Ok(Json(SeedResponse {
+        cards,
+        transactions: rrns,
+    }))
+}
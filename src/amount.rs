@@ -0,0 +1,82 @@
+use std::fmt;
+
+use crate::currency::Currency;
+
+// ============================================================================
+// Amounts
+// ============================================================================
+//
+// DE4 carries an integer count of the DE49 currency's minor units: `1500`
+// is 15.00 USD, 1500 JPY (no minor unit) or 1.500 KWD (three decimals).
+// Balance math stays in integers; the exponent only matters when amounts
+// in different currencies are compared and when they are shown to people.
+
+/// Minor units from a DE4 value: digits only, so signs, spaces, decimal
+/// points and values beyond `i64` are rejected rather than misread.
+pub fn parse_minor_units(value: &str) -> Option<i64> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount {
+    pub minor_units: i64,
+    pub currency: &'static Currency,
+}
+
+impl Amount {
+    pub fn new(minor_units: i64, currency: &'static Currency) -> Self {
+        Amount {
+            minor_units,
+            currency,
+        }
+    }
+
+    /// DE4 in the currency of DE49.
+    pub fn parse(de4: &str, currency: &'static Currency) -> Option<Self> {
+        parse_minor_units(de4).map(|minor_units| Amount::new(minor_units, currency))
+    }
+
+    /// The amount with `exponent` decimals instead of the currency's, so
+    /// amounts in different currencies can be compared against one
+    /// threshold; extra digits are truncated and overflow saturates.
+    pub fn scaled(&self, exponent: u32) -> i64 {
+        let from = self.currency.exponent;
+        if exponent >= from {
+            self.minor_units
+                .saturating_mul(10i64.saturating_pow(exponent - from))
+        } else {
+            self.minor_units / 10i64.pow(from - exponent)
+        }
+    }
+
+    /// Twelve zero-filled digits, as DE4 travels on the wire.
+    pub fn to_de4(&self) -> String {
+        format!("{:012}", self.minor_units)
+    }
+}
+
+/// `15.00 USD`, `1500 JPY`, `1.500 KWD`.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let units = self.minor_units.unsigned_abs();
+        match self.currency.exponent {
+            0 => write!(f, "{}{} {}", sign, units, self.currency.alpha),
+            exponent => {
+                let divisor = 10u64.pow(exponent);
+                write!(
+                    f,
+                    "{}{}.{:0width$} {}",
+                    sign,
+                    units / divisor,
+                    units % divisor,
+                    self.currency.alpha,
+                    width = exponent as usize
+                )
+            }
+        }
+    }
+}
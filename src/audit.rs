@@ -0,0 +1,283 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Query, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::correlation;
+use crate::error::ApiError;
+use crate::masking;
+use crate::AppState;
+
+// ============================================================================
+// Audit Log
+// ============================================================================
+//
+// Every message exchange on /authorize, /reversal and /completion is
+// appended to AUDIT_LOG as one NDJSON line, masked like every other output
+// (unless `--log-sensitive`) and independent of console logging. Once the
+// file would exceed AUDIT_LOG_MAX_BYTES it is rotated to `<path>.1`,
+// `<path>.2`, ... keeping AUDIT_LOG_FILES old files.
+
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_FILES: usize = 5;
+const DEFAULT_TAIL: usize = 100;
+const MAX_TAIL: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub path: String,
+    /// Absent when the client went away before a response was produced.
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    pub request: Value,
+    pub response: Value,
+}
+
+struct Output {
+    file: File,
+    size: u64,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+    files: usize,
+    output: Mutex<Output>,
+}
+
+impl AuditLog {
+    pub fn open(
+        path: PathBuf,
+        max_bytes: Option<u64>,
+        files: Option<usize>,
+    ) -> Result<Self, String> {
+        let output = Self::open_output(&path)?;
+        Ok(AuditLog {
+            path,
+            max_bytes: max_bytes.unwrap_or(DEFAULT_MAX_BYTES).max(1),
+            files: files.unwrap_or(DEFAULT_FILES),
+            output: Mutex::new(output),
+        })
+    }
+
+    fn open_output(path: &Path) -> Result<Output, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Output { file, size })
+    }
+
+    /// `<path>.<n>`; 0 is the live file.
+    fn generation(&self, n: usize) -> PathBuf {
+        if n == 0 {
+            return self.path.clone();
+        }
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        name.into()
+    }
+
+    fn rotate(&self, output: &mut Output) -> Result<(), String> {
+        if self.files == 0 {
+            fs::remove_file(&self.path).map_err(|e| e.to_string())?;
+        } else {
+            let _ = fs::remove_file(self.generation(self.files));
+            for n in (0..self.files).rev() {
+                let from = self.generation(n);
+                if from.exists() {
+                    fs::rename(&from, self.generation(n + 1)).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        *output = Self::open_output(&self.path)?;
+        Ok(())
+    }
+
+    pub fn append(&self, entry: &AuditEntry) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Audit entry not serializable: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut output = self.output.lock().unwrap();
+        if output.size > 0 && output.size + line.len() as u64 > self.max_bytes {
+            if let Err(e) = self.rotate(&mut output) {
+                warn!("Audit log rotation failed: {}", e);
+            }
+        }
+        match output.file.write_all(&line) {
+            Ok(()) => output.size += line.len() as u64,
+            Err(e) => warn!("Audit log write failed: {}", e),
+        }
+    }
+
+    /// The last `limit` entries, oldest first, reading into rotated files
+    /// as needed.
+    pub fn tail(&self, limit: usize) -> Vec<AuditEntry> {
+        // Holding the lock keeps rotation from moving files mid-read.
+        let _output = self.output.lock().unwrap();
+        let mut entries = Vec::new();
+        for n in 0..=self.files {
+            let Ok(file) = File::open(self.generation(n)) else {
+                break;
+            };
+            let lines: Vec<String> = BufReader::new(file).lines().map_while(Result::ok).collect();
+            for line in lines.iter().rev() {
+                if entries.len() == limit {
+                    break;
+                }
+                if let Ok(entry) = serde_json::from_str(line) {
+                    entries.push(entry);
+                }
+            }
+            if entries.len() == limit {
+                break;
+            }
+        }
+        entries.reverse();
+        entries
+    }
+}
+
+/// Masked JSON, or a placeholder for bodies that aren't JSON (they may hold
+/// anything). With `--log-sensitive` both are kept verbatim, which makes
+/// the log a replayable recording.
+fn body_value(bytes: &[u8]) -> Value {
+    match serde_json::from_slice(bytes) {
+        Ok(value) if masking::log_sensitive() => value,
+        Ok(value) => masking::mask_payload(value),
+        Err(_) if bytes.is_empty() => Value::Null,
+        Err(_) if masking::log_sensitive() => {
+            Value::String(String::from_utf8_lossy(bytes).into_owned())
+        }
+        Err(_) => Value::String(format!("<{} bytes, not JSON>", bytes.len())),
+    }
+}
+
+/// Entry under construction; written when dropped, so exchanges whose
+/// response body is aborted or never produced are still recorded.
+struct Pending {
+    state: Arc<AppState>,
+    started: Instant,
+    entry: AuditEntry,
+    response: Option<Vec<u8>>,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(audit) = &self.state.audit {
+            self.entry.duration_ms = self.started.elapsed().as_millis() as u64;
+            if let Some(body) = &self.response {
+                self.entry.response = body_value(body);
+            }
+            audit.append(&self.entry);
+        }
+    }
+}
+
+pub async fn record(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if state.audit.is_none() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, state.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Audit: could not read request body: {}", e);
+            return ApiError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "body_too_large",
+                format!("request body exceeds {} bytes", state.max_body_bytes),
+            )
+            .into_response();
+        }
+    };
+
+    let mut pending = Pending {
+        state: state.clone(),
+        started: Instant::now(),
+        entry: AuditEntry {
+            at: Utc::now(),
+            path: parts.uri.path().to_string(),
+            status: None,
+            duration_ms: 0,
+            correlation_id: correlation::header_id(&parts.headers),
+            request: body_value(&bytes),
+            response: Value::Null,
+        },
+        response: None,
+    };
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    pending.entry.status = Some(response.status().as_u16());
+    pending.response = Some(Vec::new());
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        if let (Ok(bytes), Some(buffer)) = (&chunk, &mut pending.response) {
+            buffer.extend_from_slice(bytes);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+// ============================================================================
+// Admin Handler
+// ============================================================================
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct TailQuery {
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "admin",
+    params(TailQuery),
+    responses(
+        (status = 200, description = "Most recent entries, oldest first", body = Vec<AuditEntry>),
+        (status = 404, description = "Audit log disabled", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn tail_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TailQuery>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    let audit = state.audit.as_ref().ok_or((
+        StatusCode::NOT_FOUND,
+        "audit log disabled (set AUDIT_LOG)".to_string(),
+    ))?;
+    let limit = query.limit.unwrap_or(DEFAULT_TAIL).min(MAX_TAIL);
+    Ok(Json(audit.tail(limit)))
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::AppState;
+
+// ============================================================================
+// Admin API Keys
+// ============================================================================
+
+/// Parses ADMIN_API_KEYS: comma-separated, blanks ignored.
+pub fn parse_keys(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Either `Authorization: Bearer <key>` or `X-API-Key: <key>`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| {
+        headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+    })
+}
+
+/// Compares without short-circuiting on the first differing byte.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rejects admin requests without a configured key. With no keys
+/// configured, admin routes stay open.
+pub async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if state.admin_api_keys.is_empty() {
+        return next.run(request).await;
+    }
+
+    let authorized = presented_key(request.headers()).is_some_and(|presented| {
+        state
+            .admin_api_keys
+            .iter()
+            .any(|key| constant_time_eq(key.as_bytes(), presented.as_bytes()))
+    });
+
+    if authorized {
+        next.run(request).await
+    } else {
+        warn!(
+            "Admin: rejected unauthenticated {} {}",
+            request.method(),
+            request.uri()
+        );
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or invalid API key",
+        )
+            .into_response()
+    }
+}
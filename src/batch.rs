@@ -0,0 +1,151 @@
+use axum::{
+    body::to_bytes,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::dispatch::MessageContext;
+use crate::error::{self, ApiError, ApiJson, ErrorBody};
+use crate::handlers;
+use crate::iso8583::KeyStyle;
+use crate::{AppState, Iso8583Message};
+
+// ============================================================================
+// Batch Authorization (`/authorize/batch`)
+// ============================================================================
+//
+// Load generators and seeding scripts can send many authorizations in one
+// request: a JSON array of /authorize bodies, answered with one result per
+// message, in the order sent. Up to BATCH_CONCURRENCY messages are processed
+// at once, each exactly as /authorize would (rules, latency, faults,
+// storage, webhooks), so a message that fails to parse or is rejected only
+// fails its own result.
+//
+// Headers apply to every message: one correlation ID for the batch, and an
+// `X-Signature` over the whole array when its acquirers sign. A fault that
+// withholds the response holds up the whole batch.
+
+pub const MAX_BATCH_SIZE: usize = 1000;
+const BATCH_CONCURRENCY: usize = 32;
+const ITEM_BODY_LIMIT: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BatchResult {
+    /// HTTP status /authorize would have answered with.
+    pub status: u16,
+    /// The 0110 response, or the error envelope.
+    #[schema(value_type = Object)]
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BatchResponse {
+    pub results: Vec<BatchResult>,
+}
+
+async fn authorize_one(
+    state: Arc<AppState>,
+    peer: SocketAddr,
+    headers: HeaderMap,
+    message: Value,
+) -> BatchResult {
+    let context = MessageContext {
+        peer,
+        headers,
+        received: Instant::now(),
+    };
+    let style = match &message {
+        Value::Object(object) => KeyStyle::detect(object),
+        _ => KeyStyle::default(),
+    };
+    let response = match error::from_value::<Iso8583Message>(message) {
+        Ok(message) => {
+            let pipeline = state.pipeline.clone();
+            pipeline
+                .run(&state, &context, message, |message| async {
+                    let value = serde_json::to_value(message).expect("a message serializes");
+                    match error::from_value(value) {
+                        Ok(request) => {
+                            handlers::authorize(
+                                State(state.clone()),
+                                ConnectInfo(peer),
+                                context.headers.clone(),
+                                ApiJson(request),
+                            )
+                            .await
+                        }
+                        Err(e) => e.into_response(),
+                    }
+                })
+                .await
+        }
+        Err(e) => e.into_response(),
+    };
+
+    let status = response.status();
+    match to_bytes(response.into_body(), ITEM_BODY_LIMIT).await {
+        Ok(bytes) => BatchResult {
+            status: status.as_u16(),
+            body: match serde_json::from_slice(&bytes) {
+                Ok(Value::Object(object)) if object.contains_key("mti") => {
+                    Value::Object(style.restyle(object))
+                }
+                Ok(body) => body,
+                Err(_) => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+            },
+        },
+        // A fault cut the response short.
+        Err(e) => BatchResult {
+            status: StatusCode::BAD_GATEWAY.as_u16(),
+            body: Value::String(e.to_string()),
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/authorize/batch",
+    tag = "messages",
+    request_body(content = Vec<crate::AuthorizationRequest>, description = "/authorize bodies"),
+    responses(
+        (status = 200, description = "One result per message, in order", body = BatchResponse),
+        (status = 422, description = "Not an array, or more than 1000 messages", body = ErrorBody),
+    )
+)]
+pub async fn authorize_batch(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(messages): ApiJson<Vec<Value>>,
+) -> Response {
+    if messages.len() > MAX_BATCH_SIZE {
+        return ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "batch_too_large",
+            format!(
+                "batch has {} messages, at most {} are allowed",
+                messages.len(),
+                MAX_BATCH_SIZE
+            ),
+        )
+        .into_response();
+    }
+
+    info!("Batch of {} authorizations", messages.len());
+    let results = stream::iter(messages)
+        .map(|message| authorize_one(state.clone(), peer, headers.clone(), message))
+        .buffered(BATCH_CONCURRENCY)
+        .collect()
+        .await;
+    (StatusCode::OK, Json(BatchResponse { results })).into_response()
+}
@@ -0,0 +1,224 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::country;
+use crate::pan::BinRange;
+use crate::rules::FieldSource;
+use crate::AppState;
+
+// ============================================================================
+// BIN Table
+// ============================================================================
+//
+// Maps BIN ranges to what an issuer would know about the card: the
+// Mastercard product code (MCC standard credit, MCG Gold, MCO Corporate,
+// ...), card type, whether it is a commercial product, and the issuing
+// country. A small table is bundled; BIN_TABLE names a YAML list whose
+// entries are added to it. When ranges overlap the most specific wins:
+// the longest BIN, then the narrowest range.
+//
+// Rules see the entry of the message's PAN as the pseudo-fields
+// `bin.product`, `bin.card_type`, `bin.commercial` (`true` / `false`) and
+// `bin.country` (ISO 3166 numeric), usable under `fields`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CardType {
+    Credit,
+    Debit,
+    Prepaid,
+}
+
+impl CardType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CardType::Credit => "credit",
+            CardType::Debit => "debit",
+            CardType::Prepaid => "prepaid",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BinEntry {
+    /// A BIN prefix or an inclusive range of equal-length prefixes.
+    pub bin: String,
+    /// Mastercard product code, e.g. `MCS`.
+    pub product: String,
+    pub card_type: CardType,
+    #[serde(default)]
+    pub commercial: bool,
+    /// Issuing country, ISO 3166 alpha-3 or numeric; numeric once loaded.
+    pub country: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+}
+
+const fn e(
+    bin: &'static str,
+    product: &'static str,
+    card_type: CardType,
+    commercial: bool,
+    country: &'static str,
+) -> (&'static str, &'static str, CardType, bool, &'static str) {
+    (bin, product, card_type, commercial, country)
+}
+
+const BUNDLED: &[(&str, &str, CardType, bool, &str)] = &[
+    e("222100-229999", "MCW", CardType::Credit, false, "840"),
+    e("230000-272099", "MCS", CardType::Credit, false, "840"),
+    e("500000-509999", "MSI", CardType::Debit, false, "276"),
+    e("510000-519999", "MCC", CardType::Credit, false, "840"),
+    e("520000-529999", "MDS", CardType::Debit, false, "840"),
+    e("530000-539999", "MCG", CardType::Credit, false, "826"),
+    e("535000-535999", "MPA", CardType::Prepaid, false, "826"),
+    e("540000-544999", "MPL", CardType::Credit, false, "840"),
+    e("545000-549999", "MCO", CardType::Credit, true, "840"),
+    e("550000-554999", "MCB", CardType::Credit, true, "840"),
+    e("555000-559999", "MCS", CardType::Credit, false, "840"),
+    e("560000-699999", "MSI", CardType::Debit, false, "826"),
+];
+
+#[derive(Debug, Clone)]
+pub struct BinTable {
+    entries: Vec<(BinRange, BinEntry)>,
+}
+
+impl Default for BinTable {
+    fn default() -> Self {
+        BinTable::bundled()
+    }
+}
+
+impl BinTable {
+    pub fn bundled() -> Self {
+        let entries = BUNDLED
+            .iter()
+            .map(|(bin, product, card_type, commercial, country)| BinEntry {
+                bin: bin.to_string(),
+                product: product.to_string(),
+                card_type: *card_type,
+                commercial: *commercial,
+                country: country.to_string(),
+                issuer: None,
+            })
+            .collect();
+        BinTable::from_entries(entries).expect("bundled BIN table is valid")
+    }
+
+    pub fn from_entries(entries: Vec<BinEntry>) -> Result<Self, String> {
+        let entries = entries
+            .into_iter()
+            .map(|mut entry| {
+                let range = BinRange::parse(&entry.bin)?;
+                entry.country = country::normalize(&entry.country)
+                    .ok_or_else(|| format!("unknown country {:?}", entry.country))?;
+                Ok((range, entry))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(BinTable { entries })
+    }
+
+    /// The bundled table plus the entries listed in `path`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let extra: Vec<BinEntry> =
+            serde_yaml::from_str(&contents).map_err(|e| format!("invalid BIN table: {}", e))?;
+        let mut table = BinTable::bundled();
+        table.entries.extend(BinTable::from_entries(extra)?.entries);
+        Ok(table)
+    }
+
+    /// The most specific entry whose range contains `pan`.
+    pub fn lookup(&self, pan: &str) -> Option<&BinEntry> {
+        self.entries
+            .iter()
+            .filter(|(range, _)| range.contains(pan))
+            .max_by_key(|(range, _)| {
+                let span = match range {
+                    BinRange::Prefix(_) => 0,
+                    BinRange::Range { start, end, .. } => end - start,
+                };
+                (range.width(), std::cmp::Reverse(span))
+            })
+            .map(|(_, entry)| entry)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &BinEntry> {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+}
+
+/// A message as rules see it: its fields plus the `bin.*` attributes of
+/// its PAN.
+pub struct WithBin<'a, M> {
+    pub message: &'a M,
+    pub bin: Option<&'a BinEntry>,
+}
+
+impl<M: FieldSource> FieldSource for WithBin<'_, M> {
+    fn field(&self, name: &str) -> Option<&str> {
+        let Some(attribute) = name.strip_prefix("bin.") else {
+            return self.message.field(name);
+        };
+        let bin = self.bin?;
+        match attribute {
+            "product" => Some(&bin.product),
+            "card_type" => Some(bin.card_type.as_str()),
+            "commercial" => Some(if bin.commercial { "true" } else { "false" }),
+            "country" => Some(&bin.country),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Lookup Endpoints
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/reference/bins",
+    tag = "reference",
+    responses((status = 200, body = [BinEntry]))
+)]
+pub async fn list_bins(State(state): State<Arc<AppState>>) -> Json<Vec<BinEntry>> {
+    Json(state.bins.entries().cloned().collect())
+}
+
+/// Looks up a PAN or a BIN of at least six digits.
+#[utoipa::path(
+    get,
+    path = "/reference/bins/{pan}",
+    tag = "reference",
+    params(("pan" = String, Path)),
+    responses(
+        (status = 200, body = BinEntry),
+        (status = 404, description = "No range contains the BIN", body = String),
+    )
+)]
+pub async fn lookup_bin(
+    State(state): State<Arc<AppState>>,
+    Path(pan): Path<String>,
+) -> Result<Json<BinEntry>, (StatusCode, String)> {
+    if pan.len() < 6 || !pan.bytes().all(|b| b.is_ascii_digit()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "expected a BIN or PAN of at least six digits".to_string(),
+        ));
+    }
+    state.bins.lookup(&pan).cloned().map(Json).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("no BIN range for {}", &pan[..6]),
+        )
+    })
+}
@@ -0,0 +1,318 @@
+use chrono::Utc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::de7;
+use crate::iso8583::{self, Iso8583Message};
+use crate::validation;
+use crate::{generate_rrn, AuthorizationRequest, CompletionRequest, ReversalRequest};
+
+// ============================================================================
+// Message Builders
+// ============================================================================
+//
+// For tests that generate traffic against the mock (in-process or over
+// HTTP). Every field is checked against the registry when the message is
+// built, and the fields each message gets anyway default sensibly:
+//
+//   DE7   transmission time, now (MMDDhhmmss UTC)
+//   DE11  STAN from a process-wide counter, 000001..999999
+//   DE37  RRN derived from DE11 (YDDDhh + STAN)
+//
+// `serde_json::to_value` gives the JSON the HTTP API takes; `wire::pack`
+// gives Mastercard network bytes.
+
+static NEXT_STAN: AtomicU32 = AtomicU32::new(0);
+
+/// The next STAN from a process-wide counter, skipping 000000.
+pub fn next_stan() -> String {
+    let stan = NEXT_STAN.fetch_add(1, Ordering::Relaxed) % 999_999 + 1;
+    format!("{:06}", stan)
+}
+
+/// DE7 for the current time.
+pub fn transmission_time() -> String {
+    de7::format(Utc::now())
+}
+
+/// `message` if it carries every DE its MTI makes mandatory by default, as
+/// the typed builders' messages must.
+fn complete(message: Iso8583Message) -> Result<Iso8583Message, String> {
+    let mandatory = validation::default_mandatory_fields(&message.mti);
+    validation::check_mandatory(&message, mandatory).map_err(|e| e.to_string())?;
+    Ok(message)
+}
+
+/// Builds any message type from DE numbers and values.
+#[derive(Debug, Clone)]
+pub struct MessageBuilder {
+    message: Iso8583Message,
+    error: Option<String>,
+}
+
+impl MessageBuilder {
+    pub fn new(mti: impl Into<String>) -> Self {
+        MessageBuilder {
+            message: Iso8583Message::new(mti),
+            error: None,
+        }
+    }
+
+    /// Sets a data element; unregistered DEs fail the build.
+    pub fn field(mut self, number: u8, value: impl Into<String>) -> Self {
+        if let Err(e) = self.message.set(number, value) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    /// Sets a data element only if it isn't set yet.
+    pub fn default_field(self, number: u8, value: impl Into<String>) -> Self {
+        if self.message.get(number).is_some() {
+            return self;
+        }
+        self.field(number, value)
+    }
+
+    /// Fills DE7/DE11/DE37 if absent and checks every field's format and
+    /// length.
+    pub fn build(self) -> Result<Iso8583Message, String> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let builder = self
+            .default_field(7, transmission_time())
+            .default_field(11, next_stan());
+        let stan = builder.message.get(11).unwrap().as_str().to_string();
+        let MessageBuilder { message, .. } =
+            builder.default_field(37, generate_rrn(&stan, Utc::now()));
+
+        if message.mti.len() != 4 || !message.mti.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("mti: {:?} is not four digits", message.mti));
+        }
+        for (number, value) in message.fields.iter() {
+            let definition = iso8583::field(number).expect("set() only accepts registered DEs");
+            definition
+                .check(value.as_str())
+                .map_err(|e| format!("de{}: {}", number, e))?;
+        }
+        Ok(message)
+    }
+}
+
+// ============================================================================
+// Typed Builders
+// ============================================================================
+
+macro_rules! setters {
+    ($($(#[$doc:meta])* $name:ident => $number:expr,)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(mut self, value: impl Into<String>) -> Self {
+                self.inner = self.inner.field($number, value);
+                self
+            }
+        )*
+
+        /// Sets any other registered data element.
+        pub fn field(mut self, number: u8, value: impl Into<String>) -> Self {
+            self.inner = self.inner.field(number, value);
+            self
+        }
+    };
+}
+
+/// 0100 builder. Only the PAN and amount are required; the rest defaults
+/// to a domestic US purchase: DE3 `000000`, DE18 `5999`, DE32 `000001`,
+/// DE49 `840`, and empty DE48 and DE61.
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequestBuilder {
+    inner: MessageBuilder,
+}
+
+impl AuthorizationRequest {
+    pub fn builder() -> AuthorizationRequestBuilder {
+        AuthorizationRequestBuilder {
+            inner: MessageBuilder::new("0100"),
+        }
+    }
+}
+
+impl AuthorizationRequestBuilder {
+    setters! {
+        pan => 2,
+        processing_code => 3,
+        /// In minor units.
+        amount => 4,
+        transmission_time => 7,
+        stan => 11,
+        merchant_type => 18,
+        pos_entry_mode => 22,
+        acquirer_id => 32,
+        track2 => 35,
+        rrn => 37,
+        /// 8 characters, space-padded.
+        terminal_id => 41,
+        /// 15 characters, space-padded.
+        card_acceptor_id => 42,
+        card_acceptor_name => 43,
+        additional_data => 48,
+        currency => 49,
+        pin_block => 52,
+        pos_data => 61,
+    }
+
+    pub fn build(self) -> Result<AuthorizationRequest, String> {
+        let message = self
+            .inner
+            .default_field(3, "000000")
+            .default_field(18, "5999")
+            .default_field(32, "000001")
+            .default_field(48, "")
+            .default_field(49, "840")
+            .default_field(61, "")
+            .build()?;
+        complete(message).map(AuthorizationRequest::from)
+    }
+}
+
+/// DE90 for a message referring to `mti` with `stan` and `de7` from
+/// acquirer `acquirer_id`: original MTI, STAN, transmission time, acquiring
+/// and forwarding institution IDs (zero-filled, 11 digits each).
+pub fn original_data_elements(mti: &str, stan: &str, de7: &str, acquirer_id: &str) -> String {
+    format!(
+        "{:0>4.4}{:0>6.6}{:0>10.10}{:0>11.11}{:011}",
+        mti, stan, de7, acquirer_id, 0
+    )
+}
+
+/// 0400 builder. Use `ReversalRequest::reversing` to reverse a known
+/// authorization; `builder()` defaults DE3, DE18, DE32, DE48, DE49 and DE61
+/// like an authorization, DE22 to `000` and DE39 to `00`.
+#[derive(Debug, Clone)]
+pub struct ReversalRequestBuilder {
+    inner: MessageBuilder,
+}
+
+impl ReversalRequest {
+    pub fn builder() -> ReversalRequestBuilder {
+        ReversalRequestBuilder {
+            inner: MessageBuilder::new("0400"),
+        }
+    }
+
+    /// A full reversal of `original`, with the same STAN and acquirer and
+    /// DE90 pointing at it.
+    pub fn reversing(original: &AuthorizationRequest) -> ReversalRequestBuilder {
+        let mut builder = Self::builder()
+            .pan(original.de2.as_str())
+            .processing_code(original.de3.as_str())
+            .amount(original.de4.as_str())
+            .stan(original.de11.as_str())
+            .merchant_type(original.de18.as_str())
+            .acquirer_id(original.de32.as_str())
+            .additional_data(original.de48.as_str())
+            .currency(original.de49.as_str())
+            .pos_data(original.de61.as_str())
+            .original_data_elements(original_data_elements(
+                &original.mti,
+                &original.de11,
+                &original.de7,
+                &original.de32,
+            ));
+        if let Some(de22) = &original.de22 {
+            builder = builder.pos_entry_mode(de22.as_str());
+        }
+        builder
+    }
+}
+
+impl ReversalRequestBuilder {
+    setters! {
+        pan => 2,
+        processing_code => 3,
+        /// In minor units.
+        amount => 4,
+        transmission_time => 7,
+        stan => 11,
+        merchant_type => 18,
+        pos_entry_mode => 22,
+        acquirer_id => 32,
+        response_code => 39,
+        additional_data => 48,
+        currency => 49,
+        pos_data => 61,
+        original_data_elements => 90,
+    }
+
+    pub fn build(self) -> Result<ReversalRequest, String> {
+        let message = self
+            .inner
+            .default_field(3, "000000")
+            .default_field(18, "5999")
+            .default_field(22, "000")
+            .default_field(32, "000001")
+            .default_field(39, "00")
+            .default_field(48, "")
+            .default_field(49, "840")
+            .default_field(61, "")
+            .build()?;
+        complete(message).map(ReversalRequest::from)
+    }
+}
+
+/// 0220 builder. Use `CompletionRequest::completing` to capture a known
+/// authorization; `builder()` defaults DE3, DE32 and DE49 like an
+/// authorization.
+#[derive(Debug, Clone)]
+pub struct CompletionRequestBuilder {
+    inner: MessageBuilder,
+}
+
+impl CompletionRequest {
+    pub fn builder() -> CompletionRequestBuilder {
+        CompletionRequestBuilder {
+            inner: MessageBuilder::new("0220"),
+        }
+    }
+
+    /// Captures `original` in full; override `amount` for a partial capture.
+    pub fn completing(original: &AuthorizationRequest) -> CompletionRequestBuilder {
+        Self::builder()
+            .pan(original.de2.as_str())
+            .processing_code(original.de3.as_str())
+            .amount(original.de4.as_str())
+            .stan(original.de11.as_str())
+            .acquirer_id(original.de32.as_str())
+            .currency(original.de49.as_str())
+            .original_data_elements(original_data_elements(
+                &original.mti,
+                &original.de11,
+                &original.de7,
+                &original.de32,
+            ))
+    }
+}
+
+impl CompletionRequestBuilder {
+    setters! {
+        pan => 2,
+        processing_code => 3,
+        /// In minor units.
+        amount => 4,
+        transmission_time => 7,
+        stan => 11,
+        acquirer_id => 32,
+        currency => 49,
+        original_data_elements => 90,
+    }
+
+    pub fn build(self) -> Result<CompletionRequest, String> {
+        let message = self
+            .inner
+            .default_field(3, "000000")
+            .default_field(32, "000001")
+            .default_field(49, "840")
+            .build()?;
+        complete(message).map(CompletionRequest::from)
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{Datelike, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+use crate::pan::BinRange;
+
+// ============================================================================
+// Test Card Generation
+// ============================================================================
+
+const MAX_CARDS: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct GenerateCards {
+    /// BIN prefixes (`"541333"`) or inclusive ranges of equal length
+    /// (`"510000-559999"`); each card picks one at random.
+    pub bins: Vec<String>,
+    #[serde(default = "default_count")]
+    pub count: usize,
+    #[serde(default = "default_pan_length")]
+    pub pan_length: usize,
+    /// Fixed seed for reproducible output.
+    pub seed: Option<u64>,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+fn default_pan_length() -> usize {
+    16
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GeneratedCard {
+    pub pan: String,
+    /// YYMM, as carried in DE14.
+    pub expiry: String,
+    pub cvc2: String,
+}
+
+/// Luhn check digit for a PAN without its last digit.
+pub fn luhn_check_digit(partial: &str) -> u32 {
+    let sum: u32 = partial
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = (b - b'0') as u32;
+            if i % 2 == 0 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+impl GenerateCards {
+    pub fn generate(&self) -> Result<Vec<GeneratedCard>, String> {
+        if self.count == 0 || self.count > MAX_CARDS {
+            return Err(format!("count must be within 1..={}", MAX_CARDS));
+        }
+        if !(12..=19).contains(&self.pan_length) {
+            return Err("pan_length must be within 12..=19".to_string());
+        }
+        if self.bins.is_empty() {
+            return Err("at least one BIN is required".to_string());
+        }
+        let bins = self
+            .bins
+            .iter()
+            .map(|spec| BinRange::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(bin) = bins.iter().find(|bin| bin.width() >= self.pan_length) {
+            return Err(format!(
+                "BIN of {} digits leaves no room in a {}-digit PAN",
+                bin.width(),
+                self.pan_length
+            ));
+        }
+
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let this_year = Utc::now().year();
+        let mut seen = HashSet::new();
+        let mut cards = Vec::with_capacity(self.count);
+        let mut attempts = 0;
+        while cards.len() < self.count {
+            attempts += 1;
+            if attempts > self.count * 20 {
+                return Err("BIN ranges too narrow for the requested count".to_string());
+            }
+
+            let mut pan = bins[rng.gen_range(0..bins.len())].pick(&mut rng);
+            while pan.len() < self.pan_length - 1 {
+                pan.push(char::from(b'0' + rng.gen_range(0..10u8)));
+            }
+            pan.push_str(&luhn_check_digit(&pan).to_string());
+            if !seen.insert(pan.clone()) {
+                continue;
+            }
+
+            let year = this_year + rng.gen_range(1..=5);
+            let month = rng.gen_range(1..=12);
+            cards.push(GeneratedCard {
+                pan,
+                expiry: format!("{:02}{:02}", year % 100, month),
+                cvc2: format!("{:03}", rng.gen_range(0..1000)),
+            });
+        }
+        Ok(cards)
+    }
+}
@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+
+use crate::codes::ResponseCode;
+use crate::country;
+use crate::currency::Currency;
+use crate::installments::InstallmentPolicy;
+use crate::masking;
+use crate::tokens::DeviceToken;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+// ============================================================================
+// Test Card Profiles (loaded from YAML)
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardDeck {
+    #[serde(default)]
+    pub defaults: AccountDefaults,
+    #[serde(default)]
+    pub cards: Vec<CardProfile>,
+    /// Wrong PIN attempts allowed before the card is locked (DE39=75).
+    #[serde(default = "default_pin_retry_limit")]
+    pub pin_retry_limit: u32,
+    /// MDES device tokens and their funding PANs.
+    #[serde(default)]
+    pub tokens: Vec<DeviceToken>,
+}
+
+fn default_pin_retry_limit() -> u32 {
+    3
+}
+
+/// Opening balances for cards that are not listed in the deck.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDefaults {
+    pub balance: i64,
+    pub credit_limit: i64,
+}
+
+impl Default for AccountDefaults {
+    fn default() -> Self {
+        AccountDefaults {
+            balance: 100_000_000,
+            credit_limit: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct CardProfile {
+    pub pan: String,
+    /// Opening balance in minor units.
+    pub balance: Option<i64>,
+    pub credit_limit: Option<i64>,
+    #[serde(default)]
+    pub status: CardStatus,
+    /// MCCs declined with 57 (transaction not permitted to cardholder).
+    #[serde(default)]
+    pub blocked_mccs: Vec<String>,
+    /// MCCs that are approved but logged for review.
+    #[serde(default)]
+    pub flagged_mccs: Vec<String>,
+    /// Issuing country; required for `domestic_only`.
+    pub home_country: Option<String>,
+    #[serde(default)]
+    pub allowed_countries: Vec<String>,
+    #[serde(default)]
+    pub blocked_countries: Vec<String>,
+    #[serde(default)]
+    pub domestic_only: bool,
+    /// Expected DE52 value. The mock compares it verbatim, so this may be a
+    /// clear PIN or a fixed PIN block.
+    pub pin: Option<String>,
+    /// Transaction currencies (numeric or alpha); empty allows all.
+    #[serde(default)]
+    pub allowed_currencies: Vec<String>,
+    /// Card expiry as YYMM (DE14 format).
+    pub expiry: Option<String>,
+    pub cvc2: Option<String>,
+    /// Installment plans the card accepts; none when absent.
+    pub installments: Option<InstallmentPolicy>,
+}
+
+impl CardProfile {
+    /// A `domestic_only` card needs a home country to be domestic to;
+    /// without one it would be allowed everywhere.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        let home = self.home_country.as_deref().and_then(country::normalize);
+        if self.domestic_only && home.is_none() {
+            return Err(format!(
+                "card {}: domestic_only needs a valid home_country, got {}",
+                masking::mask_pan(&self.pan),
+                self.home_country.as_deref().unwrap_or("none")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the card may be used at a merchant in `merchant_country`
+    /// (numeric). Transactions with an unknown country are not restricted.
+    pub fn country_permitted(&self, merchant_country: Option<&str>) -> bool {
+        let merchant_country = match merchant_country {
+            Some(country) => country,
+            None => return true,
+        };
+        let listed = |list: &[String]| {
+            list.iter()
+                .any(|c| country::normalize(c).as_deref() == Some(merchant_country))
+        };
+
+        if listed(&self.blocked_countries) {
+            return false;
+        }
+        if !self.allowed_countries.is_empty() && !listed(&self.allowed_countries) {
+            return false;
+        }
+        if self.domestic_only {
+            if let Some(home) = self.home_country.as_deref().and_then(country::normalize) {
+                return home == merchant_country;
+            }
+        }
+        true
+    }
+
+    pub fn currency_permitted(&self, currency: &Currency) -> bool {
+        self.allowed_currencies.is_empty()
+            || self
+                .allowed_currencies
+                .iter()
+                .any(|c| c == currency.numeric || c.eq_ignore_ascii_case(currency.alpha))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CardStatus {
+    #[default]
+    Active,
+    Lost,
+    Stolen,
+    Restricted,
+    BlockedFirstUse,
+}
+
+impl CardStatus {
+    /// The decline a card in this status produces, if any.
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        match self {
+            CardStatus::Active => None,
+            CardStatus::Lost => Some(ResponseCode::LostCard),
+            CardStatus::Stolen => Some(ResponseCode::StolenCard),
+            CardStatus::Restricted => Some(ResponseCode::RestrictedCard),
+            CardStatus::BlockedFirstUse => Some(ResponseCode::InvalidAccount),
+        }
+    }
+
+    pub fn response_message(&self) -> &'static str {
+        match self {
+            CardStatus::Active => "Card Active",
+            CardStatus::Lost => "Lost Card, Pick Up",
+            CardStatus::Stolen => "Stolen Card, Pick Up",
+            CardStatus::Restricted => "Restricted Card",
+            CardStatus::BlockedFirstUse => "Blocked, First Used",
+        }
+    }
+}
+
+impl CardDeck {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Self::from_yaml(&contents)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let deck: CardDeck =
+            serde_yaml::from_str(yaml).map_err(|e| format!("invalid card deck: {}", e))?;
+        deck.cards
+            .iter()
+            .try_for_each(CardProfile::check)
+            .map_err(|e| format!("invalid card deck: {}", e))?;
+        deck.tokens
+            .iter()
+            .try_for_each(DeviceToken::check)
+            .map_err(|e| format!("invalid card deck: {}", e))?;
+        Ok(deck)
+    }
+
+    pub fn empty() -> Self {
+        CardDeck {
+            defaults: AccountDefaults::default(),
+            cards: Vec::new(),
+            pin_retry_limit: default_pin_retry_limit(),
+            tokens: Vec::new(),
+        }
+    }
+
+    pub fn find(&self, pan: &str) -> Option<&CardProfile> {
+        self.cards.iter().find(|card| card.pan == pan)
+    }
+}
+
+// ============================================================================
+// Runtime Card Registry
+// ============================================================================
+
+/// Card profiles as modified at runtime through the admin API.
+pub struct CardRegistry {
+    profiles: Mutex<HashMap<String, CardProfile>>,
+    pin_failures: Mutex<HashMap<String, u32>>,
+    pin_retry_limit: AtomicU32,
+    fallback_counts: Mutex<HashMap<String, u32>>,
+}
+
+/// PIN retry and chip-fallback counters are kept per acquirer (DE32), so
+/// teams sharing a test card do not lock it for each other.
+pub fn counter_key(acquirer_id: &str, pan: &str) -> String {
+    format!("{}:{}", acquirer_id, pan)
+}
+
+/// Serializable copy of the registry, for snapshots.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    pub profiles: Vec<CardProfile>,
+    pub pin_failures: HashMap<String, u32>,
+    pub pin_retry_limit: u32,
+    pub fallback_counts: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PinCheck {
+    Correct,
+    /// DE39=55
+    Incorrect,
+    /// DE39=75
+    TriesExceeded,
+}
+
+impl CardRegistry {
+    pub fn new(deck: &CardDeck) -> Self {
+        CardRegistry {
+            profiles: Mutex::new(Self::index(deck)),
+            pin_failures: Mutex::new(HashMap::new()),
+            pin_retry_limit: AtomicU32::new(deck.pin_retry_limit),
+            fallback_counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn index(deck: &CardDeck) -> HashMap<String, CardProfile> {
+        deck.cards
+            .iter()
+            .map(|card| (card.pan.clone(), card.clone()))
+            .collect()
+    }
+
+    /// Replaces all profiles with the deck's, dropping runtime changes.
+    pub fn reload(&self, deck: &CardDeck) {
+        *self.profiles.lock().unwrap() = Self::index(deck);
+        self.pin_retry_limit
+            .store(deck.pin_retry_limit, Ordering::Relaxed);
+    }
+
+    /// Clears PIN retry and chip-fallback counters for every card.
+    pub fn reset_counters(&self) {
+        self.pin_failures.lock().unwrap().clear();
+        self.fallback_counts.lock().unwrap().clear();
+    }
+
+    /// Adds or replaces a profile without touching the rest of the registry.
+    pub fn upsert(&self, profile: CardProfile) {
+        self.profiles
+            .lock()
+            .unwrap()
+            .insert(profile.pan.clone(), profile);
+    }
+
+    pub fn profile(&self, pan: &str) -> Option<CardProfile> {
+        self.profiles.lock().unwrap().get(pan).cloned()
+    }
+
+    pub fn status(&self, pan: &str) -> CardStatus {
+        self.profiles
+            .lock()
+            .unwrap()
+            .get(pan)
+            .map(|profile| profile.status)
+            .unwrap_or_default()
+    }
+
+    pub fn set_status(&self, pan: &str, status: CardStatus) {
+        self.profiles
+            .lock()
+            .unwrap()
+            .entry(pan.to_string())
+            .or_insert_with(|| CardProfile {
+                pan: pan.to_string(),
+                ..CardProfile::default()
+            })
+            .status = status;
+    }
+
+    /// Checks `pin` against the expected PIN and updates the retry counter.
+    /// Once the limit is reached the card stays locked until reset.
+    pub fn verify_pin(&self, counter_key: &str, expected: &str, pin: &str) -> PinCheck {
+        let mut failures = self.pin_failures.lock().unwrap();
+        let count = failures.entry(counter_key.to_string()).or_insert(0);
+        let limit = self.pin_retry_limit.load(Ordering::Relaxed);
+
+        if *count >= limit {
+            return PinCheck::TriesExceeded;
+        }
+        if pin == expected {
+            *count = 0;
+            return PinCheck::Correct;
+        }
+
+        *count += 1;
+        if *count >= limit {
+            PinCheck::TriesExceeded
+        } else {
+            PinCheck::Incorrect
+        }
+    }
+
+    /// Clears the card's wrong-PIN counter for one acquirer, or for all of
+    /// them, returning the highest count cleared.
+    pub fn reset_pin_failures(&self, pan: &str, acquirer_id: Option<&str>) -> u32 {
+        let mut failures = self.pin_failures.lock().unwrap();
+        let mut previous = 0;
+        failures.retain(|key, count| {
+            let matches = key.rsplit_once(':').is_some_and(|(acquirer, card)| {
+                card == pan && acquirer_id.is_none_or(|id| id == acquirer)
+            });
+            if matches {
+                previous = previous.max(*count);
+            }
+            !matches
+        });
+        previous
+    }
+
+    /// Every profile, ordered by PAN.
+    pub fn profiles(&self) -> Vec<CardProfile> {
+        let mut profiles: Vec<CardProfile> =
+            self.profiles.lock().unwrap().values().cloned().collect();
+        profiles.sort_by(|a, b| a.pan.cmp(&b.pan));
+        profiles
+    }
+
+    pub fn snapshot(&self) -> RegistrySnapshot {
+        RegistrySnapshot {
+            profiles: self.profiles(),
+            pin_failures: self.pin_failures.lock().unwrap().clone(),
+            pin_retry_limit: self.pin_retry_limit.load(Ordering::Relaxed),
+            fallback_counts: self.fallback_counts.lock().unwrap().clone(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: RegistrySnapshot) {
+        *self.profiles.lock().unwrap() = snapshot
+            .profiles
+            .into_iter()
+            .map(|profile| (profile.pan.clone(), profile))
+            .collect();
+        *self.pin_failures.lock().unwrap() = snapshot.pin_failures;
+        self.pin_retry_limit
+            .store(snapshot.pin_retry_limit, Ordering::Relaxed);
+        *self.fallback_counts.lock().unwrap() = snapshot.fallback_counts;
+    }
+
+    /// Counts a chip-fallback transaction and returns the card's new total.
+    pub fn record_fallback(&self, counter_key: &str) -> u32 {
+        let mut counts = self.fallback_counts.lock().unwrap();
+        let count = counts.entry(counter_key.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+}
@@ -0,0 +1,443 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::IntoParams;
+
+use crate::card_generator::luhn_check_digit;
+use crate::iso8583::{def, FieldDefinition, FieldFormat, FieldLength};
+use crate::masking;
+use crate::wire;
+use crate::{AppState, Transaction, TransactionState};
+
+// ============================================================================
+// IPM Clearing Files
+// ============================================================================
+//
+// Captured dual-message authorizations as a Mastercard IPM clearing file
+// (T112), single-message transactions having settled already: a 1644
+// file header (DE24 `697`), one 1240 first presentment (DE24 `200`) per
+// capture, and a 1644 file trailer (DE24 `695`) carrying the message count
+// and the sum of DE4. Messages use the network framing of `wire` with the
+// IPM field layout; private data elements (PDS) travel in DE48 as 4-digit
+// tag, 3-digit length and data. Each message is preceded by a 4-byte
+// big-endian record length (RDW) and the file ends with a zero-length
+// record. The 1014-blocked variant cuts that stream into 1012-byte blocks
+// each followed by two 0x40 bytes, padding the last block with 0x40.
+
+use FieldFormat::{Alphanumeric as AN, AlphanumericSpecial as ANS, Numeric as N};
+use FieldLength::{Fixed, Lllvar, Llvar};
+
+/// The IPM layout of the data elements a clearing file uses; several
+/// differ from their authorization counterparts (DE12, DE22, DE26, DE43).
+static IPM_FIELDS: &[FieldDefinition] = &[
+    def(2, "Primary Account Number", N, Llvar(19)),
+    def(3, "Processing Code", N, Fixed(6)),
+    def(4, "Amount, Transaction", N, Fixed(12)),
+    def(12, "Date and Time, Local Transaction", N, Fixed(12)),
+    def(22, "Point of Service Data Code", AN, Fixed(12)),
+    def(24, "Function Code", N, Fixed(3)),
+    def(26, "Card Acceptor Business Code", N, Fixed(4)),
+    def(31, "Acquirer Reference Data", ANS, Llvar(23)),
+    def(37, "Retrieval Reference Number", ANS, Fixed(12)),
+    def(38, "Approval Code", ANS, Fixed(6)),
+    def(41, "Card Acceptor Terminal ID", ANS, Fixed(8)),
+    def(42, "Card Acceptor ID Code", ANS, Fixed(15)),
+    def(43, "Card Acceptor Name/Location", ANS, Llvar(99)),
+    def(48, "Additional Data", ANS, Lllvar(999)),
+    def(49, "Currency Code, Transaction", N, Fixed(3)),
+    def(71, "Message Number", N, Fixed(8)),
+    def(
+        94,
+        "Transaction Originator Institution ID Code",
+        ANS,
+        Llvar(11),
+    ),
+];
+
+fn ipm_field(number: u8) -> Option<&'static FieldDefinition> {
+    IPM_FIELDS.iter().find(|field| field.number == number)
+}
+
+pub const FUNCTION_FIRST_PRESENTMENT: &str = "200";
+pub const FUNCTION_FILE_HEADER: &str = "697";
+pub const FUNCTION_FILE_TRAILER: &str = "695";
+
+/// Block size of the 1014-blocked format, without its two pad bytes.
+const BLOCK_DATA: usize = 1012;
+const PAD: u8 = 0x40;
+
+static NEXT_FILE_SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// An IPM message: MTI and data elements keyed by DE number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpmMessage {
+    pub mti: String,
+    pub fields: BTreeMap<u8, String>,
+}
+
+impl IpmMessage {
+    fn new(mti: &str) -> Self {
+        IpmMessage {
+            mti: mti.to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    fn set(&mut self, number: u8, value: impl Into<String>) -> &mut Self {
+        self.fields.insert(number, value.into());
+        self
+    }
+
+    pub fn function_code(&self) -> Option<&str> {
+        self.fields.get(&24).map(String::as_str)
+    }
+
+    /// Private data elements from DE48, in file order.
+    pub fn pds(&self) -> Result<Vec<(String, String)>, String> {
+        parse_pds(self.fields.get(&48).map_or("", String::as_str))
+    }
+
+    pub fn pack(&self) -> Result<Vec<u8>, String> {
+        wire::pack_with(
+            &self.mti,
+            self.fields
+                .iter()
+                .map(|(number, value)| (*number, value.as_str())),
+            ipm_field,
+        )
+    }
+
+    pub fn unpack(bytes: &[u8]) -> Result<Self, String> {
+        let (mti, fields) = wire::unpack_with(bytes, ipm_field)?;
+        Ok(IpmMessage {
+            mti,
+            fields: fields.into_iter().collect(),
+        })
+    }
+}
+
+/// PDS as carried in DE48: `TTTTLLL` and the data, for each element.
+pub fn encode_pds(elements: &[(&str, String)]) -> String {
+    elements
+        .iter()
+        .map(|(tag, data)| format!("{}{:03}{}", tag, data.len(), data))
+        .collect()
+}
+
+pub fn parse_pds(mut raw: &str) -> Result<Vec<(String, String)>, String> {
+    let mut elements = Vec::new();
+    while !raw.is_empty() {
+        let header = raw
+            .get(..7)
+            .filter(|header| header.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| format!("bad PDS header in {:?}", raw))?;
+        let length: usize = header[4..].parse().expect("digits");
+        let data = raw
+            .get(7..7 + length)
+            .ok_or_else(|| format!("PDS {} is truncated", &header[..4]))?;
+        elements.push((header[..4].to_string(), data.to_string()));
+        raw = &raw[7 + length..];
+    }
+    Ok(elements)
+}
+
+/// PDS 0105: file type, file date, processor ID and sequence number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileId {
+    pub date: DateTime<Utc>,
+    pub processor_id: String,
+    pub sequence: u32,
+}
+
+impl FileId {
+    /// The next file of `date` (the simulator's day) for `processor_id`
+    /// (11 digits at most).
+    pub fn next(processor_id: &str, date: DateTime<Utc>) -> Self {
+        FileId {
+            date,
+            processor_id: processor_id.to_string(),
+            sequence: NEXT_FILE_SEQUENCE.fetch_add(1, Ordering::Relaxed) % 99_999 + 1,
+        }
+    }
+
+    /// 25 characters: `001` (outgoing clearing), YYMMDD, processor ID,
+    /// sequence.
+    pub fn pds0105(&self) -> String {
+        format!(
+            "001{}{:0>11.11}{:05}",
+            self.date.format("%y%m%d"),
+            self.processor_id,
+            self.sequence
+        )
+    }
+
+    pub fn file_name(&self) -> String {
+        format!(
+            "T112_{}_{:05}.ipm",
+            self.date.format("%y%m%d"),
+            self.sequence
+        )
+    }
+}
+
+/// DE22 for an authorization's DE22 entry mode: card-present chip,
+/// contactless, magstripe or keyed, or card-not-present e-commerce.
+fn pos_data_code(entry_mode: &str) -> String {
+    let input = match entry_mode.get(..2) {
+        Some("05") => 'C',
+        Some("07") => 'M',
+        Some("02") | Some("80") | Some("90") | Some("91") => 'B',
+        Some("81") => 'S',
+        Some("01") => '6',
+        _ => '0',
+    };
+    match input {
+        'S' => format!("610150{}00000", input),
+        _ => format!("D10101{}00000", input),
+    }
+}
+
+/// DE31: mixed use `2`, acquirer ID (last six digits), YDDD of the
+/// capture, an 11-digit sequence and a Luhn check digit.
+fn acquirer_reference(acquirer_id: &str, date: DateTime<Utc>, sequence: u32) -> String {
+    let acquirer = &acquirer_id[acquirer_id.len().saturating_sub(6)..];
+    let partial = format!(
+        "2{:0>6}{}{:011}",
+        acquirer,
+        &date.format("%y%j").to_string()[1..],
+        sequence
+    );
+    format!("{}{}", partial, luhn_check_digit(&partial))
+}
+
+/// The 1240 for a captured transaction; DE2 keeps its first six and last
+/// four digits unless `mask` is false, the rest becoming zeros.
+pub fn first_presentment(transaction: &Transaction, message_number: u32, mask: bool) -> IpmMessage {
+    let text = |key: &str| transaction.request_field(key);
+    let approval = transaction.approval_code();
+    let pan = if mask {
+        masking::mask_pan(&transaction.pan).replace('*', "0")
+    } else {
+        transaction.pan.clone()
+    };
+    let amount = transaction.settled_amount();
+    let local_time = transaction
+        .transmitted_at
+        .unwrap_or(transaction.recorded_at);
+
+    let mut message = IpmMessage::new("1240");
+    message
+        .set(2, pan)
+        .set(3, text("de3").unwrap_or("000000"))
+        .set(4, format!("{:0>12}", amount))
+        .set(12, local_time.format("%y%m%d%H%M%S").to_string())
+        .set(22, pos_data_code(text("de22").unwrap_or("")))
+        .set(24, FUNCTION_FIRST_PRESENTMENT)
+        .set(26, text("de18").unwrap_or("0000"))
+        .set(
+            31,
+            acquirer_reference(
+                &transaction.acquirer_id,
+                transaction.recorded_at,
+                message_number,
+            ),
+        )
+        .set(37, transaction.rrn.as_str())
+        .set(48, encode_pds(&[("0165", "M".to_string())]))
+        .set(49, text("de49").unwrap_or("840"))
+        .set(71, format!("{:08}", message_number))
+        .set(94, transaction.acquirer_id.as_str());
+    if let Some(approval) = approval {
+        message.set(38, approval);
+    }
+    for (number, key) in [(41, "de41"), (42, "de42"), (43, "de43")] {
+        if let Some(value) = text(key) {
+            message.set(number, value.trim_end());
+        }
+    }
+    message
+}
+
+/// Header, a first presentment per captured transaction (oldest first)
+/// and trailer.
+pub fn clearing_file(
+    file_id: &FileId,
+    transactions: &[Transaction],
+    mask: bool,
+) -> Vec<IpmMessage> {
+    let mut header = IpmMessage::new("1644");
+    header
+        .set(24, FUNCTION_FILE_HEADER)
+        .set(48, encode_pds(&[("0105", file_id.pds0105())]))
+        .set(71, format!("{:08}", 1));
+
+    let mut messages = vec![header];
+    let mut total: u64 = 0;
+    for transaction in transactions.iter().filter(|transaction| {
+        transaction.state == TransactionState::Captured && !transaction.is_single_message()
+    }) {
+        let presentment = first_presentment(transaction, messages.len() as u32 + 1, mask);
+        total = total.saturating_add(presentment.fields[&4].parse().unwrap_or(0));
+        messages.push(presentment);
+    }
+
+    let mut trailer = IpmMessage::new("1644");
+    trailer
+        .set(24, FUNCTION_FILE_TRAILER)
+        .set(
+            48,
+            encode_pds(&[
+                ("0105", file_id.pds0105()),
+                ("0301", format!("{:016}", total % 10u64.pow(16))),
+                ("0306", format!("{:08}", messages.len() + 1)),
+            ]),
+        )
+        .set(71, format!("{:08}", messages.len() + 1));
+    messages.push(trailer);
+    messages
+}
+
+/// RDW-framed records ending in a zero-length record, 1014-blocked when
+/// `blocked`.
+pub fn encode_file(messages: &[IpmMessage], blocked: bool) -> Result<Vec<u8>, String> {
+    let mut records = Vec::new();
+    for message in messages {
+        let packed = message.pack()?;
+        records.extend_from_slice(&(packed.len() as u32).to_be_bytes());
+        records.extend_from_slice(&packed);
+    }
+    records.extend_from_slice(&[0; 4]);
+    if !blocked {
+        return Ok(records);
+    }
+
+    let mut out = Vec::with_capacity(records.len() / BLOCK_DATA * 1014 + 1014);
+    for block in records.chunks(BLOCK_DATA) {
+        out.extend_from_slice(block);
+        out.resize(out.len() + BLOCK_DATA - block.len() + 2, PAD);
+    }
+    Ok(out)
+}
+
+/// Reads a file written by `encode_file`, blocked or not.
+pub fn decode_file(bytes: &[u8]) -> Result<Vec<IpmMessage>, String> {
+    let blocked = bytes.len().is_multiple_of(1014)
+        && bytes
+            .chunks(1014)
+            .all(|block| block[BLOCK_DATA..] == [PAD, PAD]);
+    let records: Vec<u8> = if blocked {
+        bytes
+            .chunks(1014)
+            .flat_map(|block| &block[..BLOCK_DATA])
+            .copied()
+            .collect()
+    } else {
+        bytes.to_vec()
+    };
+
+    let mut messages = Vec::new();
+    let mut rest = records.as_slice();
+    loop {
+        let length = rest
+            .get(..4)
+            .ok_or("file ends without a zero-length record")?;
+        let length = u32::from_be_bytes(length.try_into().expect("four bytes")) as usize;
+        if length == 0 {
+            return Ok(messages);
+        }
+        let record = rest
+            .get(4..4 + length)
+            .ok_or_else(|| format!("record {} is truncated", messages.len() + 1))?;
+        messages.push(
+            IpmMessage::unpack(record)
+                .map_err(|e| format!("record {}: {}", messages.len() + 1, e))?,
+        );
+        rest = &rest[4 + length..];
+    }
+}
+
+// ============================================================================
+// Endpoint
+// ============================================================================
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct ClearingQuery {
+    /// Only this acquirer's (DE32) captures; also used as the processor ID
+    /// in the file ID (PDS 0105).
+    pub acquirer_id: Option<String>,
+    /// 1014-blocked instead of plain RDW records.
+    #[serde(default)]
+    pub blocked: bool,
+    /// Zero the middle digits of DE2. `mask=false` only takes effect when
+    /// the server runs with `--log-sensitive`.
+    #[serde(default = "default_mask")]
+    pub mask: bool,
+}
+
+fn default_mask() -> bool {
+    true
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/clearing/ipm",
+    tag = "admin",
+    params(ClearingQuery),
+    responses(
+        (status = 200, description = "IPM clearing file of captured transactions", content_type = "application/octet-stream", body = Vec<u8>),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn ipm_file(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClearingQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let mut transactions: Vec<Transaction> = state
+        .authorized_transactions
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .into_iter()
+        .filter(|transaction| {
+            query
+                .acquirer_id
+                .as_deref()
+                .is_none_or(|id| transaction.acquirer_id == id)
+        })
+        .collect();
+    transactions.sort_by_key(|transaction| transaction.recorded_at);
+
+    let file_id = FileId::next(
+        query.acquirer_id.as_deref().unwrap_or(""),
+        state.clock.now(),
+    );
+    let mask = query.mask || !masking::log_sensitive();
+    let messages = clearing_file(&file_id, &transactions, mask);
+    let bytes = encode_file(&messages, query.blocked)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!(
+        "Admin: clearing file {} with {} presentments",
+        file_id.file_name(),
+        messages.len() - 2
+    );
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_id.file_name()),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
@@ -0,0 +1,128 @@
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::{
+    AuthorizationRequest, AuthorizationResponse, CompletionRequest, CompletionResponse,
+    ReversalRequest, ReversalResponse,
+};
+
+// ============================================================================
+// HTTP Client
+// ============================================================================
+//
+// Typed calls against a running mock, for downstream integration tests:
+//
+//   authorize()  POST /authorize   0100 -> 0110
+//   reverse()    POST /reversal    0400 -> 0410
+//   financial()  POST /completion  0220 -> 0230
+//
+// The mock only speaks JSON over HTTP; callers that need network bytes can
+// pack the same messages with `wire::pack`. A response with any DE39 is a
+// success here; errors are transport failures, non-200 answers (the error
+// envelope, an injected fault) and bodies that don't parse.
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Client {
+    /// A client for the mock at `base_url`, e.g. `http://localhost:3000`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()
+            .expect("reqwest client with default TLS");
+        Client {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// A client for a mock started with `server::spawn`.
+    pub fn local(addr: SocketAddr) -> Self {
+        Client::new(format!("http://{}", addr))
+    }
+
+    /// Replaces the per-request timeout (30s by default); raise it for
+    /// rules that inject long latencies.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("reqwest client with default TLS");
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn authorize(
+        &self,
+        request: &AuthorizationRequest,
+    ) -> Result<AuthorizationResponse, String> {
+        self.post("/authorize", request).await
+    }
+
+    pub async fn reverse(&self, request: &ReversalRequest) -> Result<ReversalResponse, String> {
+        self.post("/reversal", request).await
+    }
+
+    /// Sends a 0220 completion (financial advice) capturing an earlier
+    /// authorization.
+    pub async fn financial(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, String> {
+        self.post("/completion", request).await
+    }
+
+    async fn post<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        path: &str,
+        request: &Req,
+    ) -> Result<Resp, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http
+            .post(&url)
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| format!("POST {}: {}", url, e))?;
+
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("POST {}: reading body: {}", url, e))?;
+        if status != StatusCode::OK {
+            return Err(format!("POST {}: {}", url, describe_failure(status, &body)));
+        }
+        serde_json::from_slice(&body).map_err(|e| format!("POST {}: invalid response: {}", url, e))
+    }
+}
+
+/// `422 invalid_field: de4: ...` for the error envelope, the raw body
+/// otherwise.
+fn describe_failure(status: StatusCode, body: &[u8]) -> String {
+    if let Ok(Value::Object(envelope)) = serde_json::from_slice::<Value>(body) {
+        let error = &envelope["error"];
+        if let (Some(code), Some(message)) = (error["code"].as_str(), error["message"].as_str()) {
+            return format!("{} {}: {}", status.as_u16(), code, message);
+        }
+    }
+    format!(
+        "{} {}",
+        status.as_u16(),
+        String::from_utf8_lossy(body).trim()
+    )
+}
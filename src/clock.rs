@@ -0,0 +1,231 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::error::ApiJson;
+use crate::expiry;
+use crate::AppState;
+
+// ============================================================================
+// Virtual Clock (`/admin/clock`)
+// ============================================================================
+//
+// The date and time the mock's processing reads instead of the system's,
+// kept as an anchor (virtual instant at the last change, and the rate since)
+// so it moves between changes without ticking.
+
+/// Fastest clock rate: about eleven and a half days a second.
+pub const MAX_RATE: f64 = 1_000_000.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    real: DateTime<Utc>,
+    virtual_time: DateTime<Utc>,
+    /// Virtual seconds per real second; 0 while frozen.
+    rate: f64,
+}
+
+impl Anchor {
+    fn system() -> Self {
+        let now = Utc::now();
+        Anchor {
+            real: now,
+            virtual_time: now,
+            rate: 1.0,
+        }
+    }
+
+    /// The virtual time at `real`, held at chrono's limits rather than
+    /// overflowing them.
+    fn at(&self, real: DateTime<Utc>) -> DateTime<Utc> {
+        let elapsed = real - self.real;
+        let elapsed = if self.rate == 1.0 {
+            elapsed
+        } else {
+            let micros = elapsed.num_microseconds().unwrap_or(i64::MAX) as f64;
+            Duration::microseconds((micros * self.rate) as i64)
+        };
+        self.virtual_time
+            .checked_add_signed(elapsed)
+            .unwrap_or(if elapsed < Duration::zero() {
+                DateTime::<Utc>::MIN_UTC
+            } else {
+                DateTime::<Utc>::MAX_UTC
+            })
+    }
+}
+
+pub struct Clock {
+    anchor: RwLock<Anchor>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock {
+            anchor: RwLock::new(Anchor::system()),
+        }
+    }
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        self.anchor.read().unwrap().at(Utc::now())
+    }
+
+    /// Re-anchors at the current instant, then applies `change`.
+    fn change(&self, change: impl FnOnce(&mut Anchor)) {
+        let mut anchor = self.anchor.write().unwrap();
+        let real = Utc::now();
+        anchor.virtual_time = anchor.at(real);
+        anchor.real = real;
+        change(&mut anchor);
+    }
+
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.change(|anchor| anchor.virtual_time = time);
+    }
+
+    /// Fails, changing nothing, when the clock would pass chrono's limits.
+    pub fn advance(&self, by: Duration) -> Result<(), String> {
+        let mut result = Ok(());
+        self.change(|anchor| match anchor.virtual_time.checked_add_signed(by) {
+            Some(time) => anchor.virtual_time = time,
+            None => result = Err(format!("advancing by {} overflows the clock", by)),
+        });
+        result
+    }
+
+    /// 0 freezes the clock, 1 is real speed, at most `MAX_RATE`.
+    pub fn set_rate(&self, rate: f64) {
+        self.change(|anchor| anchor.rate = rate.clamp(0.0, MAX_RATE));
+    }
+
+    /// Back to the system clock.
+    pub fn reset(&self) {
+        *self.anchor.write().unwrap() = Anchor::system();
+    }
+
+    pub fn status(&self) -> ClockStatus {
+        let anchor = *self.anchor.read().unwrap();
+        let real = Utc::now();
+        let now = anchor.at(real);
+        ClockStatus {
+            now,
+            system_time: real,
+            offset_seconds: (now - real).num_seconds(),
+            rate: anchor.rate,
+            frozen: anchor.rate == 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ClockStatus {
+    /// The simulator's time.
+    pub now: DateTime<Utc>,
+    pub system_time: DateTime<Utc>,
+    /// `now` minus `system_time`.
+    pub offset_seconds: i64,
+    pub rate: f64,
+    pub frozen: bool,
+}
+
+/// Changes to the clock; fields left out are kept.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ClockChange {
+    /// Jumps to this instant.
+    pub time: Option<DateTime<Utc>>,
+    /// Jumps forward by a duration such as `90m` or `3d`.
+    pub advance: Option<String>,
+    /// Virtual seconds per real second, e.g. 3600 for an hour a second; at
+    /// most 1000000.
+    pub rate: Option<f64>,
+    /// `true` stops the clock; `false` restarts it at `rate` (default 1).
+    pub frozen: Option<bool>,
+}
+
+type AdminError = (StatusCode, String);
+
+fn invalid(message: impl Into<String>) -> AdminError {
+    (StatusCode::UNPROCESSABLE_ENTITY, message.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/clock",
+    tag = "admin",
+    responses((status = 200, body = ClockStatus)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_clock(State(state): State<Arc<AppState>>) -> Json<ClockStatus> {
+    Json(state.clock.status())
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/clock",
+    tag = "admin",
+    request_body = ClockChange,
+    responses(
+        (status = 200, body = ClockStatus),
+        (status = 422, description = "Conflicting or invalid changes", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn set_clock(
+    State(state): State<Arc<AppState>>,
+    ApiJson(change): ApiJson<ClockChange>,
+) -> Result<Json<ClockStatus>, AdminError> {
+    if change.time.is_some() && change.advance.is_some() {
+        return Err(invalid("give either time or advance, not both"));
+    }
+    let advance = change
+        .advance
+        .as_deref()
+        .map(expiry::parse_duration)
+        .transpose()
+        .map_err(invalid)?;
+    if let Some(rate) = change.rate {
+        if !(0.0..=MAX_RATE).contains(&rate) {
+            return Err(invalid(format!("rate must be within 0..={}", MAX_RATE)));
+        }
+    }
+    let rate = match (change.frozen, change.rate) {
+        (Some(true), Some(rate)) if rate != 0.0 => {
+            return Err(invalid("a frozen clock has rate 0"));
+        }
+        (Some(true), _) => Some(0.0),
+        (Some(false), rate) => Some(rate.unwrap_or(1.0)),
+        (None, rate) => rate,
+    };
+
+    if let Some(by) = advance {
+        state.clock.advance(by).map_err(invalid)?;
+    }
+    if let Some(time) = change.time {
+        state.clock.set(time);
+    }
+    if let Some(rate) = rate {
+        state.clock.set_rate(rate);
+    }
+    let status = state.clock.status();
+    info!(now = %status.now, rate = status.rate, "Admin: clock changed");
+    Ok(Json(status))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/clock",
+    tag = "admin",
+    responses((status = 200, description = "Back on the system clock", body = ClockStatus)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn reset_clock(State(state): State<Arc<AppState>>) -> Json<ClockStatus> {
+    state.clock.reset();
+    info!("Admin: clock reset to system time");
+    Json(state.clock.status())
+}
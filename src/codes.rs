@@ -0,0 +1,297 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+// ============================================================================
+// Code Catalogs
+// ============================================================================
+//
+// DE39 response codes and DE3 processing codes after the Mastercard
+// Customer Interface Specification. Both are carried as their two-digit
+// codes in JSON and YAML; a code outside the catalog is an error rather
+// than a string that no rule will ever match.
+
+/// Defines a catalog enum with its codes, descriptions, `ALL`, `Display`
+/// (the code) and `FromStr`.
+macro_rules! catalog {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident ($kind:literal) {
+            $($variant:ident = $code:literal, $description:literal;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn code(self) -> &'static str {
+                match self {
+                    $($name::$variant => $code,)*
+                }
+            }
+
+            pub fn description(self) -> &'static str {
+                match self {
+                    $($name::$variant => $description,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.code())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(code: &str) -> Result<Self, String> {
+                $name::ALL
+                    .iter()
+                    .copied()
+                    .find(|value| value.code() == code)
+                    .ok_or_else(|| format!("unknown {} {:?}", $kind, code))
+            }
+        }
+    };
+}
+
+catalog! {
+    /// DE39.
+    pub enum ResponseCode ("response code") {
+        Approved = "00", "Approved or completed successfully";
+        ReferToIssuer = "01", "Refer to card issuer";
+        InvalidMerchant = "03", "Invalid merchant";
+        CaptureCard = "04", "Capture card";
+        DoNotHonor = "05", "Do not honor";
+        HonorWithId = "08", "Honor with ID";
+        PartialApproval = "10", "Partial approval";
+        InvalidTransaction = "12", "Invalid transaction";
+        InvalidAmount = "13", "Invalid amount";
+        InvalidCardNumber = "14", "Invalid card number";
+        InvalidIssuer = "15", "Invalid issuer";
+        FormatError = "30", "Format error";
+        LostCard = "41", "Lost card";
+        StolenCard = "43", "Stolen card";
+        InsufficientFunds = "51", "Insufficient funds/over credit limit";
+        ExpiredCard = "54", "Expired card";
+        InvalidPin = "55", "Invalid PIN";
+        NotPermittedToCardholder = "57", "Transaction not permitted to issuer/cardholder";
+        NotPermittedToTerminal = "58", "Transaction not permitted to acquirer/terminal";
+        SuspectedFraud = "59", "Suspected fraud";
+        ExceedsAmountLimit = "61", "Exceeds withdrawal amount limit";
+        RestrictedCard = "62", "Restricted card";
+        SecurityViolation = "63", "Security violation";
+        AuthenticationRequired = "65", "Exceeds withdrawal count limit/authentication required";
+        ContactIssuer = "70", "Contact card issuer";
+        PinNotChanged = "71", "PIN not changed";
+        PinTriesExceeded = "75", "Allowable number of PIN tries exceeded";
+        InvalidToAccount = "76", "Invalid/nonexistent \"To Account\" specified";
+        InvalidFromAccount = "77", "Invalid/nonexistent \"From Account\" specified";
+        InvalidAccount = "78", "Invalid/nonexistent account specified";
+        LifeCycle = "79", "Life cycle";
+        DomesticDebitNotAllowed = "81", "Domestic debit transaction not allowed";
+        Policy = "82", "Policy";
+        FraudSecurity = "83", "Fraud/security";
+        InvalidAuthorizationLifeCycle = "84", "Invalid authorization life cycle";
+        NotDeclined = "85", "Not declined";
+        PinValidationNotPossible = "86", "PIN validation not possible";
+        PurchaseAmountOnly = "87", "Purchase amount only, no cash back allowed";
+        CryptographicFailure = "88", "Cryptographic failure";
+        UnacceptablePin = "89", "Unacceptable PIN, transaction declined, retry";
+        IssuerUnavailable = "91", "Authorization system or issuer system inoperative";
+        UnableToRoute = "92", "Unable to route transaction";
+        DuplicateTransmission = "94", "Duplicate transmission detected";
+        SystemError = "96", "System error";
+    }
+}
+
+impl ResponseCode {
+    /// Codes that result in a stored (approved) authorization.
+    pub fn is_approval(self) -> bool {
+        use ResponseCode::*;
+        match self {
+            Approved | HonorWithId | PartialApproval | NotDeclined | PurchaseAmountOnly => true,
+            ReferToIssuer
+            | InvalidMerchant
+            | CaptureCard
+            | DoNotHonor
+            | InvalidTransaction
+            | InvalidAmount
+            | InvalidCardNumber
+            | InvalidIssuer
+            | FormatError
+            | LostCard
+            | StolenCard
+            | InsufficientFunds
+            | ExpiredCard
+            | InvalidPin
+            | NotPermittedToCardholder
+            | NotPermittedToTerminal
+            | SuspectedFraud
+            | ExceedsAmountLimit
+            | RestrictedCard
+            | SecurityViolation
+            | AuthenticationRequired
+            | ContactIssuer
+            | PinNotChanged
+            | PinTriesExceeded
+            | InvalidToAccount
+            | InvalidFromAccount
+            | InvalidAccount
+            | LifeCycle
+            | DomesticDebitNotAllowed
+            | Policy
+            | FraudSecurity
+            | InvalidAuthorizationLifeCycle
+            | PinValidationNotPossible
+            | CryptographicFailure
+            | UnacceptablePin
+            | IssuerUnavailable
+            | UnableToRoute
+            | DuplicateTransmission
+            | SystemError => false,
+        }
+    }
+}
+
+catalog! {
+    /// DE3 positions 1-2.
+    pub enum TransactionType ("transaction type") {
+        Purchase = "00", "Purchase";
+        Withdrawal = "01", "Withdrawal";
+        DebitAdjustment = "02", "Debit adjustment";
+        PurchaseWithCashBack = "09", "Purchase with cash back";
+        AccountFunding = "10", "Account funding";
+        CashDisbursement = "17", "Cash disbursement";
+        ScripIssue = "18", "Scrip issue";
+        Refund = "20", "Purchase return/refund";
+        Deposit = "21", "Deposit";
+        CreditAdjustment = "22", "Credit adjustment";
+        PaymentTransaction = "28", "Payment transaction";
+        BalanceInquiry = "30", "Balance inquiry";
+        AccountTransfer = "40", "Account transfer";
+        Payment = "50", "Payment";
+        PinUnblock = "91", "PIN unblock";
+        PinChange = "92", "PIN change";
+    }
+}
+
+impl TransactionType {
+    /// Transactions that move money to the cardholder (refunds, OCTs).
+    pub fn is_credit(self) -> bool {
+        use TransactionType::*;
+        match self {
+            Refund | PaymentTransaction => true,
+            Purchase | Withdrawal | DebitAdjustment | PurchaseWithCashBack | AccountFunding
+            | CashDisbursement | ScripIssue | Deposit | CreditAdjustment | BalanceInquiry
+            | AccountTransfer | Payment | PinUnblock | PinChange => false,
+        }
+    }
+}
+
+catalog! {
+    /// DE3 positions 3-4 (from) and 5-6 (to).
+    pub enum AccountType ("account type") {
+        Default = "00", "Default account";
+        Savings = "10", "Savings account";
+        Checking = "20", "Checking account";
+        Credit = "30", "Credit card account";
+        CreditLine = "38", "Credit line account";
+        Corporate = "39", "Corporate";
+        Universal = "40", "Universal account";
+        MoneyMarket = "50", "Money market investment account";
+        Ira = "58", "IRA investment account";
+        RevolvingLoan = "90", "Revolving loan account";
+        InstallmentLoan = "91", "Installment loan account";
+        RealEstateLoan = "92", "Real estate loan account";
+    }
+}
+
+/// DE3: transaction type, then the from and to account types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessingCode {
+    pub transaction_type: TransactionType,
+    pub from_account: AccountType,
+    pub to_account: AccountType,
+}
+
+impl ProcessingCode {
+    pub fn is_credit(&self) -> bool {
+        self.transaction_type.is_credit()
+    }
+}
+
+impl fmt::Display for ProcessingCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.transaction_type, self.from_account, self.to_account
+        )
+    }
+}
+
+impl FromStr for ProcessingCode {
+    type Err = String;
+
+    /// Exactly six digits: a short code is ambiguous (`20` could be a
+    /// refund or `000020`), so it isn't zero-filled.
+    fn from_str(code: &str) -> Result<Self, String> {
+        if code.len() != 6 || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("processing code {:?} is not six digits", code));
+        }
+        Ok(ProcessingCode {
+            transaction_type: code[0..2].parse()?,
+            from_account: code[2..4].parse()?,
+            to_account: code[4..6].parse()?,
+        })
+    }
+}
+
+// ============================================================================
+// Serialization
+// ============================================================================
+
+macro_rules! as_code {
+    ($($name:ident),*) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let code = String::deserialize(deserializer)?;
+                    code.parse().map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+as_code!(ResponseCode, TransactionType, AccountType, ProcessingCode);
+
+/// Documented as the two-digit code, listing the catalog.
+impl PartialSchema for ResponseCode {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .description(Some("DE39 response code"))
+            .enum_values(Some(ResponseCode::ALL.iter().map(|code| code.code())))
+            .into()
+    }
+}
+
+impl ToSchema for ResponseCode {}
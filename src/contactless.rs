@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::amount::Amount;
+use crate::currency::Currency;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Contactless Transactions
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactlessConfig {
+    /// No-CVM limit in minor units, keyed by currency (numeric or alpha).
+    /// Above it, contactless without a PIN is soft-declined with 65.
+    #[serde(default)]
+    pub cvm_limits: HashMap<String, u64>,
+}
+
+/// DE22 PAN entry mode 07 (contactless chip) or 91 (contactless magstripe).
+pub fn is_contactless(message: &impl FieldSource) -> bool {
+    matches!(
+        message.field("de22").and_then(|d| d.get(0..2)),
+        Some("07") | Some("91")
+    )
+}
+
+impl ContactlessConfig {
+    pub fn cvm_limit(&self, currency: &Currency) -> Option<u64> {
+        self.cvm_limits
+            .iter()
+            .find(|(code, _)| {
+                *code == currency.numeric || code.eq_ignore_ascii_case(currency.alpha)
+            })
+            .map(|(_, limit)| *limit)
+    }
+
+    /// Whether the amount needs a cardholder verification that was not given.
+    pub fn requires_step_up(&self, amount: &Amount, pin_present: bool) -> bool {
+        let units = u64::try_from(amount.minor_units).unwrap_or(0);
+        !pin_present
+            && self
+                .cvm_limit(amount.currency)
+                .is_some_and(|limit| units > limit)
+    }
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Span;
+
+use crate::de48::De48;
+
+// ============================================================================
+// Correlation IDs
+// ============================================================================
+//
+// A caller can tag a message with an ID that ties it to the rest of its test
+// trace, as an `X-Correlation-Id` or `X-Request-Id` header or, for clients
+// that only speak ISO 8583, in DE48 subelement 63. The ID is recorded on the
+// handler span, stored with the transaction and echoed back.
+
+pub const CORRELATION_SUBELEMENT: &str = "63";
+
+static HEADERS: [HeaderName; 2] = [
+    HeaderName::from_static("x-correlation-id"),
+    HeaderName::from_static("x-request-id"),
+];
+
+/// Longest ID accepted; anything beyond is truncated.
+const MAX_LENGTH: usize = 128;
+
+fn from_headers(headers: &HeaderMap) -> Option<(&HeaderName, String)> {
+    HEADERS.iter().find_map(|name| {
+        let value = headers.get(name)?.to_str().ok()?.trim();
+        (!value.is_empty()).then(|| (name, value.chars().take(MAX_LENGTH).collect()))
+    })
+}
+
+/// The correlation ID from the request headers alone.
+pub fn header_id(headers: &HeaderMap) -> Option<String> {
+    from_headers(headers).map(|(_, id)| id)
+}
+
+/// The message's correlation ID, headers first, then DE48 SE63. Records it
+/// on the current span.
+pub fn attach(headers: &HeaderMap, de48: Option<&str>) -> Option<String> {
+    let id = header_id(headers).or_else(|| {
+        let parsed = De48::parse(de48?).ok()?;
+        let id = parsed.get(CORRELATION_SUBELEMENT)?.trim();
+        (!id.is_empty()).then(|| id.to_string())
+    });
+    if let Some(id) = &id {
+        Span::current().record("correlation_id", id.as_str());
+    }
+    id
+}
+
+/// Echoes the correlation header back under the name it arrived with. IDs
+/// carried in DE48 come back in the echoed DE48.
+pub async fn echo_header(request: Request, next: Next) -> Response {
+    let echoed = from_headers(request.headers())
+        .and_then(|(name, id)| Some((name.clone(), id.parse().ok()?)));
+    let mut response = next.run(request).await;
+    if let Some((name, value)) = echoed {
+        response.headers_mut().insert(name, value);
+    }
+    response
+}
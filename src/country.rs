@@ -0,0 +1,78 @@
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Merchant Country
+// ============================================================================
+//
+// Countries are compared as ISO 3166 numeric codes. DE61 subfield 13 already
+// carries the numeric code; the last three characters of DE43 carry the
+// alpha-3 code and are translated through the table below.
+
+const ALPHA3_TO_NUMERIC: &[(&str, &str)] = &[
+    ("ARE", "784"),
+    ("ARG", "032"),
+    ("AUS", "036"),
+    ("AUT", "040"),
+    ("BEL", "056"),
+    ("BRA", "076"),
+    ("CAN", "124"),
+    ("CHE", "756"),
+    ("CHN", "156"),
+    ("DEU", "276"),
+    ("DNK", "208"),
+    ("ESP", "724"),
+    ("FIN", "246"),
+    ("FRA", "250"),
+    ("GBR", "826"),
+    ("HKG", "344"),
+    ("IND", "356"),
+    ("IRL", "372"),
+    ("IRN", "364"),
+    ("ITA", "380"),
+    ("JPN", "392"),
+    ("KOR", "410"),
+    ("KWT", "414"),
+    ("MEX", "484"),
+    ("NLD", "528"),
+    ("NOR", "578"),
+    ("PRK", "408"),
+    ("POL", "616"),
+    ("PRT", "620"),
+    ("RUS", "643"),
+    ("SAU", "682"),
+    ("SGP", "702"),
+    ("SWE", "752"),
+    ("TUR", "792"),
+    ("USA", "840"),
+    ("ZAF", "710"),
+];
+
+/// Normalises an alpha-3 or numeric country code to numeric.
+pub fn normalize(code: &str) -> Option<String> {
+    let code = code.trim();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_digit()) {
+        return Some(code.to_string());
+    }
+    let upper = code.to_ascii_uppercase();
+    ALPHA3_TO_NUMERIC
+        .iter()
+        .find(|(alpha, _)| *alpha == upper)
+        .map(|(_, numeric)| numeric.to_string())
+}
+
+/// Merchant country from DE61 subfield 13 (positions 14-16), else DE43.
+pub fn merchant_country(message: &impl FieldSource) -> Option<String> {
+    let from_de61 = message
+        .field("de61")
+        .and_then(|de61| de61.get(13..16))
+        .filter(|code| code.chars().all(|c| c.is_ascii_digit()));
+    if let Some(code) = from_de61 {
+        return Some(code.to_string());
+    }
+
+    message
+        .field("de43")
+        .map(str::trim_end)
+        .and_then(|de43| de43.get(de43.len().checked_sub(3)?..))
+        .and_then(normalize)
+}
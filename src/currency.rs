@@ -0,0 +1,89 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// ============================================================================
+// ISO 4217 Currency Table
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Currency {
+    pub alpha: &'static str,
+    pub numeric: &'static str,
+    /// Number of minor-unit digits (2 for USD, 0 for JPY, 3 for KWD).
+    pub exponent: u32,
+    pub name: &'static str,
+}
+
+const fn c(
+    alpha: &'static str,
+    numeric: &'static str,
+    exponent: u32,
+    name: &'static str,
+) -> Currency {
+    Currency {
+        alpha,
+        numeric,
+        exponent,
+        name,
+    }
+}
+
+pub const CURRENCIES: &[Currency] = &[
+    c("AED", "784", 2, "UAE Dirham"),
+    c("ARS", "032", 2, "Argentine Peso"),
+    c("AUD", "036", 2, "Australian Dollar"),
+    c("BHD", "048", 3, "Bahraini Dinar"),
+    c("BRL", "986", 2, "Brazilian Real"),
+    c("CAD", "124", 2, "Canadian Dollar"),
+    c("CHF", "756", 2, "Swiss Franc"),
+    c("CLP", "152", 0, "Chilean Peso"),
+    c("CNY", "156", 2, "Yuan Renminbi"),
+    c("COP", "170", 2, "Colombian Peso"),
+    c("CZK", "203", 2, "Czech Koruna"),
+    c("DKK", "208", 2, "Danish Krone"),
+    c("EGP", "818", 2, "Egyptian Pound"),
+    c("EUR", "978", 2, "Euro"),
+    c("GBP", "826", 2, "Pound Sterling"),
+    c("HKD", "344", 2, "Hong Kong Dollar"),
+    c("HUF", "348", 2, "Forint"),
+    c("IDR", "360", 2, "Rupiah"),
+    c("ILS", "376", 2, "New Israeli Sheqel"),
+    c("INR", "356", 2, "Indian Rupee"),
+    c("ISK", "352", 0, "Iceland Krona"),
+    c("JOD", "400", 3, "Jordanian Dinar"),
+    c("JPY", "392", 0, "Yen"),
+    c("KES", "404", 2, "Kenyan Shilling"),
+    c("KRW", "410", 0, "Won"),
+    c("KWD", "414", 3, "Kuwaiti Dinar"),
+    c("MXN", "484", 2, "Mexican Peso"),
+    c("MYR", "458", 2, "Malaysian Ringgit"),
+    c("NGN", "566", 2, "Naira"),
+    c("NOK", "578", 2, "Norwegian Krone"),
+    c("NZD", "554", 2, "New Zealand Dollar"),
+    c("OMR", "512", 3, "Rial Omani"),
+    c("PEN", "604", 2, "Sol"),
+    c("PHP", "608", 2, "Philippine Peso"),
+    c("PKR", "586", 2, "Pakistan Rupee"),
+    c("PLN", "985", 2, "Zloty"),
+    c("QAR", "634", 2, "Qatari Rial"),
+    c("RON", "946", 2, "Romanian Leu"),
+    c("SAR", "682", 2, "Saudi Riyal"),
+    c("SEK", "752", 2, "Swedish Krona"),
+    c("SGD", "702", 2, "Singapore Dollar"),
+    c("THB", "764", 2, "Baht"),
+    c("TND", "788", 3, "Tunisian Dinar"),
+    c("TRY", "949", 2, "Turkish Lira"),
+    c("TWD", "901", 2, "New Taiwan Dollar"),
+    c("UAH", "980", 2, "Hryvnia"),
+    c("USD", "840", 2, "US Dollar"),
+    c("VND", "704", 0, "Dong"),
+    c("ZAR", "710", 2, "Rand"),
+];
+
+/// Looks up a currency by numeric (DE49) or alpha code.
+pub fn lookup(code: &str) -> Option<&'static Currency> {
+    let code = code.trim();
+    CURRENCIES
+        .iter()
+        .find(|currency| currency.numeric == code || currency.alpha.eq_ignore_ascii_case(code))
+}
@@ -0,0 +1,175 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::settlement::{self, SettlementReport, SettlementTotals};
+use crate::webhooks::EventKind;
+use crate::AppState;
+
+// ============================================================================
+// Settlement Cutover
+// ============================================================================
+//
+// With SETTLEMENT_CUTOVER, a scheduler on the simulator's clock closes the
+// open business day at the cutover, freezing its totals, and rolls DE15.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parses `HH:MM` (or `HH:MM:SS`).
+pub fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| format!("expected HH:MM, got {:?}", value))
+}
+
+/// The business day open at `now`: today until the cutover, then tomorrow.
+fn business_day_at(cutover: NaiveTime, now: DateTime<Utc>) -> NaiveDate {
+    let today = now.date_naive();
+    if now.time() < cutover {
+        today
+    } else {
+        today.succ_opt().unwrap_or(today)
+    }
+}
+
+/// DE15, settlement date: MMDD.
+pub fn de15(date: NaiveDate) -> String {
+    date.format("%m%d").to_string()
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CutoverSummary {
+    #[schema(value_type = String, format = Date)]
+    pub closed_date: NaiveDate,
+    pub closed_at: DateTime<Utc>,
+    /// The business day now open, and its DE15.
+    #[schema(value_type = String, format = Date)]
+    pub open_date: NaiveDate,
+    pub de15: String,
+    /// The closed day's frozen totals, by acquirer and currency.
+    pub totals: Vec<SettlementTotals>,
+}
+
+struct Calendar {
+    open: NaiveDate,
+    closed: BTreeMap<NaiveDate, SettlementReport>,
+}
+
+pub struct SettlementCalendar {
+    cutover: Option<NaiveTime>,
+    calendar: Mutex<Calendar>,
+}
+
+impl SettlementCalendar {
+    pub fn new(cutover: Option<NaiveTime>, now: DateTime<Utc>) -> Self {
+        SettlementCalendar {
+            cutover,
+            calendar: Mutex::new(Calendar {
+                open: cutover.map_or(now.date_naive(), |cutover| business_day_at(cutover, now)),
+                closed: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// The open business day; `None` without SETTLEMENT_CUTOVER.
+    pub fn open_day(&self) -> Option<NaiveDate> {
+        self.cutover?;
+        Some(self.calendar.lock().unwrap().open)
+    }
+
+    /// Whether the open business day's cutover has passed at `now`.
+    fn due(&self, now: DateTime<Utc>) -> bool {
+        let Some(cutover) = self.cutover else {
+            return false;
+        };
+        let open = self.calendar.lock().unwrap().open;
+        open.and_time(cutover).and_utc() <= now
+    }
+
+    /// The frozen report of a closed business day.
+    pub fn closed_report(&self, date: NaiveDate) -> Option<SettlementReport> {
+        self.calendar.lock().unwrap().closed.get(&date).cloned()
+    }
+}
+
+/// Closes the open business day, opens the next and emits the event.
+pub fn run_cutover(state: &AppState) -> Result<CutoverSummary, String> {
+    let transactions = state.authorized_transactions.list()?;
+    let rules = state.rules();
+    let interchange = rules.interchange.as_ref().map(|table| (table, &state.bins));
+
+    let summary = {
+        let mut calendar = state.settlement.calendar.lock().unwrap();
+        let closed_date = calendar.open;
+        let mut report = settlement::summarize(closed_date, &transactions, interchange);
+        let closed_at = state.clock.now();
+        report.closed_at = Some(closed_at);
+        let open_date = closed_date.succ_opt().unwrap_or(closed_date);
+        calendar.open = open_date;
+        calendar.closed.insert(closed_date, report.clone());
+        CutoverSummary {
+            closed_date,
+            closed_at,
+            open_date,
+            de15: de15(open_date),
+            totals: report.totals,
+        }
+    };
+    info!(
+        closed = %summary.closed_date,
+        open = %summary.open_date,
+        "settlement cutover"
+    );
+    state.publish(EventKind::SettlementCutover, &summary);
+    Ok(summary)
+}
+
+/// Runs a cutover every day at SETTLEMENT_CUTOVER.
+pub fn spawn_scheduler(state: Arc<AppState>) {
+    if state.settlement.cutover.is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            while state.settlement.due(state.clock.now()) {
+                if let Err(e) = run_cutover(&state) {
+                    warn!("Settlement cutover failed: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/settlement/cutover",
+    tag = "admin",
+    responses(
+        (status = 200, body = CutoverSummary),
+        (status = 409, description = "SETTLEMENT_CUTOVER is not set", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn force_cutover(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CutoverSummary>, (StatusCode, String)> {
+    if state.settlement.cutover.is_none() {
+        return Err((
+            StatusCode::CONFLICT,
+            "no settlement calendar (set SETTLEMENT_CUTOVER)".to_string(),
+        ));
+    }
+    info!("Admin: forcing settlement cutover");
+    run_cutover(&state)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
@@ -0,0 +1,32 @@
+use axum::response::Html;
+
+// ============================================================================
+// Web Dashboard (`/dashboard`)
+// ============================================================================
+//
+// A single page, compiled into the binary, for QA staff who drive the
+// simulator from a browser rather than curl. It only calls the public API:
+//
+//   live stats          /monitor (server-sent events)
+//   recent transactions /transactions, refreshed on every /ws/transactions
+//                       event
+//   test authorization  POST /authorize
+//   card profiles       /admin/cards and /admin/cards/{pan}/status
+//   active rules        /admin/rules
+//   fault toggles       /admin/faults
+//
+// With ADMIN_API_KEYS set, the admin panels need a key entered on the page;
+// it is kept in the browser's local storage.
+
+const PAGE: &str = include_str!("../assets/dashboard.html");
+
+/// The dashboard page.
+#[utoipa::path(
+    get,
+    path = "/dashboard",
+    tag = "health",
+    responses((status = 200, description = "HTML dashboard", content_type = "text/html", body = String))
+)]
+pub async fn dashboard() -> Html<&'static str> {
+    Html(PAGE)
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::amount::{self, Amount};
+use crate::codes::ResponseCode;
+use crate::currency::{self, Currency};
+use crate::validation;
+
+// ============================================================================
+// Dynamic Currency Conversion (DCC)
+// ============================================================================
+//
+// When a merchant offers the cardholder their home currency, the
+// authorization carries the converted amount in DE4 / DE49, the currency
+// the cardholder is billed in, and the original amount and currency in
+// DE6 / DE51. One without the other is a format error.
+//
+// With a `dcc` section in the rules, the markup the merchant applied is
+// checked against the table's rate for the pair: DE4 may exceed DE6 at
+// that rate by at most `max_markup_percent`. A pair without a rate, or a
+// larger markup, is declined with 13. Without the section DCC amounts are
+// accepted as sent. Both amount sets are echoed; a partial approval scales
+// DE6 with DE4.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DccConfig {
+    pub max_markup_percent: f64,
+    #[serde(default)]
+    pub rates: Vec<FxRate>,
+}
+
+/// Units of `to` per unit of `from`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRate {
+    /// Original currency (DE51), alpha or numeric; numeric once loaded.
+    pub from: String,
+    /// Billing currency (DE49), alpha or numeric; numeric once loaded.
+    pub to: String,
+    pub rate: f64,
+}
+
+impl DccConfig {
+    /// Checks the rates, normalising their currencies.
+    pub(crate) fn check(&mut self) -> Result<(), String> {
+        for rate in &mut self.rates {
+            for code in [&mut rate.from, &mut rate.to] {
+                *code = currency::lookup(code)
+                    .ok_or_else(|| format!("dcc: unknown currency {:?}", code))?
+                    .numeric
+                    .to_string();
+            }
+            if rate.rate <= 0.0 {
+                return Err(format!(
+                    "dcc: rate {}/{} must be positive",
+                    rate.from, rate.to
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        self.rates
+            .iter()
+            .find(|rate| rate.from == from && rate.to == to)
+            .map(|rate| rate.rate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DccIssue {
+    /// The DE missing from the pair (6 or 51); a format error.
+    Missing(u8),
+    /// DE39=13
+    NoRate,
+    /// DE39=13
+    MarkupExceeded { markup_percent: f64 },
+}
+
+impl DccIssue {
+    pub fn decline(&self) -> (ResponseCode, &'static str) {
+        match self {
+            DccIssue::Missing(_) => (ResponseCode::FormatError, validation::FORMAT_ERROR_MESSAGE),
+            DccIssue::NoRate => (ResponseCode::InvalidAmount, "No DCC Rate for Currency Pair"),
+            DccIssue::MarkupExceeded { .. } => {
+                (ResponseCode::InvalidAmount, "DCC Markup Exceeds Limit")
+            }
+        }
+    }
+}
+
+fn major_units(minor_units: i64, currency: &Currency) -> f64 {
+    minor_units as f64 / 10f64.powi(currency.exponent as i32)
+}
+
+/// Checks the DCC fields of a validated authorization. `Ok(None)` when
+/// it is not a DCC transaction.
+pub fn check(
+    config: Option<&DccConfig>,
+    amount: &Amount,
+    de6: Option<&str>,
+    de51: Option<&str>,
+) -> Result<Option<f64>, DccIssue> {
+    let (de6, de51) = match (de6, de51) {
+        (None, None) => return Ok(None),
+        (Some(_), None) => return Err(DccIssue::Missing(51)),
+        (None, Some(_)) => return Err(DccIssue::Missing(6)),
+        (Some(de6), Some(de51)) => (de6, de51),
+    };
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    let original = currency::lookup(de51).expect("validated DE51 is a known currency");
+    let rate = config
+        .rate(original.numeric, amount.currency.numeric)
+        .ok_or(DccIssue::NoRate)?;
+    let converted = major_units(amount::parse_minor_units(de6).unwrap_or(0), original) * rate;
+    let billed = major_units(amount.minor_units, amount.currency);
+    let markup_percent = if converted > 0.0 {
+        (billed / converted - 1.0) * 100.0
+    } else {
+        0.0
+    };
+    // Rounding to the minor unit can push an exact markup a hair over.
+    if markup_percent > config.max_markup_percent + 1e-6 {
+        return Err(DccIssue::MarkupExceeded { markup_percent });
+    }
+    Ok(Some(markup_percent))
+}
+
+/// DE6 for a response approving `approved` of `requested` (DE4 values).
+pub fn scale_original(de6: &str, requested: &str, approved: &str) -> String {
+    match (
+        amount::parse_minor_units(de6),
+        amount::parse_minor_units(requested),
+        amount::parse_minor_units(approved),
+    ) {
+        (Some(original), Some(requested), Some(approved))
+            if requested > 0 && approved != requested =>
+        {
+            let scaled =
+                (original as i128 * approved as i128 + requested as i128 / 2) / requested as i128;
+            format!("{:012}", scaled)
+        }
+        _ => de6.to_string(),
+    }
+}
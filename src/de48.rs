@@ -0,0 +1,80 @@
+// ============================================================================
+// DE48 Additional Data (TCC + tag/length/value subelements)
+// ============================================================================
+//
+// Layout: an optional one-letter Transaction Category Code, followed by
+// subelements encoded as 2-digit tag, 2-digit length, then the data.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct De48 {
+    pub tcc: Option<char>,
+    pub subelements: Vec<(String, String)>,
+}
+
+impl De48 {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut de48 = De48::default();
+        let mut rest = raw;
+
+        if let Some(first) = rest.chars().next() {
+            if first.is_ascii_alphabetic() {
+                de48.tcc = Some(first);
+                rest = &rest[1..];
+            }
+        }
+
+        while !rest.is_empty() {
+            let tag = rest
+                .get(0..2)
+                .filter(|t| t.chars().all(|c| c.is_ascii_digit()))
+                .ok_or_else(|| format!("bad subelement tag in {:?}", rest))?;
+            let length: usize = rest
+                .get(2..4)
+                .filter(|l| l.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|l| l.parse().ok())
+                .ok_or_else(|| format!("bad length for subelement {}", tag))?;
+            let value = rest
+                .get(4..4 + length)
+                .ok_or_else(|| format!("subelement {} is truncated", tag))?;
+
+            de48.subelements.push((tag.to_string(), value.to_string()));
+            rest = &rest[4 + length..];
+        }
+
+        Ok(de48)
+    }
+
+    pub fn get(&self, tag: &str) -> Option<&str> {
+        self.subelements
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Replaces the subelement if present, otherwise appends it.
+    pub fn set(&mut self, tag: &str, value: &str) {
+        match self.subelements.iter_mut().find(|(t, _)| t == tag) {
+            Some(existing) => existing.1 = value.to_string(),
+            None => self.subelements.push((tag.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        if let Some(tcc) = self.tcc {
+            out.push(tcc);
+        }
+        for (tag, value) in &self.subelements {
+            out.push_str(&format!("{}{:02}{}", tag, value.len(), value));
+        }
+        out
+    }
+}
+
+/// Encodes nested subfields (same 2-digit id / 2-digit length layout).
+pub fn encode_subfields(subfields: &[(&str, &str)]) -> String {
+    subfields
+        .iter()
+        .map(|(id, value)| format!("{}{:02}{}", id, value.len(), value))
+        .collect()
+}
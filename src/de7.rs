@@ -0,0 +1,61 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+
+// ============================================================================
+// DE7 Transmission Date and Time
+// ============================================================================
+//
+// DE7 is MMDDhhmmss in UTC with no year. The year is taken to be the one
+// that puts the timestamp closest to the mock's clock, so a message sent
+// at 23:59:59 on 31 December and received after midnight still lands in
+// the old year. With CLOCK_SKEW set, messages further than that from the
+// clock are rejected with a format error on DE7 or, in `flag` mode, only
+// logged.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkewMode {
+    Reject,
+    Flag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkew {
+    pub tolerance: Duration,
+    pub mode: SkewMode,
+}
+
+/// The time DE7 names, in the year nearest to `now`; `None` for anything
+/// that is not a valid MMDDhhmmss.
+pub fn parse(de7: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if de7.len() != 10 || !de7.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| de7[range].parse::<u32>().ok();
+    let (month, day) = (field(0..2)?, field(2..4)?);
+    let (hour, minute, second) = (field(4..6)?, field(6..8)?, field(8..10)?);
+
+    // 29 February is only valid in leap years, so try the neighbours of
+    // the current year rather than validating once.
+    [now.year() - 1, now.year(), now.year() + 1]
+        .into_iter()
+        .filter_map(|year| {
+            NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, second)
+        })
+        .map(|time| Utc.from_utc_datetime(&time))
+        .min_by_key(|time| (*time - now).abs())
+}
+
+/// MMDDhhmmss for `time`.
+pub fn format(time: DateTime<Utc>) -> String {
+    time.format("%m%d%H%M%S").to_string()
+}
+
+impl ClockSkew {
+    /// How far `transmitted_at` is from `now`, when that is beyond the
+    /// tolerance.
+    pub fn exceeded(&self, transmitted_at: DateTime<Utc>, now: DateTime<Utc>) -> Option<Duration> {
+        let skew = (transmitted_at - now).abs();
+        (skew > self.tolerance).then_some(skew)
+    }
+}
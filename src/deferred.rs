@@ -0,0 +1,161 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use lru::LruCache;
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::handlers;
+use crate::latency::Latency;
+use crate::masking;
+use crate::{AppState, AuthorizationResponse};
+
+// ============================================================================
+// Deferred Authorizations
+// ============================================================================
+//
+// An issuer that takes its time can be modelled with `defer` on a rule's
+// action: /authorize answers at once with 202 Accepted and a reference, and
+// the 0110 follows once the `defer` latency has passed, as an
+// `authorization.approved` / `authorization.declined` event (webhooks, live
+// feed, Kafka) carrying the reference. Clients without a webhook can poll
+// `GET /authorize/{reference}` (202 while pending, 200 with the 0110 after).
+//
+// The decision itself is taken, and an approval stored and its funds held,
+// before the 202 is sent; only the answer is withheld, so a reversal sent
+// while waiting finds the transaction. Faults do not apply: there is no
+// HTTP response to the decision to corrupt.
+//
+// The last DEFERRED_CAPACITY references are kept.
+
+const DEFERRED_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeferredStatus {
+    Pending,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeferredAuthorization {
+    pub reference: String,
+    pub status: DeferredStatus,
+    /// Echoed so the answer can also be matched by DE32 / DE11.
+    pub de11: String,
+    pub de32: String,
+    pub de37: Option<String>,
+    /// The 0110, once delivered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<AuthorizationResponse>,
+}
+
+pub struct DeferredAuthorizations {
+    entries: Mutex<LruCache<String, DeferredAuthorization>>,
+}
+
+impl Default for DeferredAuthorizations {
+    fn default() -> Self {
+        DeferredAuthorizations {
+            entries: Mutex::new(LruCache::new(NonZeroUsize::new(DEFERRED_CAPACITY).unwrap())),
+        }
+    }
+}
+
+impl DeferredAuthorizations {
+    pub fn get(&self, reference: &str) -> Option<DeferredAuthorization> {
+        self.entries.lock().unwrap().peek(reference).cloned()
+    }
+
+    fn insert(&self, entry: DeferredAuthorization) {
+        self.entries
+            .lock()
+            .unwrap()
+            .put(entry.reference.clone(), entry);
+    }
+}
+
+/// Answers 202 for `response` and delivers it after `delay`.
+pub(crate) fn defer(
+    state: &Arc<AppState>,
+    response: AuthorizationResponse,
+    delay: &Latency,
+) -> Response {
+    let reference = Uuid::new_v4().to_string();
+    let delay = delay.sample();
+    info!(
+        %reference,
+        delay_ms = delay.as_millis() as u64,
+        "authorization deferred"
+    );
+    let pending = DeferredAuthorization {
+        reference: reference.clone(),
+        status: DeferredStatus::Pending,
+        de11: response.de11.clone(),
+        de32: response.de32.clone(),
+        de37: response.de37.clone(),
+        response: None,
+    };
+    state.deferred.insert(pending.clone());
+
+    let state = state.clone();
+    let mut completed = pending.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        info!(%reference, de39 = %response.de39, "deferred authorization delivered");
+        handlers::notify_authorization(&state, &response, Some(&reference));
+        completed.status = DeferredStatus::Completed;
+        completed.response = Some(response);
+        state.deferred.insert(completed);
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        [(
+            header::LOCATION,
+            format!("/authorize/{}", pending.reference),
+        )],
+        Json(pending),
+    )
+        .into_response()
+}
+
+/// Polls a deferred authorization.
+#[utoipa::path(
+    get,
+    path = "/authorize/{reference}",
+    tag = "messages",
+    params(("reference" = String, Path)),
+    responses(
+        (status = 200, description = "Delivered; `response` is the 0110 with DE2 masked", body = DeferredAuthorization),
+        (status = 202, description = "Still pending", body = DeferredAuthorization),
+        (status = 404, description = "Unknown or expired reference", body = String),
+    )
+)]
+pub async fn get_deferred(
+    State(state): State<Arc<AppState>>,
+    Path(reference): Path<String>,
+) -> Response {
+    let Some(mut entry) = state.deferred.get(&reference) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("no deferred authorization {}", reference),
+        )
+            .into_response();
+    };
+    let status = match entry.status {
+        DeferredStatus::Pending => StatusCode::ACCEPTED,
+        DeferredStatus::Completed => StatusCode::OK,
+    };
+    if let Some(response) = &mut entry.response {
+        response.de2 = masking::mask_pan(&response.de2);
+    }
+    (status, Json(entry)).into_response()
+}
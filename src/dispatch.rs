@@ -0,0 +1,357 @@
+use axum::{
+    async_trait,
+    body::{to_bytes, Body, Bytes},
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::error::{ApiError, ApiJson, ErrorBody};
+use crate::faults::CutShort;
+use crate::handlers;
+use crate::iso8583::{self, DeKey, Iso8583Message};
+use crate::processing;
+use crate::{AppState, AuthorizationRequest, CompletionRequest, ResponseCode, ReversalRequest};
+
+// ============================================================================
+// Generic Messages (`/iso8583`)
+// ============================================================================
+//
+// Any MTI on one endpoint, DEs keyed by number, answered by the
+// `MessageHandler` registered for it; request MTIs without one get DE39 12
+// in-band, anything else 422 `unsupported_mti`.
+
+pub const UNSUPPORTED_MESSAGE: &str = "Unsupported Message Type";
+
+const RESPONSE_BODY_LIMIT: usize = 1024 * 1024;
+
+/// A message with its data elements keyed by DE number.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GenericMessage {
+    #[schema(example = "0100")]
+    pub mti: String,
+    /// Data elements by number, e.g. `"2"` for the PAN.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// Set on responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_message: Option<String>,
+}
+
+/// What a handler gets besides the message.
+#[derive(Debug, Clone)]
+pub struct MessageContext {
+    /// The client's address, which the built-in handlers rate limit by.
+    pub peer: SocketAddr,
+    /// The request headers, e.g. the correlation ID.
+    pub headers: HeaderMap,
+    /// When the message arrived.
+    pub received: Instant,
+}
+
+/// Answers the messages of one request MTI on `/iso8583`.
+#[async_trait]
+pub trait MessageHandler: Send + Sync {
+    /// The request MTI handled, e.g. `"9100"`.
+    fn mti(&self) -> &str;
+
+    /// Checks the message before `handle`; an error is answered as is.
+    fn validate(&self, _message: &Iso8583Message) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    /// The response, usually built with `reply`.
+    async fn handle(
+        &self,
+        state: Arc<AppState>,
+        context: MessageContext,
+        message: Iso8583Message,
+    ) -> Response;
+}
+
+/// Handlers by request MTI.
+#[derive(Clone)]
+pub struct MessageHandlers(HashMap<String, Arc<dyn MessageHandler>>);
+
+impl MessageHandlers {
+    /// Authorization, completion and reversal, as on their own endpoints.
+    pub fn builtin() -> Self {
+        let mut handlers = MessageHandlers(HashMap::new());
+        for (mti, endpoint) in [
+            (processing::AUTHORIZATION_REQUEST, Endpoint::Authorize),
+            (processing::FINANCIAL_REQUEST, Endpoint::Authorize),
+            ("0220", Endpoint::Completion),
+            ("0400", Endpoint::Reversal),
+        ] {
+            handlers.register(Builtin { mti, endpoint });
+        }
+        handlers
+    }
+
+    /// Adds a handler, replacing any other for its MTI.
+    pub fn register(&mut self, handler: impl MessageHandler + 'static) {
+        self.0.insert(handler.mti().to_string(), Arc::new(handler));
+    }
+
+    pub fn get(&self, mti: &str) -> Option<Arc<dyn MessageHandler>> {
+        self.0.get(mti).cloned()
+    }
+
+    /// The MTIs handled, in order.
+    pub fn mtis(&self) -> Vec<&str> {
+        let mut mtis: Vec<&str> = self.0.keys().map(String::as_str).collect();
+        mtis.sort_unstable();
+        mtis
+    }
+}
+
+impl Default for MessageHandlers {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for MessageHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.mtis()).finish()
+    }
+}
+
+/// The response to a message: `answer` and `response_message`, as the
+/// message endpoints answer.
+pub fn reply(answer: &Iso8583Message, response_message: &str) -> Response {
+    let mut body = serde_json::to_value(answer).expect("a message serializes");
+    body["response_message"] = response_message.into();
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+/// The endpoint a built-in handler passes messages to.
+#[derive(Debug, Clone, Copy)]
+enum Endpoint {
+    Authorize,
+    Completion,
+    Reversal,
+}
+
+struct Builtin {
+    mti: &'static str,
+    endpoint: Endpoint,
+}
+
+#[async_trait]
+impl MessageHandler for Builtin {
+    fn mti(&self) -> &str {
+        self.mti
+    }
+
+    async fn handle(
+        &self,
+        state: Arc<AppState>,
+        context: MessageContext,
+        message: Iso8583Message,
+    ) -> Response {
+        let (state, peer, headers) = (State(state), ConnectInfo(context.peer), context.headers);
+        match self.endpoint {
+            Endpoint::Authorize => {
+                let request = AuthorizationRequest::from(message);
+                handlers::authorize(state, peer, headers, ApiJson(request)).await
+            }
+            Endpoint::Completion => {
+                let request = CompletionRequest::from(message);
+                handlers::completion(state, peer, headers, ApiJson(request)).await
+            }
+            Endpoint::Reversal => {
+                let request = ReversalRequest::from(message);
+                handlers::reversal(state, peer, headers, ApiJson(request)).await
+            }
+        }
+    }
+}
+
+/// The response MTI to a request or advice: 0100 → 0110, 0221 → 0230.
+/// `None` for anything that expects no response.
+pub fn response_mti(mti: &str) -> Option<String> {
+    let digits = mti.as_bytes();
+    if digits.len() != 4 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let function = match digits[2] {
+        b'0' => '1',
+        b'2' => '3',
+        _ => return None,
+    };
+    // Repeats (odd origins) are answered as the original would be.
+    let origin = char::from(digits[3] & !1);
+    Some(format!("{}{}{}", &mti[..2], function, origin))
+}
+
+/// `message` answered as a switch would without processing it: its fields
+/// under `response_mti`, with DE39 `code`.
+pub(crate) fn echo(
+    message: Iso8583Message,
+    response_mti: String,
+    code: ResponseCode,
+) -> Iso8583Message {
+    let mut answer = Iso8583Message {
+        mti: response_mti,
+        fields: message.fields,
+    };
+    answer.set(39, code.code()).expect("DE39 is registered");
+    answer
+}
+
+fn field_error(code: &'static str, number: &str, issue: impl Into<String>) -> ApiError {
+    ApiError::field(code, format!("fields.{}", number), issue.into())
+}
+
+impl GenericMessage {
+    /// The message with its fields typed by the registry. DE numbers the
+    /// registry doesn't know are skipped, as on the other endpoints.
+    fn to_message(&self) -> Result<Iso8583Message, ApiError> {
+        let mut message = Iso8583Message::new(self.mti.clone());
+        for (key, value) in &self.fields {
+            let Some(de_key) = DeKey::parse(key) else {
+                return Err(field_error(
+                    "unknown_field",
+                    key,
+                    format!("unknown field `{}`, expected a DE number or alias", key),
+                ));
+            };
+            let Some(definition) = de_key.definition else {
+                warn!("Ignoring unregistered data element {}", key);
+                continue;
+            };
+            message
+                .set(definition.number, value.clone())
+                .map_err(|e| field_error("invalid_field", key, e))?;
+        }
+        Ok(message)
+    }
+}
+
+/// Rewrites the JSON message a handler answered with, leaving anything
+/// that is not a message (error envelopes, plain text) as it is.
+pub(crate) async fn rewrite(
+    response: Response,
+    rewrite: impl FnOnce(Map<String, Value>) -> Map<String, Value>,
+) -> Response {
+    if response.extensions().get::<CutShort>().is_some() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, RESPONSE_BODY_LIMIT).await {
+        Ok(bytes) => bytes,
+        // A fault dropped the connection; drop this one too.
+        Err(e) => {
+            let error = io::Error::new(io::ErrorKind::ConnectionAborted, e.to_string());
+            let stream = futures::stream::once(async move { Err::<Bytes, _>(error) });
+            return Response::from_parts(parts, Body::from_stream(stream));
+        }
+    };
+    let Ok(Value::Object(object)) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if !object.contains_key("mti") {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(
+        parts,
+        Body::from(Value::Object(rewrite(object)).to_string()),
+    )
+}
+
+/// A handler's JSON message in the generic shape.
+fn reshape(object: Map<String, Value>) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let mut rest = Map::new();
+    for (key, value) in object {
+        match iso8583::parse_key(&key) {
+            Some(number) => {
+                fields.insert(number.to_string(), value);
+            }
+            None => {
+                rest.insert(key, value);
+            }
+        }
+    }
+    let mut reshaped = Map::new();
+    if let Some(mti) = rest.remove("mti") {
+        reshaped.insert("mti".to_string(), mti);
+    }
+    reshaped.insert("fields".to_string(), Value::Object(fields));
+    reshaped.extend(rest);
+    reshaped
+}
+
+#[utoipa::path(
+    post,
+    path = "/iso8583",
+    tag = "messages",
+    request_body = GenericMessage,
+    responses(
+        (status = 200, description = "The response message; DE39 12 for an unsupported request MTI", body = GenericMessage),
+        (status = 422, description = "Malformed message, or an MTI that is not a request or advice", body = ErrorBody),
+    )
+)]
+pub async fn dispatch(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(generic): ApiJson<GenericMessage>,
+) -> Response {
+    let context = MessageContext {
+        peer,
+        headers,
+        received: Instant::now(),
+    };
+    let message = match generic.to_message() {
+        Ok(message) => message,
+        Err(e) => return e.into_response(),
+    };
+
+    let pipeline = state.pipeline.clone();
+    let response = pipeline
+        .run(&state, &context, message, |message| {
+            handle(state.clone(), context.clone(), message)
+        })
+        .await;
+    rewrite(response, reshape).await
+}
+
+/// Answers `message` with the handler for its MTI.
+async fn handle(
+    state: Arc<AppState>,
+    context: MessageContext,
+    message: Iso8583Message,
+) -> Response {
+    let Some(handler) = state.handlers.get(&message.mti) else {
+        let Some(response_mti) = response_mti(&message.mti) else {
+            return ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "unsupported_mti",
+                format!("MTI {:?} is not a request or advice", message.mti),
+            )
+            .into_response();
+        };
+        info!("Unsupported MTI {}", message.mti);
+        let answer = echo(message, response_mti, ResponseCode::InvalidTransaction);
+        return reply(&answer, UNSUPPORTED_MESSAGE);
+    };
+    if let Err(e) = handler.validate(&message) {
+        return e.into_response();
+    }
+    handler.handle(state, context, message).await
+}
@@ -0,0 +1,76 @@
+use crate::codes::ResponseCode;
+use crate::de48::De48;
+use crate::rules::FieldSource;
+use crate::ucaf;
+
+// ============================================================================
+// DSRP Cryptogram Validation
+// ============================================================================
+//
+// Digital Secure Remote Payments are tokenized e-commerce authorizations
+// whose UCAF (DE48 SE43) holds a cryptogram generated by the wallet rather
+// than an AAV from 3-D Secure. The network validates the cryptogram on the
+// issuer's behalf and reports the outcome in DE48 SE71 (on-behalf
+// services): service `51`, result `V` (valid) or `I` (invalid). Invalid
+// cryptograms are declined with 88.
+//
+// The mock's validation is keyed off the token: a token with a
+// `dsrp_cryptogram` only accepts that value, other tokens accept any
+// well-formed cryptogram (28 base64 characters, like a 20-byte AAV).
+
+pub const ON_BEHALF_SUBELEMENT: &str = "71";
+
+/// On-behalf service code for MDES cryptogram validation.
+const CRYPTOGRAM_VALIDATION: &str = "51";
+
+const CRYPTOGRAM_LENGTH: usize = 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptogramResult {
+    Valid,
+    /// DE39=88
+    Invalid,
+}
+
+impl CryptogramResult {
+    /// DE48 SE71 value: service, result 1, result 2 (unused).
+    pub fn on_behalf(self) -> String {
+        let result = match self {
+            CryptogramResult::Valid => 'V',
+            CryptogramResult::Invalid => 'I',
+        };
+        format!("{}{} ", CRYPTOGRAM_VALIDATION, result)
+    }
+
+    pub fn decline(self) -> Option<(ResponseCode, &'static str)> {
+        (self == CryptogramResult::Invalid)
+            .then_some((ResponseCode::CryptographicFailure, "Cryptographic Failure"))
+    }
+}
+
+/// The DSRP cryptogram of an e-commerce authorization, if it carries one.
+/// Only meaningful once DE2 is known to be a token.
+pub fn cryptogram(message: &impl FieldSource) -> Option<String> {
+    if !ucaf::is_ecommerce(message) {
+        return None;
+    }
+    De48::parse(message.field("de48")?)
+        .ok()?
+        .get(ucaf::UCAF_SUBELEMENT)
+        .filter(|ucaf| !ucaf.is_empty())
+        .map(str::to_string)
+}
+
+pub fn validate(cryptogram: &str, expected: Option<&str>) -> CryptogramResult {
+    let well_formed = cryptogram.len() == CRYPTOGRAM_LENGTH
+        && cryptogram
+            .trim_end_matches('=')
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+    let matches = expected.is_none_or(|expected| expected == cryptogram);
+    if well_formed && matches {
+        CryptogramResult::Valid
+    } else {
+        CryptogramResult::Invalid
+    }
+}
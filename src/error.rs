@@ -0,0 +1,180 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::error::Category;
+use utoipa::ToSchema;
+
+// ============================================================================
+// Request Error Envelope
+// ============================================================================
+//
+// Malformed request bodies are answered with one JSON shape:
+//
+//   {"error": {"status": 422, "code": "invalid_field", "message": "...",
+//              "fields": [{"field": "de4", "issue": "..."}]}}
+//
+//   400 invalid_json            body is not well-formed JSON
+//   413 body_too_large          body exceeds MAX_BODY_BYTES
+//   415 unsupported_media_type  Content-Type is not application/json
+//   422 missing_field           a mandatory field is absent, e.g. `mti`
+//   422 unknown_field           a key that is neither `mti` nor a DE
+//   422 invalid_field           a field has the wrong type or format
+//   422 unsupported_mti         /iso8583 got an MTI that expects no response
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path to the field, e.g. `de4` or `cards[0].balance`.
+    pub field: String,
+    pub issue: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiError {
+    #[serde(serialize_with = "serialize_status")]
+    #[schema(value_type = u16, example = 422)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+fn serialize_status<S: serde::Serializer>(status: &StatusCode, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u16(status.as_u16())
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ApiError,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub(crate) fn field(code: &'static str, field: String, issue: String) -> Self {
+        ApiError {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code,
+            message: format!("{}: {}", field, issue),
+            fields: vec![FieldError { field, issue }],
+        }
+    }
+
+    /// Maps a serde failure to 400 (syntax) or 422 (content) with the
+    /// offending field.
+    fn from_json(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = error.path().to_string();
+        let inner = error.into_inner();
+        if matches!(
+            inner.classify(),
+            Category::Syntax | Category::Eof | Category::Io
+        ) {
+            return ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_json",
+                format!("request body is not valid JSON: {}", inner),
+            );
+        }
+
+        // serde_json appends " at line L column C"; the path says more.
+        let issue = inner.to_string();
+        let issue = issue
+            .rsplit_once(" at line ")
+            .map_or(issue.as_str(), |(issue, _)| issue)
+            .to_string();
+
+        // A missing field is reported against the enclosing object; name
+        // the field itself.
+        let within = |name: &str| {
+            if path == "." {
+                name.to_string()
+            } else {
+                format!("{}.{}", path, name)
+            }
+        };
+        if let Some(name) = backticked(&issue, "missing field `") {
+            return Self::field("missing_field", within(name), "is required".to_string());
+        }
+        if issue.starts_with("unknown field `") {
+            return Self::field("unknown_field", path, issue);
+        }
+        Self::field("invalid_field", path, issue)
+    }
+}
+
+/// Deserializes one message of a larger body, reporting failures like
+/// `ApiJson` does.
+pub fn from_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, ApiError> {
+    serde_path_to_error::deserialize(value).map_err(ApiError::from_json)
+}
+
+/// The name in "<prefix>name`...".
+fn backticked<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    message.strip_prefix(prefix)?.split('`').next()
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorBody { error: self })).into_response()
+    }
+}
+
+/// `Json` extractor whose rejections use the error envelope.
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| {
+                let mime = mime.trim();
+                mime == "application/json" || mime.ends_with("+json")
+            });
+        if !is_json {
+            return Err(ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+                "expected Content-Type: application/json",
+            ));
+        }
+
+        let bytes =
+            Bytes::from_request(request, state)
+                .await
+                .map_err(|rejection| match rejection.status() {
+                    StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "body_too_large",
+                        rejection.body_text(),
+                    ),
+                    status => ApiError::new(status, "unreadable_body", rejection.body_text()),
+                })?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value = serde_path_to_error::deserialize(deserializer).map_err(ApiError::from_json)?;
+        Ok(ApiJson(value))
+    }
+}
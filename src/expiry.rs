@@ -0,0 +1,188 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{AppState, Transaction, TransactionState};
+
+// ============================================================================
+// Authorization Hold Expiry
+// ============================================================================
+//
+// Uncaptured authorizations expire after the `hold_expiry` period (or
+// TRANSACTION_TTL) on the simulator's clock, moving to `expired` and
+// releasing their hold.
+
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Parses `90s`, `30m`, `12h` or `7d` (a bare number means seconds).
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&value[..i], c),
+        _ => (value, 's'),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", value))?;
+
+    let duration = match unit {
+        's' => Duration::try_seconds(number),
+        'm' => Duration::try_minutes(number),
+        'h' => Duration::try_hours(number),
+        'd' => Duration::try_days(number),
+        _ => return Err(format!("invalid duration unit in {}", value)),
+    };
+    duration.ok_or_else(|| format!("duration out of range: {}", value))
+}
+
+impl Transaction {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// The stored state, or `Expired` for an uncaptured authorization past
+    /// its expiry that the sweeper has not reached yet.
+    pub fn effective_state(&self, now: DateTime<Utc>) -> TransactionState {
+        if self.state == TransactionState::Authorized && self.is_expired(now) {
+            TransactionState::Expired
+        } else {
+            self.state
+        }
+    }
+}
+
+/// A duration written as in TRANSACTION_TTL (`12h`, `7d`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period(pub Duration);
+
+impl Serialize for Period {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let seconds = self.0.num_seconds();
+        let text = [(86_400, 'd'), (3_600, 'h'), (60, 'm')]
+            .into_iter()
+            .find(|(unit, _)| seconds != 0 && seconds % unit == 0)
+            .map_or_else(
+                || format!("{}s", seconds),
+                |(unit, suffix)| format!("{}{}", seconds / unit, suffix),
+            );
+        serializer.serialize_str(&text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Period {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse_duration(&text)
+            .map(Period)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// How long an uncaptured authorization holds funds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldExpiryConfig {
+    /// `null` keeps holds until captured or reversed.
+    #[serde(default = "default_period")]
+    pub default: Option<Period>,
+    /// Periods by DE18, single MCCs or ranges such as `3501-3999`.
+    #[serde(default = "default_mcc_periods")]
+    pub mcc: BTreeMap<String, Period>,
+}
+
+fn default_period() -> Option<Period> {
+    Some(Period(Duration::days(7)))
+}
+
+fn default_mcc_periods() -> BTreeMap<String, Period> {
+    ["3351-3441", "3501-3999", "4411", "7011", "7512"]
+        .into_iter()
+        .map(|mcc| (mcc.to_string(), Period(Duration::days(30))))
+        .collect()
+}
+
+impl Default for HoldExpiryConfig {
+    fn default() -> Self {
+        HoldExpiryConfig {
+            default: default_period(),
+            mcc: default_mcc_periods(),
+        }
+    }
+}
+
+impl HoldExpiryConfig {
+    /// The hold period for a merchant category.
+    pub fn period(&self, mcc: &str) -> Option<Duration> {
+        let listed = self.mcc.iter().find(|(key, _)| match key.split_once('-') {
+            Some((low, high)) => mcc.len() == 4 && low <= mcc && mcc <= high,
+            None => key.as_str() == mcc,
+        });
+        match listed {
+            Some((_, period)) => Some(period.0),
+            None => self.default.map(|period| period.0),
+        }
+    }
+}
+
+/// Moves the authorization under `key` to `expired` and releases its hold,
+/// if it is still uncaptured and past its expiry when the store gets to it.
+pub(crate) fn expire(state: &AppState, key: &str) {
+    let now = state.clock.now();
+    let mut expired = None;
+    let updated = state
+        .authorized_transactions
+        .update(key, &mut |transaction| {
+            if !transaction
+                .state
+                .can_transition_to(TransactionState::Expired)
+                || !transaction.is_expired(now)
+            {
+                return false;
+            }
+            transaction.state = TransactionState::Expired;
+            expired = Some(transaction.pan.clone());
+            true
+        });
+    if let Err(e) = updated {
+        warn!("Failed to mark transaction {} expired: {}", key, e);
+    }
+    if let Some(pan) = expired {
+        info!("Authorization {} expired", key);
+        state.ledger.release(&pan, key);
+    }
+}
+
+/// Periodically expires uncaptured authorizations past their expiry, and
+/// releases what is left of partially captured ones.
+/// Lookups use `effective_state`, so nothing slips through between sweeps.
+pub fn spawn_sweeper(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let transactions = match state.authorized_transactions.list() {
+                Ok(transactions) => transactions,
+                Err(e) => {
+                    warn!("Expiry sweep failed: {}", e);
+                    continue;
+                }
+            };
+            let now = state.clock.now();
+            for transaction in transactions.into_iter().filter(|t| t.is_expired(now)) {
+                match transaction.state {
+                    TransactionState::Authorized => expire(&state, &transaction.key()),
+                    // What partial captures left of the hold lapses too.
+                    TransactionState::Captured => {
+                        if let Some(amount) =
+                            state.ledger.release(&transaction.pan, &transaction.key())
+                        {
+                            info!("Released {} left on {}", amount, transaction.stan);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,185 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::masking;
+use crate::AppState;
+
+// ============================================================================
+// Transaction Export
+// ============================================================================
+
+/// Exportable columns, in default output order. `request` and `response`
+/// (the stored payloads) and `interchange_rate` (the name of the matched
+/// rate) can be selected explicitly. The interchange columns are null
+/// when the rules price no interchange for the transaction.
+const DEFAULT_COLUMNS: &[&str] = &[
+    "rrn",
+    "acquirer_id",
+    "terminal_id",
+    "card_acceptor_id",
+    "stan",
+    "pan",
+    "amount",
+    "response_code",
+    "state",
+    "captured_amount",
+    "captures",
+    "refunded_amount",
+    "refund_of",
+    "timestamp",
+    "recorded_at",
+    "interchange_fee",
+];
+const EXTRA_COLUMNS: &[&str] = &["request", "response", "interchange_rate"];
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Comma-separated column list; defaults to `DEFAULT_COLUMNS`.
+    pub columns: Option<String>,
+    /// Mask DE2 (first 6 / last 4). `mask=false` only takes effect when
+    /// the server runs with `--log-sensitive`.
+    #[serde(default = "default_mask")]
+    pub mask: bool,
+}
+
+fn default_mask() -> bool {
+    true
+}
+
+impl ExportQuery {
+    fn columns(&self) -> Result<Vec<String>, String> {
+        let Some(columns) = &self.columns else {
+            return Ok(DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect());
+        };
+        columns
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|column| {
+                if DEFAULT_COLUMNS.contains(&column) || EXTRA_COLUMNS.contains(&column) {
+                    Ok(column.to_string())
+                } else {
+                    Err(format!("unknown column: {}", column))
+                }
+            })
+            .collect()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Every stored transaction", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+        )),
+        (status = 400, description = "Unknown column", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn export_transactions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let columns = query.columns().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut transactions = state
+        .authorized_transactions
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    transactions.sort_by_key(|transaction| transaction.recorded_at);
+
+    let header_row = csv_line(columns.iter().cloned());
+    let mask = query.mask || !masking::log_sensitive();
+    let rules = state.rules();
+    let rows = transactions.into_iter().map(move |mut transaction| {
+        let fee = rules.interchange.as_ref().and_then(|interchange| {
+            interchange.fee(&transaction, state.bins.lookup(&transaction.pan))
+        });
+        if mask {
+            transaction.pan = masking::mask_pan(&transaction.pan);
+        }
+        let mut record = match serde_json::to_value(&transaction) {
+            Ok(Value::Object(record)) => record,
+            _ => Map::new(),
+        };
+        if let Some(fee) = fee {
+            record.insert("interchange_fee".to_string(), fee.amount.into());
+            record.insert("interchange_rate".to_string(), fee.rate.into());
+        }
+        columns
+            .iter()
+            .map(|column| (column.clone(), record.remove(column).unwrap_or(Value::Null)))
+            .collect::<Map<String, Value>>()
+    });
+
+    // Rows are rendered lazily as the body is polled, so large stores are
+    // never held as one formatted string.
+    let (content_type, lines): (_, Box<dyn Iterator<Item = String> + Send>) = match query.format {
+        ExportFormat::Ndjson => (
+            "application/x-ndjson",
+            Box::new(rows.map(|row| format!("{}\n", Value::Object(row)))),
+        ),
+        ExportFormat::Csv => (
+            "text/csv",
+            Box::new(
+                std::iter::once(header_row)
+                    .chain(rows.map(|row| csv_line(row.values().map(csv_value)))),
+            ),
+        ),
+    };
+
+    let stream = futures::stream::iter(lines.map(|line| Ok::<_, Infallible>(Bytes::from(line))));
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// RFC 4180 line: fields containing commas, quotes or newlines are quoted.
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let mut line = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
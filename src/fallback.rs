@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::codes::ResponseCode;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Chip Fallback Detection
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChipFallbackConfig {
+    /// Decline every fallback transaction.
+    #[serde(default)]
+    pub decline: bool,
+    /// Decline once a card has fallen back more than this many times.
+    pub max_per_card: Option<u32>,
+    #[serde(default = "default_decline_code")]
+    pub decline_code: ResponseCode,
+}
+
+fn default_decline_code() -> ResponseCode {
+    ResponseCode::DoNotHonor
+}
+
+impl ChipFallbackConfig {
+    /// `count` includes the current transaction.
+    pub fn should_decline(&self, count: u32) -> bool {
+        self.decline || self.max_per_card.is_some_and(|max| count > max)
+    }
+}
+
+/// Service code from track 2 (DE35): the three digits after the separator
+/// and the YYMM expiry date.
+pub fn service_code(track2: &str) -> Option<&str> {
+    let separator = track2.find(['=', 'D', 'd'])?;
+    track2
+        .get(separator + 5..separator + 8)
+        .filter(|code| code.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// DE22 80 is an explicit fallback; 02/90 is a swipe that counts as fallback
+/// when the service code says the card has a chip (first digit 2 or 6).
+pub fn is_fallback(message: &impl FieldSource) -> bool {
+    match message.field("de22").and_then(|d| d.get(0..2)) {
+        Some("80") => true,
+        Some("02") | Some("90") => message
+            .field("de35")
+            .and_then(service_code)
+            .is_some_and(|code| code.starts_with('2') || code.starts_with('6')),
+        _ => false,
+    }
+}
@@ -0,0 +1,172 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+use tracing::info;
+
+// ============================================================================
+// Fault Injection
+// ============================================================================
+//
+// Faults are applied after the transaction has been processed and stored, so
+// an approval that is never delivered still has to be reversed by the client.
+// The mock has no binary (length-prefixed TCP) listener: connection faults
+// act on the HTTP connection the message arrived on.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fault {
+    /// Hold the request open and never answer.
+    NoResponse,
+    /// Answer only after `ms`, well past typical acquirer timeouts.
+    LateResponse { ms: u64 },
+    /// Answer with an MTI that does not match the request.
+    WrongMti {
+        #[serde(default = "default_wrong_mti")]
+        mti: String,
+    },
+    /// Drop echo fields the client expects to see.
+    MissingFields {
+        #[serde(default = "default_missing_fields")]
+        fields: Vec<String>,
+    },
+    /// Cut the JSON body short, leaving it unparseable.
+    TruncatedJson { bytes: Option<usize> },
+    /// Send part of the body, then abort the connection mid-response.
+    DropConnection { bytes: Option<usize> },
+    /// Send part of the body (none by default), then reset the connection
+    /// with a TCP RST.
+    ResetConnection { bytes: Option<usize> },
+    /// Announce the whole body's length, send part of it, then stall with
+    /// the connection open.
+    PartialFrame { bytes: Option<usize> },
+}
+
+/// Marks a response whose body a connection fault cuts short. Layers that
+/// read bodies whole pass it on untouched; `server::serve` resets the
+/// connection when `reset` is set.
+#[derive(Debug, Clone, Copy)]
+pub struct CutShort {
+    pub reset: bool,
+}
+
+fn default_wrong_mti() -> String {
+    "0210".to_string()
+}
+
+fn default_missing_fields() -> Vec<String> {
+    vec!["de2".to_string(), "de4".to_string(), "de11".to_string()]
+}
+
+impl Fault {
+    /// Produces the (possibly delayed or corrupted) HTTP response for `body`.
+    pub async fn render(&self, body: &impl Serialize) -> Response {
+        let mut value = serde_json::to_value(body).unwrap();
+
+        match self {
+            Fault::NoResponse => {
+                info!("Fault injected: withholding response");
+                std::future::pending::<()>().await;
+            }
+            Fault::LateResponse { ms } => {
+                info!("Fault injected: responding after {} ms", ms);
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+            }
+            Fault::WrongMti { mti } => {
+                info!("Fault injected: responding with MTI {}", mti);
+                value["mti"] = serde_json::Value::String(mti.clone());
+            }
+            Fault::MissingFields { fields } => {
+                info!("Fault injected: dropping fields {:?}", fields);
+                if let Some(object) = value.as_object_mut() {
+                    for field in fields {
+                        object.remove(field);
+                    }
+                }
+            }
+            Fault::TruncatedJson { bytes } => {
+                let json = value.to_string();
+                let cut = bytes.unwrap_or(json.len() / 2).min(json.len());
+                info!("Fault injected: truncating body to {} bytes", cut);
+                return (
+                    StatusCode::OK,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    json.as_bytes()[..cut].to_vec(),
+                )
+                    .into_response();
+            }
+            Fault::DropConnection { bytes } => {
+                let json = value.to_string();
+                let cut = bytes.unwrap_or(json.len() / 2).min(json.len());
+                info!("Fault injected: dropping connection after {} bytes", cut);
+                return aborted(&json.as_bytes()[..cut], false);
+            }
+            Fault::ResetConnection { bytes } => {
+                let json = value.to_string();
+                let cut = bytes.unwrap_or(0).min(json.len());
+                info!("Fault injected: resetting connection after {} bytes", cut);
+                return aborted(&json.as_bytes()[..cut], true);
+            }
+            Fault::PartialFrame { bytes } => {
+                let json = value.to_string();
+                let cut = bytes.unwrap_or(json.len() / 2).min(json.len());
+                info!(
+                    "Fault injected: stalling after {} of {} bytes",
+                    cut,
+                    json.len()
+                );
+                let partial = Bytes::copy_from_slice(&json.as_bytes()[..cut]);
+                let stream = futures::stream::once(async move { Ok::<_, io::Error>(partial) })
+                    .chain(futures::stream::pending());
+                let mut response = (
+                    StatusCode::OK,
+                    [
+                        (header::CONTENT_TYPE, "application/json".to_string()),
+                        (header::CONTENT_LENGTH, json.len().to_string()),
+                    ],
+                    Body::from_stream(stream),
+                )
+                    .into_response();
+                response.extensions_mut().insert(CutShort { reset: false });
+                return response;
+            }
+        }
+
+        (StatusCode::OK, Json(value)).into_response()
+    }
+}
+
+/// A response that sends `partial`, then aborts the connection, with a RST
+/// if `reset`.
+fn aborted(partial: &[u8], reset: bool) -> Response {
+    // A body stream that errors makes hyper abort the connection, so the
+    // client sees an incomplete chunked response. The pause lets the partial
+    // chunk reach the wire before the abort.
+    let chunks: Vec<Result<Bytes, io::Error>> = vec![
+        Ok(Bytes::copy_from_slice(partial)),
+        Err(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "injected connection drop",
+        )),
+    ];
+    let stream = futures::stream::iter(chunks).then(|chunk| async move {
+        if chunk.is_err() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        chunk
+    });
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(stream),
+    )
+        .into_response();
+    response.extensions_mut().insert(CutShort { reset });
+    response
+}
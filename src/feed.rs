@@ -0,0 +1,91 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+
+use crate::webhooks::Event;
+use crate::AppState;
+
+// ============================================================================
+// Live Transaction Feed
+// ============================================================================
+//
+// Every processed authorization, reversal and completion is published here
+// as the same masked event that webhooks receive, so dashboards and testers
+// can watch traffic as it happens instead of polling /transactions:
+//
+//   GET /ws/transactions  one JSON text frame per event
+//
+// Subscribers only see events published after they connect. One that falls
+// more than FEED_CAPACITY events behind skips the events it missed.
+
+pub const FEED_CAPACITY: usize = 1024;
+
+pub struct EventFeed {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventFeed {
+    fn default() -> Self {
+        EventFeed {
+            sender: broadcast::channel(FEED_CAPACITY).0,
+        }
+    }
+}
+
+impl EventFeed {
+    pub fn publish(&self, event: &Event) {
+        // No subscribers is not an error.
+        let _ = self.sender.send(event.clone());
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+/// Streams every processed transaction over a WebSocket.
+#[utoipa::path(
+    get,
+    path = "/ws/transactions",
+    tag = "transactions",
+    responses((status = 101, description = "WebSocket of webhook-style JSON events"))
+)]
+pub async fn transaction_socket(
+    State(state): State<Arc<AppState>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let events = state.feed.subscribe();
+    upgrade.on_upgrade(move |socket| stream(socket, events))
+}
+
+async fn stream(mut socket: WebSocket, mut events: broadcast::Receiver<Event>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(text) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    debug!("Feed subscriber lagging, skipped {} events", missed)
+                }
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::rules::FieldSource;
+use crate::{country, currency};
+
+// ============================================================================
+// Mock Fraud Scoring (Decision Intelligence style)
+// ============================================================================
+//
+// The score is a pure function of the message, so the same request always
+// scores the same. It is returned in DE48 SE75 (subfield 01 score, 02 reason).
+
+pub const FRAUD_SUBELEMENT: &str = "75";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraudConfig {
+    /// Decline with 59 when the score is above this value (0-999).
+    pub decline_threshold: Option<u16>,
+    #[serde(default = "default_high_risk_mccs")]
+    pub high_risk_mccs: Vec<String>,
+    /// ISO 3166 numeric codes.
+    #[serde(default)]
+    pub high_risk_countries: Vec<String>,
+}
+
+fn default_high_risk_mccs() -> Vec<String> {
+    ["4829", "5967", "6051", "6211", "7995"]
+        .iter()
+        .map(|mcc| mcc.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FraudScore {
+    pub score: u16,
+    /// Two-character reason for the dominant risk factor.
+    pub reason: &'static str,
+}
+
+impl FraudConfig {
+    pub fn score(&self, message: &impl FieldSource) -> FraudScore {
+        let mut factors: Vec<(u16, &'static str)> = Vec::new();
+
+        // Up to 300 points, saturating at 5,000.00 in major units of any
+        // currency: the amount is compared in hundredths.
+        let amount = message
+            .field("de49")
+            .and_then(currency::lookup)
+            .zip(message.field("de4"))
+            .and_then(|(currency, de4)| Amount::parse(de4, currency))
+            .map_or(0, |amount| amount.scaled(2).clamp(0, 500_000));
+        factors.push(((amount * 300 / 500_000) as u16, "AM"));
+
+        let mcc = message.field("de18").unwrap_or_default();
+        if self.high_risk_mccs.iter().any(|m| m == mcc) {
+            factors.push((250, "MC"));
+        }
+
+        if let Some(country) = country::merchant_country(message) {
+            if self.high_risk_countries.contains(&country) {
+                factors.push((200, "CT"));
+            }
+        }
+
+        let entry_mode_points = match message.field("de22").and_then(|d| d.get(0..2)) {
+            Some("01") => 150,             // manual key entry
+            Some("81") => 100,             // e-commerce
+            Some("02") | Some("90") => 80, // magnetic stripe
+            _ => 0,
+        };
+        factors.push((entry_mode_points, "EM"));
+
+        let jitter = (fnv1a(message.field("de2").unwrap_or_default()) % 50) as u16;
+        let score = (factors.iter().map(|(points, _)| points).sum::<u16>() + jitter).min(999);
+        let reason = factors
+            .iter()
+            .max_by_key(|(points, _)| *points)
+            .filter(|(points, _)| *points > 0)
+            .map(|(_, reason)| *reason)
+            .unwrap_or("LR");
+
+        FraudScore { score, reason }
+    }
+
+    pub fn should_decline(&self, score: &FraudScore) -> bool {
+        self.decline_threshold
+            .is_some_and(|threshold| score.score > threshold)
+    }
+}
+
+impl FraudScore {
+    pub fn subelement_value(&self) -> String {
+        crate::de48::encode_subfields(&[("01", &format!("{:03}", self.score)), ("02", self.reason)])
+    }
+}
+
+fn fnv1a(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,232 @@
+use axum::body::{self, Body};
+use axum::extract::ConnectInfo;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::{Map, Value};
+use std::net::SocketAddr;
+use tonic::metadata::{KeyAndValueRef, MetadataMap};
+use tonic::{Code, Status};
+use tower::ServiceExt;
+use tracing::warn;
+
+use crate::iso8583;
+
+// ============================================================================
+// gRPC Service (feature `grpc`)
+// ============================================================================
+//
+// For client teams whose stacks are gRPC-first, the `MockIssuer` service of
+// proto/mock.proto is served on GRPC_PORT next to the HTTP API. Each call is
+// turned into the matching REST request and sent through the same router,
+// so rules, validation, middleware and storage behave exactly as over HTTP:
+//
+//   Authorize  POST /authorize         Financial  POST /completion
+//   Reverse    POST /reversal          Query      GET /transactions/{rrn}
+//
+// Request metadata is passed on as HTTP headers (correlation IDs, acquirer
+// signatures). A signature covers the JSON form of the message: `mti`, then
+// the DEs in ascending order. HTTP errors come back as gRPC statuses with
+// the error body as their message.
+
+pub mod proto {
+    tonic::include_proto!("mastercard.mock.v1");
+}
+
+use proto::mock_issuer_server::{MockIssuer, MockIssuerServer};
+use proto::{IsoMessage, QueryRequest, Transaction};
+
+const BODY_LIMIT: usize = 1024 * 1024;
+
+pub struct GrpcService {
+    app: Router,
+}
+
+impl GrpcService {
+    pub fn new(app: Router) -> MockIssuerServer<Self> {
+        MockIssuerServer::new(GrpcService { app })
+    }
+
+    /// Sends one REST request through the router and returns its JSON body.
+    async fn call(
+        &self,
+        method: Method,
+        uri: &str,
+        metadata: &MetadataMap,
+        peer: Option<SocketAddr>,
+        body: Option<Value>,
+    ) -> Result<Value, Status> {
+        let mut request = Request::builder().method(method).uri(uri);
+        for entry in metadata.iter() {
+            if let KeyAndValueRef::Ascii(key, value) = entry {
+                let name = key.as_str();
+                if name.starts_with("grpc-") || ["content-type", "te"].contains(&name) {
+                    continue;
+                }
+                request = request.header(name, value.as_encoded_bytes());
+            }
+        }
+        let body = match body {
+            Some(json) => {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        let mut request = request
+            .body(body)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        if let Some(peer) = peer {
+            request.extensions_mut().insert(ConnectInfo(peer));
+        }
+
+        let response = self
+            .app
+            .clone()
+            .oneshot(request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), BODY_LIMIT)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        if !status.is_success() {
+            let message = String::from_utf8_lossy(&bytes).into_owned();
+            return Err(Status::new(code(status), message));
+        }
+        serde_json::from_slice(&bytes).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn message(
+        &self,
+        uri: &str,
+        request: tonic::Request<IsoMessage>,
+    ) -> Result<tonic::Response<IsoMessage>, Status> {
+        let peer = request.remote_addr();
+        let json = to_json(request.get_ref());
+        let reply = self
+            .call(Method::POST, uri, request.metadata(), peer, Some(json))
+            .await?;
+        Ok(tonic::Response::new(from_json(&reply)))
+    }
+}
+
+fn code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::AlreadyExists,
+        StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        _ => Code::Internal,
+    }
+}
+
+/// The JSON API form of a message: `mti`, then `deN` in DE order.
+fn to_json(message: &IsoMessage) -> Value {
+    let mut json = Map::new();
+    json.insert("mti".to_string(), Value::from(message.mti.clone()));
+    let mut fields: Vec<_> = message.fields.iter().collect();
+    fields.sort();
+    for (number, value) in fields {
+        json.insert(format!("de{}", number), Value::from(value.clone()));
+    }
+    Value::Object(json)
+}
+
+fn from_json(json: &Value) -> IsoMessage {
+    let mut message = IsoMessage::default();
+    let Some(object) = json.as_object() else {
+        return message;
+    };
+    for (key, value) in object {
+        let Some(value) = value.as_str() else {
+            continue;
+        };
+        match key.as_str() {
+            "mti" => message.mti = value.to_string(),
+            "response_message" => message.response_message = value.to_string(),
+            _ => {
+                if let Some(number) = iso8583::parse_key(key) {
+                    message.fields.insert(number.into(), value.to_string());
+                }
+            }
+        }
+    }
+    message
+}
+
+#[tonic::async_trait]
+impl MockIssuer for GrpcService {
+    async fn authorize(
+        &self,
+        request: tonic::Request<IsoMessage>,
+    ) -> Result<tonic::Response<IsoMessage>, Status> {
+        self.message("/authorize", request).await
+    }
+
+    async fn reverse(
+        &self,
+        request: tonic::Request<IsoMessage>,
+    ) -> Result<tonic::Response<IsoMessage>, Status> {
+        self.message("/reversal", request).await
+    }
+
+    async fn financial(
+        &self,
+        request: tonic::Request<IsoMessage>,
+    ) -> Result<tonic::Response<IsoMessage>, Status> {
+        self.message("/completion", request).await
+    }
+
+    async fn query(
+        &self,
+        request: tonic::Request<QueryRequest>,
+    ) -> Result<tonic::Response<Transaction>, Status> {
+        let rrn = &request.get_ref().rrn;
+        if rrn.is_empty() || !rrn.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Status::invalid_argument("rrn must be alphanumeric"));
+        }
+        let json = self
+            .call(
+                Method::GET,
+                &format!("/transactions/{}", rrn),
+                request.metadata(),
+                request.remote_addr(),
+                None,
+            )
+            .await?;
+        let stored: crate::Transaction =
+            serde_json::from_value(json).map_err(|e| Status::internal(e.to_string()))?;
+        let state = serde_json::to_value(stored.state)
+            .ok()
+            .and_then(|state| state.as_str().map(str::to_string))
+            .unwrap_or_default();
+        Ok(tonic::Response::new(Transaction {
+            pan: stored.pan,
+            amount: stored.amount,
+            stan: stored.stan,
+            rrn: stored.rrn,
+            acquirer_id: stored.acquirer_id,
+            response_code: stored.response_code.to_string(),
+            state,
+            captured_amount: stored.captured_amount.unwrap_or_default(),
+            trace_id: stored.trace_id.unwrap_or_default(),
+            request: stored.request.as_ref().map(from_json),
+            response: stored.response.as_ref().map(from_json),
+        }))
+    }
+}
+
+/// Serves the gRPC service on `addr` until the process exits.
+pub async fn serve(addr: SocketAddr, app: Router) {
+    if let Err(e) = tonic::transport::Server::builder()
+        .add_service(GrpcService::new(app))
+        .serve(addr)
+        .await
+    {
+        warn!("gRPC server on {} stopped: {}", addr, e);
+    }
+}
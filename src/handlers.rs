@@ -0,0 +1,1114 @@
+use axum::{
+    extract::{ConnectInfo, Json, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{debug, info, info_span, instrument, warn};
+
+use crate::acquirers;
+use crate::amount::{self, Amount};
+use crate::bins::WithBin;
+use crate::cards::{self, PinCheck};
+use crate::cutover;
+use crate::dcc::{self, DccIssue};
+use crate::de48::De48;
+use crate::de7;
+use crate::deferred;
+use crate::error::{ApiJson, ErrorBody};
+use crate::expiry;
+use crate::installments;
+use crate::merchants::WithMerchant;
+use crate::originals::{self, OriginalReference};
+use crate::pan;
+use crate::processing::{self, MessageMode};
+use crate::rate_limit::{self, Verdict};
+use crate::refunds;
+use crate::rules::{Action, FieldSource};
+use crate::store::{Capture, Transition};
+use crate::stored_credentials;
+use crate::tokenization::TokenizationRequest;
+use crate::tokens::{Detokenized, TokenIssue};
+use crate::validation::ValidationMode;
+use crate::webhooks::{Event, EventKind};
+use crate::{
+    contactless, correlation, country, currency, fallback, fraud, issuers, latency, masking, ucaf,
+    validation,
+};
+use crate::{
+    generate_rrn, transaction_key, AppState, AuthorizationRequest, AuthorizationResponse,
+    CompletionRequest, CompletionResponse, Iso8583Message, ResponseCode, ReversalRequest,
+    ReversalResponse, Transaction, TransactionState,
+};
+
+// ============================================================================
+// Request Handlers
+// ============================================================================
+
+#[utoipa::path(
+    post,
+    path = "/authorize",
+    tag = "messages",
+    request_body = AuthorizationRequest,
+    responses(
+        (status = 200, description = "0110 (or 0210) response; DE39 carries the decision", body = AuthorizationResponse),
+        (status = 422, description = "Malformed message", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(mti = %payload.mti, stan = %payload.de11, acquirer = %payload.de32, correlation_id = tracing::field::Empty))]
+pub async fn authorize(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(mut payload): ApiJson<AuthorizationRequest>,
+) -> Response {
+    let correlation_id = correlation::attach(&headers, Some(&payload.de48));
+    let validation_mode = ValidationMode::requested(&headers, state.validation_mode);
+
+    match state.throttle(peer, &payload.de32) {
+        Verdict::Allow => {}
+        Verdict::Decline(code) => {
+            let response = AuthorizationResponse::echo(&payload, code, rate_limit::DECLINE_MESSAGE);
+            return authorization_reply(&state, response, None);
+        }
+        Verdict::Reject(response) => return response,
+    }
+
+    // Phase spans are entered only around synchronous sections and dropped
+    // before every await.
+    let phase = info_span!("parse").entered();
+
+    let rules = state.rules();
+    let mode = rules.message_mode(&payload.de2);
+    if payload.mti != mode.request_mti() {
+        let response = AuthorizationResponse {
+            mti: processing::response_mti(&payload.mti).to_string(),
+            de2: payload.de2.clone(),
+            de3: payload.de3.clone(),
+            de4: payload.de4.clone(),
+            de6: payload.de6.clone(),
+            de7: payload.de7.clone(),
+            de11: payload.de11.clone(),
+            de15: None,
+            de18: payload.de18.clone(),
+            de32: payload.de32.clone(),
+            de37: payload.de37.clone(),
+            de38: None,
+            de39: ResponseCode::InvalidTransaction,
+            de41: payload.de41.clone(),
+            de42: payload.de42.clone(),
+            de44: None,
+            de48: payload.de48.clone(),
+            de49: payload.de49.clone(),
+            de51: payload.de51.clone(),
+            de61: payload.de61.clone(),
+            de63: None,
+            de112: None,
+            response_message: "Invalid MTI for Authorization Request".to_string(),
+        };
+
+        return authorization_reply(&state, response, None);
+    }
+
+    let acquirer = rules.acquirers.get(&payload.de32);
+    if let Some(acquirer) = acquirer {
+        acquirer.fill_currency(&mut payload.de49);
+    }
+
+    if let Err(violation) = state.validate(&Iso8583Message::from(payload.clone()), validation_mode)
+    {
+        info!("Format error in authorization: {}", violation);
+        let mut response = AuthorizationResponse::echo(
+            &payload,
+            ResponseCode::FormatError,
+            validation::FORMAT_ERROR_MESSAGE,
+        );
+        response.de44 = Some(violation.de44());
+        return authorization_reply(&state, response, None);
+    }
+    let currency = currency::lookup(&payload.de49).expect("validated DE49 is a known currency");
+
+    if acquirer.is_some_and(|acquirer| !acquirer.mcc_permitted(&payload.de18)) {
+        info!(
+            "MCC {} not permitted for acquirer {}",
+            payload.de18,
+            acquirers::label(&payload.de32, acquirer)
+        );
+        let response = AuthorizationResponse::echo(
+            &payload,
+            ResponseCode::NotPermittedToTerminal,
+            "Transaction Not Permitted to Acquirer",
+        );
+        return authorization_reply(&state, response, None);
+    }
+
+    let amount = Amount::parse(&payload.de4, currency).unwrap_or(Amount::new(0, currency));
+    match dcc::check(
+        rules.dcc.as_ref(),
+        &amount,
+        payload.de6.as_deref(),
+        payload.de51.as_deref(),
+    ) {
+        Ok(Some(markup)) => info!("DCC markup {:.2}%", markup),
+        Ok(None) => {}
+        Err(issue) => {
+            info!("DCC authorization declined: {:?}", issue);
+            let (code, message) = issue.decline();
+            let mut response = AuthorizationResponse::echo(&payload, code, message);
+            if let DccIssue::Missing(number) = issue {
+                response.de44 = Some(format!("{:03}", number));
+            }
+            return authorization_reply(&state, response, None);
+        }
+    }
+
+    if let Some(stans) = &state.stans {
+        // The clock may have moved since validation read DE7.
+        let Some(transmitted_at) = de7::parse(&payload.de7, state.clock.now()) else {
+            info!("DE7 {} is not a date on the clock", payload.de7);
+            let mut response = AuthorizationResponse::echo(
+                &payload,
+                ResponseCode::FormatError,
+                validation::FORMAT_ERROR_MESSAGE,
+            );
+            response.de44 = Some("007".to_string());
+            return authorization_reply(&state, response, None);
+        };
+        let day = transmitted_at.date_naive();
+        if !stans.record(&payload.de32, day, &payload.de11) {
+            info!(
+                "STAN {} already used by acquirer {} on {}",
+                payload.de11, payload.de32, day
+            );
+            let response = AuthorizationResponse::echo(
+                &payload,
+                ResponseCode::DuplicateTransmission,
+                "Duplicate Transmission",
+            );
+            return authorization_reply(&state, response, None);
+        }
+    }
+
+    drop(phase);
+    let phase = info_span!("card_checks").entered();
+
+    // A TAR names the card being provisioned in DE2, not a token.
+    let tokenization = TokenizationRequest::parse(&payload.de48);
+    let detokenized = match tokenization {
+        Some(_) => Ok(None),
+        None => state.tokens.detokenize(&payload.de2, &payload.de48),
+    };
+    let mut token = match detokenized {
+        Ok(token) => token,
+        Err(issue) => {
+            info!(
+                "Token {} not usable: {:?}",
+                masking::pan_for_log(&payload.de2),
+                issue
+            );
+            let (code, message) = match issue {
+                TokenIssue::Unknown | TokenIssue::RequestorMismatch => {
+                    (ResponseCode::InvalidCardNumber, "Invalid Card Number")
+                }
+                TokenIssue::Suspended => (ResponseCode::RestrictedCard, "Restricted Card"),
+            };
+            let response = AuthorizationResponse::echo(&payload, code, message);
+            return authorization_reply(&state, response, None);
+        }
+    };
+    if let Some(token) = &mut token {
+        info!(
+            "Token {} de-tokenized to {}",
+            masking::pan_for_log(&token.token),
+            masking::pan_for_log(&token.pan)
+        );
+        payload.de2 = token.pan.clone();
+        if let Some(result) = token.validate_dsrp(&payload) {
+            info!("DSRP cryptogram {:?}", result);
+            if let Some((code, message)) = result.decline() {
+                let response = AuthorizationResponse::echo(&payload, code, message);
+                return authorization_reply(&state, response, Some(token));
+            }
+        }
+    }
+
+    if let Err(issue) = pan::check(&payload.de2, &state.test_bins) {
+        info!(
+            "Invalid card number {}: {}",
+            masking::pan_for_log(&payload.de2),
+            issue
+        );
+        let response = AuthorizationResponse::echo(
+            &payload,
+            ResponseCode::InvalidCardNumber,
+            "Invalid Card Number",
+        );
+        return authorization_reply(&state, response, token.as_ref());
+    }
+
+    let profile = state.cards.profile(&payload.de2).unwrap_or_default();
+    if let Some(request) = tokenization {
+        let decision = request.decide(profile.status, &state.rules().tokenization);
+        info!(
+            "Tokenization request for {} (device score {:?}): {:?}",
+            masking::pan_for_log(&payload.de2),
+            request.device_score,
+            decision
+        );
+        let response =
+            AuthorizationResponse::echo(&payload, decision.response_code(), decision.message());
+        return authorization_reply(&state, response, None);
+    }
+
+    if let Some(code) = profile.status.response_code() {
+        info!("Card status {:?}, declining with {}", profile.status, code);
+        let response =
+            AuthorizationResponse::echo(&payload, code, profile.status.response_message());
+        return authorization_reply(&state, response, token.as_ref());
+    }
+
+    if profile.blocked_mccs.contains(&payload.de18) {
+        info!("MCC {} blocked for this card", payload.de18);
+        let response = AuthorizationResponse::echo(
+            &payload,
+            ResponseCode::NotPermittedToCardholder,
+            "Transaction Not Permitted to Cardholder",
+        );
+        return authorization_reply(&state, response, token.as_ref());
+    }
+    if profile.flagged_mccs.contains(&payload.de18) {
+        info!("MCC {} flagged for review on this card", payload.de18);
+    }
+
+    if let (Some(expected), Some(pin)) = (&profile.pin, &payload.de52) {
+        let counter_key = cards::counter_key(&payload.de32, &payload.de2);
+        let decline = match state.cards.verify_pin(&counter_key, expected, pin) {
+            PinCheck::Correct => None,
+            PinCheck::Incorrect => Some((ResponseCode::InvalidPin, "Incorrect PIN")),
+            PinCheck::TriesExceeded => Some((
+                ResponseCode::PinTriesExceeded,
+                "Allowable PIN Tries Exceeded",
+            )),
+        };
+        if let Some((code, message)) = decline {
+            warn!(
+                "PIN check failed for {}: {}",
+                masking::pan_for_log(&payload.de2),
+                message
+            );
+            let response = AuthorizationResponse::echo(&payload, code, message);
+            return authorization_reply(&state, response, token.as_ref());
+        }
+    }
+
+    let merchant_country = country::merchant_country(&payload);
+    if !profile.country_permitted(merchant_country.as_deref()) {
+        info!(
+            "Merchant country {} not permitted for this card",
+            merchant_country.as_deref().unwrap_or("?")
+        );
+        let response =
+            AuthorizationResponse::echo(&payload, ResponseCode::RestrictedCard, "Restricted Card");
+        return authorization_reply(&state, response, token.as_ref());
+    }
+
+    if !profile.currency_permitted(currency) {
+        info!("Currency {} not permitted for this card", currency.alpha);
+        let response = AuthorizationResponse::echo(
+            &payload,
+            ResponseCode::NotPermittedToCardholder,
+            "Transaction Not Permitted to Cardholder",
+        );
+        return authorization_reply(&state, response, token.as_ref());
+    }
+
+    let installment = match installments::requested(&payload) {
+        Ok(installment) => installment,
+        Err(number) => {
+            info!("Malformed installment plan in DE{}", number);
+            let mut response = AuthorizationResponse::echo(
+                &payload,
+                ResponseCode::FormatError,
+                validation::FORMAT_ERROR_MESSAGE,
+            );
+            response.de44 = Some(format!("{:03}", number));
+            return authorization_reply(&state, response, token.as_ref());
+        }
+    };
+    if let Some(installment) = &installment {
+        let amount = amount::parse_minor_units(&payload.de4).unwrap_or(0);
+        let permitted = profile
+            .installments
+            .as_ref()
+            .is_some_and(|policy| policy.permits(installment, amount));
+        if !permitted {
+            info!(
+                "Installment plan {:?} x{} not permitted for this card",
+                installment.plan, installment.count
+            );
+            let response = AuthorizationResponse::echo(
+                &payload,
+                ResponseCode::NotPermittedToCardholder,
+                installments::DECLINE_MESSAGE,
+            );
+            return authorization_reply(&state, response, token.as_ref());
+        }
+    }
+
+    if stored_credentials::is_merchant_initiated(&payload) {
+        let Some(trace_id) = stored_credentials::initial_trace_id(&payload.de48) else {
+            info!("MIT without a valid initial trace ID in DE48 SE63");
+            let mut response = AuthorizationResponse::echo(
+                &payload,
+                ResponseCode::FormatError,
+                validation::FORMAT_ERROR_MESSAGE,
+            );
+            response.de44 = Some("048".to_string());
+            return authorization_reply(&state, response, token.as_ref());
+        };
+        let transactions = state
+            .authorized_transactions
+            .find_by_trace_id(&trace_id)
+            .unwrap_or_else(|e| {
+                warn!("Cannot look up transactions for MIT check: {}", e);
+                Vec::new()
+            });
+        match stored_credentials::find_initial(&transactions, &payload.de2, &trace_id) {
+            Some(initial) => info!("MIT refers to CIT {}", initial.key()),
+            None => {
+                info!(
+                    "MIT trace ID {} matches no prior CIT of this card",
+                    trace_id
+                );
+                let response = AuthorizationResponse::echo(
+                    &payload,
+                    ResponseCode::InvalidTransaction,
+                    stored_credentials::NO_PRIOR_CIT_MESSAGE,
+                );
+                return authorization_reply(&state, response, token.as_ref());
+            }
+        }
+    }
+
+    let is_credit = payload.processing_code().is_ok_and(|code| code.is_credit());
+    let refunded = match refunds::original_reference(&payload).filter(|_| is_credit) {
+        None => None,
+        Some(reference) => {
+            let amount = amount::parse_minor_units(&payload.de4).unwrap_or(0);
+            match refunds::find_original(
+                state.authorized_transactions.as_ref(),
+                &reference,
+                amount,
+                &rules.refunds,
+            ) {
+                Ok(original) => {
+                    info!("Credit refunds {}", original.key());
+                    Some(original.key())
+                }
+                Err((code, message)) => {
+                    info!("Linked refund declined: {}", message);
+                    let response = AuthorizationResponse::echo(&payload, code, message);
+                    return authorization_reply(&state, response, token.as_ref());
+                }
+            }
+        }
+    };
+
+    drop(phase);
+    let phase = info_span!("rules").entered();
+
+    let bin = state.bins.lookup(&payload.de2);
+    let (rule_name, action) = rules.evaluate(&WithMerchant {
+        message: &WithBin {
+            message: &payload,
+            bin,
+        },
+        registry: &rules.merchants,
+    });
+    info!("Rule matched: {}", rule_name.unwrap_or("<default>"));
+
+    let mut action = action.clone();
+    if let Some(standalone) = rules.refunds.standalone.as_ref() {
+        if is_credit && refunded.is_none() {
+            info!("Standalone credit, answered by refunds.standalone");
+            action = standalone.clone();
+        }
+    }
+    let mut message_override = None;
+    if let Some(script) = &state.script {
+        match script.run(&payload) {
+            Ok(Some(script_override)) => {
+                script_override.apply(&mut action);
+                message_override = script_override.response_message;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Script hook failed, using rule result: {}", e),
+        }
+    }
+
+    let issuer = rules.issuer(&payload.de2);
+    let stand_in = issuer
+        .and_then(|issuer| issuer.stip.as_ref())
+        .filter(|stip| stip.issuer_unavailable());
+    if let (Some(issuer), Some(stip)) = (issuer, stand_in) {
+        let code = stip.decide(payload.de4.parse().unwrap_or(u64::MAX));
+        info!(
+            "Issuer {} unavailable, stand-in answers {}",
+            issuer.name, code
+        );
+        action = Action::respond(code);
+        message_override = code
+            .is_approval()
+            .then(|| issuers::STAND_IN_MESSAGE.to_string());
+    }
+
+    if action.response_code.is_approval() {
+        if let Some(code) = state.random_decline().and_then(|r| r.roll()) {
+            info!("Random decline triggered: {}", code);
+            action.response_code = code;
+            action.partial_amount = None;
+        }
+    }
+
+    if ucaf::is_ecommerce(&payload) {
+        let de48 = De48::parse(&payload.de48).unwrap_or_default();
+        match ucaf::validate(&de48) {
+            Err(code) => {
+                info!("Invalid UCAF/SLI data in DE48");
+                action.response_code = code;
+                action.partial_amount = None;
+            }
+            Ok(authentication) => {
+                info!("E-commerce authentication: {:?}", authentication);
+                if action.require_3ds
+                    && authentication != ucaf::Authentication::Authenticated
+                    && action.response_code.is_approval()
+                {
+                    action.response_code = ResponseCode::AuthenticationRequired;
+                    action.partial_amount = None;
+                }
+            }
+        }
+    }
+
+    if contactless::is_contactless(&payload) && action.response_code.is_approval() {
+        let amount = Amount::parse(&payload.de4, currency).unwrap_or(Amount::new(0, currency));
+        if rules
+            .contactless
+            .requires_step_up(&amount, payload.de52.is_some())
+        {
+            info!("Contactless amount above no-CVM limit, requesting step-up");
+            action.response_code = ResponseCode::AuthenticationRequired;
+            action.partial_amount = None;
+        }
+    }
+
+    if fallback::is_fallback(&payload) {
+        let count = state
+            .cards
+            .record_fallback(&cards::counter_key(&payload.de32, &payload.de2));
+        info!("Chip fallback detected ({} for this card)", count);
+        if let Some(config) = &rules.chip_fallback {
+            if config.should_decline(count) && action.response_code.is_approval() {
+                action.response_code = config.decline_code;
+                action.partial_amount = None;
+            }
+        }
+    }
+
+    let fraud_score = rules
+        .fraud_scoring
+        .as_ref()
+        .map(|fraud| (fraud, fraud.score(&payload)));
+    if let Some((fraud, score)) = &fraud_score {
+        info!("Fraud score: {} ({})", score.score, score.reason);
+        if action.response_code.is_approval() && fraud.should_decline(score) {
+            action.response_code = ResponseCode::SuspectedFraud;
+            action.partial_amount = None;
+        }
+    }
+
+    drop(phase);
+
+    let latency = action
+        .latency()
+        .or_else(|| {
+            issuer
+                .filter(|_| stand_in.is_none())
+                .and_then(|issuer| issuer.latency.clone())
+        })
+        .or_else(|| rules.endpoint_latency.authorize.clone());
+    latency::inject(latency.as_ref()).await;
+
+    let mut response_code = action.response_code;
+    let mut approved_amount = action
+        .partial_amount
+        .clone()
+        .unwrap_or_else(|| payload.de4.clone());
+
+    let phase = info_span!("ledger").entered();
+
+    let key = transaction_key(&payload.de32, &payload.de11);
+    if response_code.is_approval() {
+        match Amount::parse(&approved_amount, currency) {
+            Some(amount) if is_credit => {
+                let recorded = refunded.as_ref().map_or(Ok(()), |original| {
+                    refunds::record(
+                        state.authorized_transactions.as_ref(),
+                        original,
+                        amount.minor_units,
+                        &rules.refunds,
+                    )
+                });
+                match recorded {
+                    Ok(()) => {
+                        info!("Crediting {}", amount);
+                        state.ledger.credit(&payload.de2, amount.minor_units);
+                    }
+                    Err((code, message)) => {
+                        info!("Linked refund declined: {}", message);
+                        response_code = code;
+                        approved_amount = payload.de4.clone();
+                        message_override = Some(message.to_string());
+                    }
+                }
+            }
+            Some(amount) => {
+                let funded = match mode {
+                    MessageMode::Dual => state.ledger.hold(&payload.de2, &key, amount.minor_units),
+                    MessageMode::Single => state.ledger.debit(&payload.de2, amount.minor_units),
+                };
+                if funded.is_err() {
+                    info!(
+                        "Insufficient funds for {} to {}",
+                        amount,
+                        masking::pan_for_log(&payload.de2)
+                    );
+                    response_code = ResponseCode::InsufficientFunds;
+                    approved_amount = payload.de4.clone();
+                }
+            }
+            None => {
+                response_code = ResponseCode::InvalidAmount;
+                approved_amount = payload.de4.clone();
+            }
+        }
+    }
+
+    drop(phase);
+    let phase = info_span!("respond").entered();
+
+    let mut de48 = payload.de48.clone();
+    if let Some((_, score)) = &fraud_score {
+        match De48::parse(&payload.de48) {
+            Ok(mut parsed) => {
+                parsed.set(fraud::FRAUD_SUBELEMENT, &score.subelement_value());
+                de48 = parsed.encode();
+            }
+            Err(e) => warn!("DE48 not parseable, fraud score not returned: {}", e),
+        }
+    }
+
+    let rrn = payload
+        .de37
+        .clone()
+        .unwrap_or_else(|| generate_rrn(&payload.de11, state.clock.now()));
+    let settlement_date = state.settlement.open_day();
+
+    let trace_id = response_code
+        .is_approval()
+        .then(|| stored_credentials::trace_id(&payload.de11, &payload.de7));
+    let installment_terms = installment
+        .filter(|_| response_code.is_approval())
+        .zip(profile.installments.as_ref())
+        .map(|(installment, policy)| {
+            let amount = amount::parse_minor_units(&approved_amount).unwrap_or(0);
+            policy.terms(&installment, amount)
+        });
+
+    let mut response = AuthorizationResponse {
+        mti: mode.response_mti().to_string(),
+        de2: payload.de2.clone(),
+        de3: payload.de3.clone(),
+        de4: approved_amount.clone(),
+        de6: payload
+            .de6
+            .as_deref()
+            .map(|de6| dcc::scale_original(de6, &payload.de4, &approved_amount)),
+        de7: payload.de7.clone(),
+        de11: payload.de11.clone(),
+        de15: settlement_date.map(cutover::de15),
+        de18: payload.de18.clone(),
+        de32: payload.de32.clone(),
+        de37: Some(rrn.clone()),
+        de38: response_code
+            .is_approval()
+            .then(originals::generate_approval_code),
+        de39: response_code,
+        de41: payload.de41.clone(),
+        de42: payload.de42.clone(),
+        de44: None,
+        de48,
+        de49: payload.de49.clone(),
+        de51: payload.de51.clone(),
+        de61: payload.de61.clone(),
+        de63: trace_id.clone(),
+        de112: installment_terms
+            .as_ref()
+            .map(|terms| terms.de112(payload.field("de112"))),
+        response_message: message_override.unwrap_or_else(|| match response_code {
+            ResponseCode::Approved => "Transaction Approved".to_string(),
+            ResponseCode::PartialApproval => "Partial Approval".to_string(),
+            ResponseCode::InvalidAmount => "Invalid Amount".to_string(),
+            ResponseCode::InsufficientFunds => "Insufficient Funds".to_string(),
+            ResponseCode::FormatError => "Format Error".to_string(),
+            ResponseCode::SuspectedFraud => "Suspected Fraud".to_string(),
+            ResponseCode::AuthenticationRequired => "Authentication Required".to_string(),
+            _ => "Transaction Not Authorized".to_string(),
+        }),
+    };
+    if let Some(token) = &token {
+        token.restore(&mut response);
+    }
+
+    let amount = Amount::parse(&response.de4, currency)
+        .map_or_else(|| response.de4.clone(), |amount| amount.to_string());
+    info!(de39 = %response.de39, %amount, "authorization response");
+    debug!(response = %masking::payload_for_log(&response), "authorization response");
+
+    drop(phase);
+    let phase = info_span!("storage").entered();
+
+    if response_code.is_approval() {
+        let posted = mode == MessageMode::Single;
+        let recorded_at = state.clock.now();
+        let transaction = Transaction {
+            pan: payload.de2.clone(),
+            captured_amount: posted.then(|| approved_amount.clone()),
+            captures: Vec::new(),
+            refunded_amount: None,
+            refund_of: refunded,
+            terminal_id: payload.de41.as_deref().map(|id| id.trim_end().to_string()),
+            card_acceptor_id: payload.de42.as_deref().map(|id| id.trim_end().to_string()),
+            amount: approved_amount,
+            stan: payload.de11.clone(),
+            timestamp: payload.de7.clone(),
+            transmitted_at: de7::parse(&payload.de7, recorded_at),
+            response_code,
+            recorded_at,
+            rrn,
+            request: serde_json::to_value(&payload)
+                .ok()
+                .map(masking::mask_payload),
+            response: serde_json::to_value(&response)
+                .ok()
+                .map(masking::mask_payload),
+            state: if posted {
+                TransactionState::Captured
+            } else {
+                TransactionState::Authorized
+            },
+            reversal_de90: None,
+            acquirer_id: payload.de32.clone(),
+            correlation_id,
+            token: token.as_ref().map(|token| masking::mask_pan(&token.token)),
+            installments: installment_terms,
+            trace_id,
+            settlement_date,
+            expires_at: (!posted)
+                .then(|| state.hold_expires_at(&payload.de18, recorded_at))
+                .flatten(),
+        };
+
+        if let Err(e) = state.authorized_transactions.insert(&key, transaction) {
+            warn!("Failed to store transaction {}: {}", payload.de11, e);
+        }
+    }
+
+    drop(phase);
+
+    if let Some(delay) = &action.defer {
+        return deferred::defer(&state, response, delay);
+    }
+
+    notify_authorization(&state, &response, None);
+
+    if let Some(fault) = &action.fault {
+        return fault.render(&response).await;
+    }
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// `reference` is set when a deferred authorization is delivered.
+pub(crate) fn notify_authorization(
+    state: &AppState,
+    response: &AuthorizationResponse,
+    reference: Option<&str>,
+) {
+    state
+        .monitor
+        .record_authorization(response.de39.is_approval());
+    let kind = if response.de39.is_approval() {
+        EventKind::AuthorizationApproved
+    } else {
+        EventKind::AuthorizationDeclined
+    };
+    state.publish_event(Event::new(kind, response).with_reference(reference));
+}
+
+/// Sends an early 0110 decline and notifies webhooks.
+fn authorization_reply(
+    state: &AppState,
+    mut response: AuthorizationResponse,
+    token: Option<&Detokenized>,
+) -> Response {
+    if let Some(token) = token {
+        token.restore(&mut response);
+    }
+    notify_authorization(state, &response, None);
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/reversal",
+    tag = "messages",
+    request_body = ReversalRequest,
+    responses(
+        (status = 200, description = "0410 response", body = ReversalResponse),
+        (status = 422, description = "Malformed message", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(mti = %payload.mti, stan = %payload.de11, acquirer = %payload.de32, correlation_id = tracing::field::Empty))]
+pub async fn reversal(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(mut payload): ApiJson<ReversalRequest>,
+) -> Response {
+    correlation::attach(&headers, Some(&payload.de48));
+    let validation_mode = ValidationMode::requested(&headers, state.validation_mode);
+
+    let throttled = match state.throttle(peer, &payload.de32) {
+        Verdict::Allow => None,
+        Verdict::Decline(code) => Some(code),
+        Verdict::Reject(response) => return response,
+    };
+
+    if payload.mti != "0400" {
+        let response = ReversalResponse::echo(
+            &payload,
+            ResponseCode::InvalidTransaction,
+            "Invalid MTI for Reversal Request",
+        );
+        return (StatusCode::OK, Json(response)).into_response();
+    }
+
+    if let Some(acquirer) = state.rules().acquirers.get(&payload.de32) {
+        acquirer.fill_currency(&mut payload.de49);
+    }
+    if let Err(violation) = state.validate(&Iso8583Message::from(payload.clone()), validation_mode)
+    {
+        info!("Format error in reversal: {}", violation);
+        let mut response = ReversalResponse::echo(
+            &payload,
+            ResponseCode::FormatError,
+            validation::FORMAT_ERROR_MESSAGE,
+        );
+        response.de44 = Some(violation.de44());
+        return (StatusCode::OK, Json(response)).into_response();
+    }
+
+    if let Some(code) = &throttled {
+        let response = ReversalResponse::echo(&payload, *code, rate_limit::DECLINE_MESSAGE);
+        return (StatusCode::OK, Json(response)).into_response();
+    }
+
+    latency::inject(state.rules().endpoint_latency.reversal.as_ref()).await;
+
+    let phase = info_span!("storage").entered();
+
+    let reference = OriginalReference {
+        de32: payload.de32.clone(),
+        de11: Some(payload.de11.clone()),
+        de90: Some(payload.de90.clone()),
+        de37: payload.additional.field("de37").map(str::to_string),
+        de38: payload.additional.field("de38").map(str::to_string),
+        de2: Some(payload.de2.clone()),
+    };
+    let original = reference
+        .find(
+            state.authorized_transactions.as_ref(),
+            &state.reversal_match(&payload.de32),
+        )
+        .unwrap_or_else(|e| {
+            warn!("Failed to look up transaction {}: {}", payload.de11, e);
+            None
+        })
+        .map(|(matched_by, original)| {
+            info!(?matched_by, original = %original.key(), "original found");
+            original
+        });
+    let now = state.clock.now();
+
+    let (response_code, response_message) = match original {
+        None => (ResponseCode::DuplicateTransmission, "Original Not Found"),
+        Some(original) => {
+            let key = original.key();
+            let reversed = state.authorized_transactions.transition(
+                &key,
+                now,
+                TransactionState::Reversed,
+                |original| {
+                    original.reversal_de90 = Some(payload.de90.clone());
+                    let captured = original.captured_amount.is_some();
+                    Ok::<_, Infallible>((
+                        original.pan.clone(),
+                        captured.then(|| original.captured_total()),
+                    ))
+                },
+            );
+            match reversed {
+                Ok(Transition::Moved((pan, captured))) => {
+                    // Also frees what partial captures left of the hold.
+                    state.ledger.release(&pan, &key);
+                    if let Some(captured) = captured {
+                        state.ledger.credit(&pan, captured);
+                    }
+                    (ResponseCode::Approved, "Reversal Approved")
+                }
+                Ok(Transition::Refused(original)) => match original.effective_state(now) {
+                    TransactionState::Reversed
+                        if state.idempotent_reversals
+                            && original.reversal_de90.as_deref() == Some(payload.de90.as_str()) =>
+                    {
+                        info!("Replaying reversal of {}", payload.de11);
+                        (ResponseCode::Approved, "Reversal Approved")
+                    }
+                    TransactionState::Expired => {
+                        if original.state != TransactionState::Expired {
+                            expiry::expire(&state, &key);
+                        }
+                        (
+                            ResponseCode::InvalidAuthorizationLifeCycle,
+                            "Original Authorization Expired",
+                        )
+                    }
+                    _ => (ResponseCode::DuplicateTransmission, "Duplicate Reversal"),
+                },
+                Ok(Transition::Declined(never)) => match never {},
+                Ok(Transition::NotFound) => {
+                    (ResponseCode::DuplicateTransmission, "Original Not Found")
+                }
+                Err(e) => {
+                    warn!("Failed to update transaction {}: {}", payload.de11, e);
+                    (ResponseCode::SystemError, "System Error")
+                }
+            }
+        }
+    };
+
+    drop(phase);
+
+    let response = ReversalResponse {
+        mti: "0410".to_string(),
+        de2: payload.de2.clone(),
+        de3: payload.de3.clone(),
+        de4: payload.de4.clone(),
+        de7: payload.de7.clone(),
+        de11: payload.de11.clone(),
+        de18: payload.de18.clone(),
+        de32: payload.de32.clone(),
+        de39: response_code,
+        de44: None,
+        de48: payload.de48.clone(),
+        de49: payload.de49.clone(),
+        de61: payload.de61.clone(),
+        de90: payload.de90.clone(),
+        response_message: response_message.to_string(),
+    };
+
+    info!(de39 = %response.de39, "reversal response");
+    debug!(response = %masking::payload_for_log(&response), "reversal response");
+
+    state.publish(EventKind::ReversalProcessed, &response);
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Captures a 0220 against its authorization. An authorization can be
+/// captured in several parts (split shipments, incremental charges) while
+/// its hold lasts, until the captures together reach its amount plus
+/// CAPTURE_TOLERANCE percent; each part draws down the hold and is kept
+/// in `captures`.
+fn capture(
+    state: &AppState,
+    key: &str,
+    payload: &CompletionRequest,
+    now: DateTime<Utc>,
+) -> (ResponseCode, &'static str) {
+    let Some(amount) = amount::parse_minor_units(&payload.de4) else {
+        return (ResponseCode::InvalidAmount, "Invalid Amount");
+    };
+
+    let captured = state.authorized_transactions.transition(
+        key,
+        now,
+        TransactionState::Captured,
+        |original| {
+            let repeated = original
+                .captures
+                .iter()
+                .any(|capture| capture.de7 == payload.de7 && capture.amount == payload.de4);
+            if repeated {
+                return Err((ResponseCode::DuplicateTransmission, "Duplicate Completion"));
+            }
+
+            let authorized = amount::parse_minor_units(&original.amount).unwrap_or(0);
+            let captured = original.captured_total();
+            if original.state == TransactionState::Captured {
+                // Posted 0200s and seeded captures have no hold left to draw on.
+                if original.captures.is_empty() {
+                    return Err((ResponseCode::DuplicateTransmission, "Duplicate Completion"));
+                }
+                if captured >= authorized {
+                    return Err((
+                        ResponseCode::DuplicateTransmission,
+                        "Authorization Fully Captured",
+                    ));
+                }
+                if original.is_expired(now) {
+                    return Err((
+                        ResponseCode::InvalidAuthorizationLifeCycle,
+                        "Original Authorization Expired",
+                    ));
+                }
+            }
+            let limit = authorized as f64 * (1.0 + state.capture_tolerance / 100.0);
+            if captured.saturating_add(amount) as f64 > limit {
+                return Err((ResponseCode::InvalidAmount, "Capture Exceeds Authorization"));
+            }
+
+            original.captures.push(Capture {
+                amount: payload.de4.clone(),
+                de7: payload.de7.clone(),
+                captured_at: now,
+            });
+            original.captured_amount = Some(format!("{:012}", captured.saturating_add(amount)));
+            Ok((
+                original.pan.clone(),
+                original.captures.len(),
+                original.settled_amount().to_string(),
+            ))
+        },
+    );
+
+    match captured {
+        Ok(Transition::Moved((pan, captures, settled))) => {
+            state.ledger.post(&pan, key, amount);
+            info!(captures, captured = %settled, "authorization captured");
+            (ResponseCode::Approved, "Completion Accepted")
+        }
+        Ok(Transition::Declined(declined)) => declined,
+        Ok(Transition::Refused(original)) => match original.effective_state(now) {
+            TransactionState::Expired => {
+                if original.state != TransactionState::Expired {
+                    expiry::expire(state, key);
+                }
+                (
+                    ResponseCode::InvalidAuthorizationLifeCycle,
+                    "Original Authorization Expired",
+                )
+            }
+            _ => (
+                ResponseCode::InvalidTransaction,
+                "Original Authorization Reversed",
+            ),
+        },
+        Ok(Transition::NotFound) => (ResponseCode::DuplicateTransmission, "Original Not Found"),
+        Err(e) => {
+            warn!("Failed to update transaction {}: {}", payload.de11, e);
+            (ResponseCode::SystemError, "System Error")
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/completion",
+    tag = "messages",
+    request_body = CompletionRequest,
+    responses(
+        (status = 200, description = "0230 response", body = CompletionResponse),
+        (status = 422, description = "Malformed message", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(mti = %payload.mti, stan = %payload.de11, acquirer = %payload.de32, correlation_id = tracing::field::Empty))]
+pub async fn completion(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(mut payload): ApiJson<CompletionRequest>,
+) -> Response {
+    correlation::attach(&headers, None);
+    let validation_mode = ValidationMode::requested(&headers, state.validation_mode);
+
+    let throttled = match state.throttle(peer, &payload.de32) {
+        Verdict::Allow => None,
+        Verdict::Decline(code) => Some(code),
+        Verdict::Reject(response) => return response,
+    };
+
+    if let Some(acquirer) = state.rules().acquirers.get(&payload.de32) {
+        acquirer.fill_currency(&mut payload.de49);
+    }
+    let violation = state
+        .validate(&Iso8583Message::from(payload.clone()), validation_mode)
+        .err();
+    if let Some(violation) = &violation {
+        info!("Format error in completion: {}", violation);
+    }
+
+    let (response_code, response_message) = if payload.mti != "0220" {
+        (
+            ResponseCode::InvalidTransaction,
+            "Invalid MTI for Completion Advice",
+        )
+    } else if violation.is_some() {
+        (ResponseCode::FormatError, validation::FORMAT_ERROR_MESSAGE)
+    } else if let Some(code) = &throttled {
+        (*code, rate_limit::DECLINE_MESSAGE)
+    } else {
+        let _storage = info_span!("storage").entered();
+        let key = transaction_key(&payload.de32, &payload.de11);
+        capture(&state, &key, &payload, state.clock.now())
+    };
+
+    let response = CompletionResponse {
+        mti: "0230".to_string(),
+        de2: payload.de2.clone(),
+        de3: payload.de3.clone(),
+        de4: payload.de4.clone(),
+        de7: payload.de7.clone(),
+        de11: payload.de11.clone(),
+        de32: payload.de32.clone(),
+        de39: response_code,
+        de44: violation
+            .filter(|_| payload.mti == "0220")
+            .map(|violation| violation.de44()),
+        de49: payload.de49.clone(),
+        de90: payload.de90.clone(),
+        response_message: response_message.to_string(),
+    };
+
+    info!(de39 = %response.de39, "completion response");
+    debug!(response = %masking::payload_for_log(&response), "completion response");
+
+    state.publish(EventKind::AdviceProcessed, &response);
+
+    (StatusCode::OK, Json(response)).into_response()
+}
@@ -0,0 +1,102 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::masking;
+use crate::AppState;
+
+// ============================================================================
+// Health and Readiness Probes
+// ============================================================================
+//
+//   GET /health  liveness: the process is up and serving HTTP
+//   GET /ready   readiness: listener bound and storage reachable (503 if not)
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadyResponse {
+    pub status: &'static str,
+    pub listener: ComponentStatus,
+    pub storage: ComponentStatus,
+    /// Fingerprint of the active rule set; changes when rules are restored.
+    pub config_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentStatus {
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, body = HealthResponse))
+)]
+pub async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        started_at: state.started_at,
+        uptime_seconds: (Utc::now() - state.started_at).num_seconds(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, body = ReadyResponse),
+        (status = 503, description = "Listener not bound or storage unreachable", body = ReadyResponse),
+    )
+)]
+pub async fn ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<ReadyResponse>) {
+    let listening = state.listening.load(Ordering::Relaxed);
+    let listener = ComponentStatus {
+        ok: listening,
+        detail: if listening { "bound" } else { "not bound" }.to_string(),
+    };
+
+    let backend = state.authorized_transactions.backend();
+    let storage = match state.authorized_transactions.ping() {
+        Ok(()) => ComponentStatus {
+            ok: true,
+            detail: backend.to_string(),
+        },
+        Err(e) => ComponentStatus {
+            ok: false,
+            detail: format!("{}: {}", backend, e),
+        },
+    };
+
+    let rules = serde_json::to_string(&*state.rules()).unwrap_or_default();
+    let config_version = masking::hash_value(&rules);
+
+    let ready = listener.ok && storage.ok;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadyResponse {
+            status: if ready { "ready" } else { "not_ready" },
+            listener,
+            storage,
+            config_version,
+        }),
+    )
+}
@@ -0,0 +1,264 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::codes::ResponseCode;
+use crate::error::ApiJson;
+use crate::expiry;
+use crate::AppState;
+
+// ============================================================================
+// Incident Toggles (`/admin/incident`)
+// ============================================================================
+//
+// A simulated outage: the `incident` pipeline stage answers a share of
+// messages with 91 or 96, on real time and outside the rules.
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IncidentRequest {
+    /// `91` or `96`.
+    #[schema(value_type = String, example = "91")]
+    pub code: ResponseCode,
+    /// Fraction of messages answered with `code`, between 0.0 and 1.0.
+    #[serde(default = "full_rate")]
+    pub rate: f64,
+    /// How long it lasts, such as `90s` or `15m`; until cleared if absent.
+    pub duration: Option<String>,
+    /// How long the rate takes to fall to zero once `duration` is over.
+    pub recovery: Option<String>,
+    /// Seed for reproducible runs; RANDOM_SEED or a random seed otherwise.
+    pub seed: Option<u64>,
+}
+
+fn full_rate() -> f64 {
+    1.0
+}
+
+struct Incident {
+    code: ResponseCode,
+    rate: f64,
+    started_at: DateTime<Utc>,
+    /// When the rate starts falling; `None` until cleared.
+    ends_at: Option<DateTime<Utc>>,
+    recovery: Duration,
+    rng: StdRng,
+}
+
+impl Incident {
+    /// The failure rate at `now`; `None` once fully recovered.
+    fn rate_at(&self, now: DateTime<Utc>) -> Option<f64> {
+        let Some(ends_at) = self.ends_at else {
+            return Some(self.rate);
+        };
+        if now < ends_at {
+            return Some(self.rate);
+        }
+        let recovered = (now - ends_at).num_milliseconds() as f64;
+        let recovery = self.recovery.num_milliseconds() as f64;
+        (recovered < recovery).then(|| self.rate * (1.0 - recovered / recovery))
+    }
+
+    fn status(&self, now: DateTime<Utc>) -> IncidentStatus {
+        let current_rate = self.rate_at(now);
+        IncidentStatus {
+            active: current_rate.is_some(),
+            code: Some(self.code.code().to_string()),
+            rate: Some(self.rate),
+            current_rate: current_rate.unwrap_or(0.0),
+            started_at: Some(self.started_at),
+            ends_at: self.ends_at,
+            recovered_at: self.ends_at.map(|ends_at| ends_at + self.recovery),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IncidentStatus {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// The configured rate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<f64>,
+    /// The rate now, lower than `rate` while recovering.
+    pub current_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the rate starts falling; absent until cleared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// When the rate reaches zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovered_at: Option<DateTime<Utc>>,
+}
+
+impl IncidentStatus {
+    fn inactive() -> Self {
+        IncidentStatus {
+            active: false,
+            code: None,
+            rate: None,
+            current_rate: 0.0,
+            started_at: None,
+            ends_at: None,
+            recovered_at: None,
+        }
+    }
+}
+
+/// The incident in force, if any.
+pub struct Incidents {
+    current: Mutex<Option<Incident>>,
+    random_seed: Option<u64>,
+}
+
+impl Incidents {
+    pub fn new(random_seed: Option<u64>) -> Self {
+        Incidents {
+            current: Mutex::new(None),
+            random_seed,
+        }
+    }
+
+    /// Starts an incident, replacing any in force.
+    pub fn start(&self, request: &IncidentRequest) -> Result<IncidentStatus, String> {
+        if !matches!(
+            request.code,
+            ResponseCode::IssuerUnavailable | ResponseCode::SystemError
+        ) {
+            return Err(format!("code must be 91 or 96, got {}", request.code));
+        }
+        if !(0.0..=1.0).contains(&request.rate) {
+            return Err(format!(
+                "rate must be within 0.0..=1.0, got {}",
+                request.rate
+            ));
+        }
+        let period = |value: Option<&str>| -> Result<Option<Duration>, String> {
+            let Some(value) = value else {
+                return Ok(None);
+            };
+            let period = expiry::parse_duration(value)?;
+            if period <= Duration::zero() {
+                return Err(format!("{} is not a positive duration", value));
+            }
+            Ok(Some(period))
+        };
+        let duration = period(request.duration.as_deref())?;
+        let recovery = period(request.recovery.as_deref())?;
+        if duration.is_none() && recovery.is_some() {
+            return Err("recovery needs a duration".to_string());
+        }
+
+        let now = Utc::now();
+        let rng = match request.seed.or(self.random_seed) {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let incident = Incident {
+            code: request.code,
+            rate: request.rate,
+            started_at: now,
+            ends_at: duration.map(|duration| now + duration),
+            recovery: recovery.unwrap_or_else(Duration::zero),
+            rng,
+        };
+        let status = incident.status(now);
+        *self.current.lock().unwrap() = Some(incident);
+        Ok(status)
+    }
+
+    /// Ends the incident at once; false if none was in force.
+    pub fn clear(&self) -> bool {
+        self.current.lock().unwrap().take().is_some()
+    }
+
+    pub fn status(&self) -> IncidentStatus {
+        self.current
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or_else(IncidentStatus::inactive, |incident| {
+                incident.status(Utc::now())
+            })
+    }
+
+    /// The code to answer this message with, or `None` to let it through.
+    pub fn strike(&self) -> Option<ResponseCode> {
+        let mut current = self.current.lock().unwrap();
+        let incident = current.as_mut()?;
+        let Some(rate) = incident.rate_at(Utc::now()) else {
+            info!("Incident over, {} no longer answered", incident.code);
+            *current = None;
+            return None;
+        };
+        incident.rng.gen_bool(rate).then_some(incident.code)
+    }
+}
+
+type AdminError = (StatusCode, String);
+
+#[utoipa::path(
+    get,
+    path = "/admin/incident",
+    tag = "admin",
+    responses((status = 200, body = IncidentStatus)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_incident(State(state): State<Arc<AppState>>) -> Json<IncidentStatus> {
+    Json(state.incidents.status())
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/incident",
+    tag = "admin",
+    request_body = IncidentRequest,
+    responses(
+        (status = 200, body = IncidentStatus),
+        (status = 422, description = "Invalid code, rate or duration", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn start_incident(
+    State(state): State<Arc<AppState>>,
+    ApiJson(request): ApiJson<IncidentRequest>,
+) -> Result<Json<IncidentStatus>, AdminError> {
+    let status = state
+        .incidents
+        .start(&request)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    info!(
+        rate = request.rate,
+        duration = request.duration.as_deref().unwrap_or("until cleared"),
+        "Admin: incident started, answering {}",
+        request.code
+    );
+    Ok(Json(status))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/incident",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Incident cleared", body = IncidentStatus),
+        (status = 404, description = "No incident in force", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn clear_incident(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<IncidentStatus>, AdminError> {
+    if !state.incidents.clear() {
+        return Err((StatusCode::NOT_FOUND, "no incident in force".to_string()));
+    }
+    info!("Admin: incident cleared");
+    Ok(Json(state.incidents.status()))
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::de48::De48;
+use crate::messages::AuthorizationRequest;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Installment Payments
+// ============================================================================
+//
+// Installment plans from DE112 subelement 001 (or DE48 subelement 95),
+// checked against the card profile's policy; approvals return the terms in
+// DE112 subelement 002.
+
+pub const PLAN_SUBELEMENT: &str = "001";
+pub const TERMS_SUBELEMENT: &str = "002";
+pub const DE48_PLAN_SUBELEMENT: &str = "95";
+pub const DECLINE_MESSAGE: &str = "Installment Plan Not Permitted";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanType {
+    /// `20`
+    Issuer,
+    /// `21`
+    Merchant,
+}
+
+impl PlanType {
+    fn parse(code: &str) -> Option<Self> {
+        match code {
+            "20" => Some(PlanType::Issuer),
+            "21" => Some(PlanType::Merchant),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            PlanType::Issuer => "20",
+            PlanType::Merchant => "21",
+        }
+    }
+}
+
+/// Installment plans a card accepts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstallmentPolicy {
+    pub max_installments: u32,
+    /// Plan types allowed; empty allows both.
+    #[serde(default)]
+    pub plans: Vec<PlanType>,
+    /// Smallest DE4, in minor units, that may be paid in installments.
+    pub min_amount: Option<i64>,
+    /// Interest charged per month on issuer-financed plans.
+    #[serde(default)]
+    pub monthly_interest_percent: f64,
+}
+
+/// The plan requested in an authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstallmentRequest {
+    pub plan: PlanType,
+    pub count: u32,
+}
+
+/// The plan of an approved authorization, as stored and returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InstallmentTerms {
+    pub plan: PlanType,
+    pub count: u32,
+    pub monthly_interest_percent: f64,
+    /// Minor units, as are the amounts below.
+    pub first_installment: i64,
+    pub installment: i64,
+    pub total: i64,
+}
+
+/// DE112 subelements, in order.
+pub fn parse_de112(raw: &str) -> Result<Vec<(String, String)>, String> {
+    let mut subelements = Vec::new();
+    let mut rest = raw;
+    while !rest.is_empty() {
+        let tag = rest
+            .get(0..3)
+            .filter(|t| t.chars().all(|c| c.is_ascii_digit()))
+            .ok_or_else(|| format!("bad subelement tag in {:?}", rest))?;
+        let length: usize = rest
+            .get(3..6)
+            .and_then(|l| l.parse().ok())
+            .ok_or_else(|| format!("bad length for subelement {}", tag))?;
+        let value = rest
+            .get(6..6 + length)
+            .ok_or_else(|| format!("subelement {} is truncated", tag))?;
+        subelements.push((tag.to_string(), value.to_string()));
+        rest = &rest[6 + length..];
+    }
+    Ok(subelements)
+}
+
+pub fn encode_de112(subelements: &[(String, String)]) -> String {
+    subelements
+        .iter()
+        .map(|(tag, value)| format!("{}{:03}{}", tag, value.len(), value))
+        .collect()
+}
+
+fn parse_plan(value: &str) -> Option<InstallmentRequest> {
+    if value.len() != 4 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let plan = PlanType::parse(&value[..2])?;
+    let count: u32 = value[2..].parse().ok()?;
+    (count >= 2).then_some(InstallmentRequest { plan, count })
+}
+
+/// The installment plan of `request`, if it asks for one. A malformed plan
+/// is reported with the number of the DE carrying it.
+pub fn requested(request: &AuthorizationRequest) -> Result<Option<InstallmentRequest>, u8> {
+    if let Some(de112) = request.field("de112") {
+        let subelements = parse_de112(de112).map_err(|_| 112)?;
+        if let Some((_, value)) = subelements.iter().find(|(tag, _)| tag == PLAN_SUBELEMENT) {
+            return parse_plan(value).map(Some).ok_or(112);
+        }
+    }
+    match De48::parse(&request.de48)
+        .ok()
+        .and_then(|de48| de48.get(DE48_PLAN_SUBELEMENT).map(str::to_string))
+    {
+        Some(value) => parse_plan(&value).map(Some).ok_or(48),
+        None => Ok(None),
+    }
+}
+
+impl InstallmentPolicy {
+    /// Whether the card may pay `amount` (DE4, minor units) in `request`.
+    pub fn permits(&self, request: &InstallmentRequest, amount: i64) -> bool {
+        request.count <= self.max_installments
+            && (self.plans.is_empty() || self.plans.contains(&request.plan))
+            && self.min_amount.is_none_or(|min| amount >= min)
+    }
+
+    /// Terms for `amount`, the approved DE4.
+    pub fn terms(&self, request: &InstallmentRequest, amount: i64) -> InstallmentTerms {
+        let monthly_interest_percent = match request.plan {
+            PlanType::Issuer => self.monthly_interest_percent,
+            PlanType::Merchant => 0.0,
+        };
+        let interest =
+            (amount as f64 * monthly_interest_percent / 100.0 * request.count as f64).round();
+        let total = amount + interest as i64;
+        let installment = total / request.count as i64;
+        InstallmentTerms {
+            plan: request.plan,
+            count: request.count,
+            monthly_interest_percent,
+            first_installment: total - installment * (request.count as i64 - 1),
+            installment,
+            total,
+        }
+    }
+}
+
+impl InstallmentTerms {
+    /// The response DE112: the request's subelements with the terms added.
+    pub fn de112(&self, request_de112: Option<&str>) -> String {
+        let mut subelements = request_de112
+            .and_then(|raw| parse_de112(raw).ok())
+            .unwrap_or_default();
+        subelements.retain(|(tag, _)| tag != TERMS_SUBELEMENT);
+        if !subelements.iter().any(|(tag, _)| tag == PLAN_SUBELEMENT) {
+            subelements.insert(
+                0,
+                (
+                    PLAN_SUBELEMENT.to_string(),
+                    format!("{}{:02}", self.plan.code(), self.count),
+                ),
+            );
+        }
+        subelements.push((
+            TERMS_SUBELEMENT.to_string(),
+            format!(
+                "{:02}{:05}{:012}{:012}",
+                self.count,
+                (self.monthly_interest_percent * 100.0).round() as i64,
+                self.first_installment,
+                self.total
+            ),
+        ));
+        encode_de112(&subelements)
+    }
+}
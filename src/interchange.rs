@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::amount;
+use crate::bins::BinEntry;
+use crate::codes::ProcessingCode;
+use crate::country;
+use crate::{Transaction, TransactionState};
+
+// ============================================================================
+// Interchange Fees
+// ============================================================================
+//
+// A simulated interchange fee for each stored transaction, from the
+// `interchange` table in the rules file, so fee engines can be checked
+// against predictable numbers. Rates are tried in file order; the first
+// whose conditions all match applies, else `default`:
+//
+//   product     BIN table product code of the PAN (`MCS`, `MCO`, ...)
+//   mcc         DE18
+//   region      `domestic` when the BIN's country is the merchant's
+//               (DE61 subfield 13 or DE43), `cross_border` otherwise
+//   entry_mode  the first two digits of DE22
+//
+// The fee is `percent` of the settled amount, rounded to the minor unit,
+// plus `fixed` minor units. Reversed transactions and credits (DE3 `20` /
+// `28`) carry no fee. Fees are worked out when exported, so a changed
+// table applies to transactions already stored.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterchangeConfig {
+    #[serde(default)]
+    pub rates: Vec<InterchangeRate>,
+    /// Applies when no rate matches; no fee when absent.
+    pub default: Option<FeeRate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterchangeRate {
+    pub name: String,
+    #[serde(default)]
+    pub when: RateConditions,
+    #[serde(flatten)]
+    pub rate: FeeRate,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FeeRate {
+    /// Percent of the settled amount.
+    #[serde(default)]
+    pub percent: f64,
+    /// Minor units added per transaction.
+    #[serde(default)]
+    pub fixed: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    Domestic,
+    CrossBorder,
+}
+
+/// All present conditions must match for the rate to apply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateConditions {
+    pub product: Option<Vec<String>>,
+    pub mcc: Option<Vec<String>>,
+    pub region: Option<Region>,
+    pub entry_mode: Option<Vec<String>>,
+}
+
+/// The fee of one transaction and the rate it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fee {
+    /// `None` for the default rate.
+    pub rate: Option<String>,
+    /// Minor units of the transaction currency.
+    pub amount: i64,
+}
+
+impl RateConditions {
+    fn matches(&self, transaction: &Transaction, bin: Option<&BinEntry>) -> bool {
+        let listed = |list: &Option<Vec<String>>, value: Option<&str>| {
+            list.as_ref()
+                .is_none_or(|list| value.is_some_and(|value| list.iter().any(|v| v == value)))
+        };
+        if !listed(&self.product, bin.map(|bin| bin.product.as_str())) {
+            return false;
+        }
+        if !listed(&self.mcc, transaction.request_field("de18")) {
+            return false;
+        }
+        let entry_mode = transaction
+            .request_field("de22")
+            .and_then(|de22| de22.get(..2));
+        if !listed(&self.entry_mode, entry_mode) {
+            return false;
+        }
+        self.region.is_none_or(|region| {
+            let merchant = country::merchant_country(transaction);
+            match (bin, merchant) {
+                (Some(bin), Some(merchant)) => {
+                    (bin.country == merchant) == (region == Region::Domestic)
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+impl InterchangeConfig {
+    /// The fee for `transaction`, whose PAN has the BIN table entry `bin`;
+    /// `None` when it carries none.
+    pub fn fee(&self, transaction: &Transaction, bin: Option<&BinEntry>) -> Option<Fee> {
+        let credit = transaction
+            .request_field("de3")
+            .and_then(|code| code.parse::<ProcessingCode>().ok())
+            .is_some_and(|code| code.is_credit());
+        if credit || transaction.state == TransactionState::Reversed {
+            return None;
+        }
+        let (name, rate) = self
+            .rates
+            .iter()
+            .find(|rate| rate.when.matches(transaction, bin))
+            .map(|rate| (Some(rate.name.clone()), rate.rate))
+            .or_else(|| self.default.map(|rate| (None, rate)))?;
+        let settled = amount::parse_minor_units(transaction.settled_amount()).unwrap_or(0);
+        let percentage = (settled as f64 * rate.percent / 100.0).round() as i64;
+        Some(Fee {
+            rate: name,
+            amount: percentage.saturating_add(rate.fixed),
+        })
+    }
+}
@@ -0,0 +1,660 @@
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::warn;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Field Registry
+// ============================================================================
+//
+// Format, length and wire encoding of every data element the mock knows,
+// after the Mastercard Customer Interface Specification. In JSON each DE is
+// a string keyed `de<N>` (or another key style, below); fixed-length fields
+// are sent at full length, though lenient validation lets most through short
+// (packing zero-fills numeric ones on the left, space-fills others on the
+// right), variable-length fields carry their LL/LLL prefix on the wire only.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldFormat {
+    /// `n`: digits.
+    Numeric,
+    /// `an`: letters and digits.
+    Alphanumeric,
+    /// `ans`: letters, digits and special characters.
+    AlphanumericSpecial,
+    /// `z`: track 2 data, digits and separators.
+    Track2,
+    /// `b`: binary, carried as hex in JSON.
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase", tag = "type", content = "max")]
+pub enum FieldLength {
+    /// Exactly this many characters (bytes for binary fields).
+    Fixed(usize),
+    /// Two-digit length prefix, up to this many.
+    Llvar(usize),
+    /// Three-digit length prefix, up to this many.
+    Lllvar(usize),
+}
+
+impl FieldLength {
+    pub fn max(&self) -> usize {
+        match *self {
+            FieldLength::Fixed(max) | FieldLength::Llvar(max) | FieldLength::Lllvar(max) => max,
+        }
+    }
+}
+
+/// How a field's characters are represented on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Ebcdic,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FieldDefinition {
+    pub number: u8,
+    pub name: &'static str,
+    pub format: FieldFormat,
+    pub length: FieldLength,
+    pub encoding: Encoding,
+}
+
+impl FieldDefinition {
+    /// Checks a JSON value against the field's format and maximum length.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let allowed = |c: char| match self.format {
+            FieldFormat::Numeric => c.is_ascii_digit(),
+            FieldFormat::Alphanumeric => c.is_ascii_alphanumeric(),
+            FieldFormat::AlphanumericSpecial => c.is_ascii() && !c.is_ascii_control(),
+            FieldFormat::Track2 => c.is_ascii_digit() || c == '=' || c == 'D',
+            FieldFormat::Binary => c.is_ascii_hexdigit(),
+        };
+        if let Some(c) = value.chars().find(|&c| !allowed(c)) {
+            return Err(format!(
+                "invalid character {:?} for a {} field",
+                c,
+                self.format.code()
+            ));
+        }
+
+        let length = match self.format {
+            FieldFormat::Binary if !value.len().is_multiple_of(2) => {
+                return Err("binary field needs an even number of hex digits".to_string())
+            }
+            FieldFormat::Binary => value.len() / 2,
+            _ => value.len(),
+        };
+        if length > self.length.max() {
+            return Err(format!(
+                "length {} exceeds the maximum of {}",
+                length,
+                self.length.max()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that a fixed-length value isn't short, which `check` allows
+    /// since packing pads it. Assumes `check` passed.
+    pub fn check_full_length(&self, value: &str) -> Result<(), String> {
+        let FieldLength::Fixed(fixed) = self.length else {
+            return Ok(());
+        };
+        let length = match self.format {
+            FieldFormat::Binary => value.len() / 2,
+            _ => value.len(),
+        };
+        if length < fixed {
+            return Err(format!("length {} is short of the fixed {}", length, fixed));
+        }
+        Ok(())
+    }
+}
+
+impl FieldFormat {
+    /// The specification's abbreviation: `n`, `an`, `ans`, `z` or `b`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FieldFormat::Numeric => "n",
+            FieldFormat::Alphanumeric => "an",
+            FieldFormat::AlphanumericSpecial => "ans",
+            FieldFormat::Track2 => "z",
+            FieldFormat::Binary => "b",
+        }
+    }
+}
+
+pub(crate) const fn def(
+    number: u8,
+    name: &'static str,
+    format: FieldFormat,
+    length: FieldLength,
+) -> FieldDefinition {
+    let encoding = match format {
+        FieldFormat::Binary => Encoding::Binary,
+        _ => Encoding::Ebcdic,
+    };
+    FieldDefinition {
+        number,
+        name,
+        format,
+        length,
+        encoding,
+    }
+}
+
+use FieldFormat::{Alphanumeric as AN, AlphanumericSpecial as ANS, Binary as B, Numeric as N};
+use FieldLength::{Fixed, Lllvar, Llvar};
+
+static FIELDS: &[FieldDefinition] = &[
+    def(2, "Primary Account Number", N, Llvar(19)),
+    def(3, "Processing Code", N, Fixed(6)),
+    def(4, "Amount, Transaction", N, Fixed(12)),
+    def(5, "Amount, Settlement", N, Fixed(12)),
+    def(6, "Amount, Cardholder Billing", N, Fixed(12)),
+    def(7, "Transmission Date and Time", N, Fixed(10)),
+    def(9, "Conversion Rate, Settlement", N, Fixed(8)),
+    def(10, "Conversion Rate, Cardholder Billing", N, Fixed(8)),
+    def(11, "Systems Trace Audit Number", N, Fixed(6)),
+    def(12, "Time, Local Transaction", N, Fixed(6)),
+    def(13, "Date, Local Transaction", N, Fixed(4)),
+    def(14, "Date, Expiration", N, Fixed(4)),
+    def(15, "Date, Settlement", N, Fixed(4)),
+    def(16, "Date, Conversion", N, Fixed(4)),
+    def(18, "Merchant Type", N, Fixed(4)),
+    def(20, "Primary Account Number Country Code", N, Fixed(3)),
+    def(22, "Point-of-Service Entry Mode", N, Fixed(3)),
+    def(23, "Card Sequence Number", N, Fixed(3)),
+    def(26, "Point-of-Service PIN Capture Code", N, Fixed(2)),
+    def(28, "Amount, Transaction Fee", AN, Fixed(9)),
+    def(32, "Acquiring Institution ID Code", N, Llvar(11)),
+    def(33, "Forwarding Institution ID Code", N, Llvar(11)),
+    def(35, "Track 2 Data", FieldFormat::Track2, Llvar(37)),
+    def(37, "Retrieval Reference Number", AN, Fixed(12)),
+    def(38, "Authorization ID Response", AN, Fixed(6)),
+    def(39, "Response Code", AN, Fixed(2)),
+    def(41, "Card Acceptor Terminal ID", ANS, Fixed(8)),
+    def(42, "Card Acceptor ID Code", ANS, Fixed(15)),
+    def(43, "Card Acceptor Name/Location", ANS, Fixed(40)),
+    def(44, "Additional Response Data", ANS, Llvar(25)),
+    def(45, "Track 1 Data", ANS, Llvar(76)),
+    def(48, "Additional Data—Private Use", ANS, Lllvar(999)),
+    def(49, "Currency Code, Transaction", N, Fixed(3)),
+    def(50, "Currency Code, Settlement", N, Fixed(3)),
+    def(51, "Currency Code, Cardholder Billing", N, Fixed(3)),
+    def(52, "Personal ID Number (PIN) Data", B, Fixed(8)),
+    def(53, "Security-Related Control Information", N, Fixed(16)),
+    def(54, "Additional Amounts", ANS, Lllvar(240)),
+    def(
+        55,
+        "Integrated Circuit Card (ICC) System-Related Data",
+        B,
+        Lllvar(255),
+    ),
+    def(56, "Payment Account Reference Data", ANS, Lllvar(37)),
+    def(60, "Advice Reason Code", ANS, Lllvar(60)),
+    def(61, "Point-of-Service (POS) Data", ANS, Lllvar(26)),
+    def(
+        62,
+        "Intermediate Network Facility (INF) Data",
+        ANS,
+        Lllvar(100),
+    ),
+    def(63, "Network Data", ANS, Lllvar(50)),
+    def(90, "Original Data Elements", N, Fixed(42)),
+    def(95, "Replacement Amounts", AN, Fixed(42)),
+    def(100, "Receiving Institution ID Code", N, Llvar(11)),
+    def(102, "Account ID 1", ANS, Llvar(28)),
+    def(103, "Account ID 2", ANS, Llvar(28)),
+    def(
+        108,
+        "Additional Transaction Reference Data",
+        ANS,
+        Lllvar(999),
+    ),
+    def(112, "Additional Data (National Use)", ANS, Lllvar(100)),
+    def(120, "Record Data", ANS, Lllvar(999)),
+    def(121, "Authorizing Agent ID Code", ANS, Lllvar(6)),
+    def(124, "Member-defined Data", ANS, Lllvar(299)),
+    def(127, "Private Data", ANS, Lllvar(100)),
+];
+
+/// Definition of data element `number`, if the registry has one.
+pub fn field(number: u8) -> Option<&'static FieldDefinition> {
+    FIELDS.iter().find(|field| field.number == number)
+}
+
+/// Every registered data element, in DE order.
+pub fn fields() -> &'static [FieldDefinition] {
+    FIELDS
+}
+
+/// DE number from a JSON key such as `de48`.
+pub fn parse_key(key: &str) -> Option<u8> {
+    key.strip_prefix("de")?.parse().ok()
+}
+
+// ============================================================================
+// JSON Key Styles
+// ============================================================================
+//
+// A message may name its data elements in any of three styles, so teams can
+// keep the payloads they already have:
+//
+//   de2  prefixed, as the mock answers by default
+//   2    the bare number
+//   pan  the alias below; DEs without one keep `de<N>`
+//
+// Styles can be mixed within a message. The first DE key of a request picks
+// the style its response is written in.
+
+static ALIASES: &[(u8, &str)] = &[
+    (2, "pan"),
+    (3, "processing_code"),
+    (4, "amount"),
+    (6, "billing_amount"),
+    (7, "transmission_time"),
+    (11, "stan"),
+    (14, "expiry_date"),
+    (18, "merchant_type"),
+    (22, "pos_entry_mode"),
+    (32, "acquirer_id"),
+    (35, "track2"),
+    (37, "rrn"),
+    (38, "auth_code"),
+    (39, "response_code"),
+    (41, "terminal_id"),
+    (42, "card_acceptor_id"),
+    (43, "card_acceptor_name"),
+    (44, "additional_response_data"),
+    (48, "additional_data"),
+    (49, "currency"),
+    (51, "billing_currency"),
+    (52, "pin_block"),
+    (55, "icc_data"),
+    (61, "pos_data"),
+    (63, "network_data"),
+    (90, "original_data_elements"),
+];
+
+/// The alias of DE `number`, e.g. `pan` for DE2.
+pub fn alias(number: u8) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|(n, _)| *n == number)
+        .map(|(_, alias)| *alias)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyStyle {
+    /// `de2`.
+    #[default]
+    Prefixed,
+    /// `2`.
+    Number,
+    /// `pan`.
+    Alias,
+}
+
+/// A JSON key that names a data element, in any style.
+#[derive(Debug, Clone, Copy)]
+pub struct DeKey {
+    pub style: KeyStyle,
+    /// `None` for a number the registry doesn't know, e.g. `de999`.
+    pub definition: Option<&'static FieldDefinition>,
+}
+
+impl DeKey {
+    /// `None` if `key` names no data element (`mti`, `response_message`).
+    pub fn parse(key: &str) -> Option<DeKey> {
+        let (style, digits) = match key.strip_prefix("de") {
+            Some(digits) => (KeyStyle::Prefixed, digits),
+            None => (KeyStyle::Number, key),
+        };
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(DeKey {
+                style,
+                definition: digits.parse().ok().and_then(field),
+            });
+        }
+        let (number, _) = ALIASES.iter().find(|(_, alias)| *alias == key)?;
+        Some(DeKey {
+            style: KeyStyle::Alias,
+            definition: field(*number),
+        })
+    }
+}
+
+impl KeyStyle {
+    /// The style of the first key of `object` that names a DE; `Prefixed`
+    /// if none does.
+    pub fn detect(object: &Map<String, Value>) -> Self {
+        object
+            .keys()
+            .find_map(|key| DeKey::parse(key))
+            .map(|key| key.style)
+            .unwrap_or_default()
+    }
+
+    /// The key for DE `number` in this style.
+    pub fn key(self, number: u8) -> String {
+        match (self, alias(number)) {
+            (KeyStyle::Number, _) => number.to_string(),
+            (KeyStyle::Alias, Some(alias)) => alias.to_string(),
+            (KeyStyle::Prefixed | KeyStyle::Alias, _) => format!("de{}", number),
+        }
+    }
+
+    /// Renames the `de<N>` keys of a JSON message into this style, keeping
+    /// their order.
+    pub fn restyle(self, object: Map<String, Value>) -> Map<String, Value> {
+        if self == KeyStyle::Prefixed {
+            return object;
+        }
+        object
+            .into_iter()
+            .map(|(key, value)| match parse_key(&key) {
+                Some(number) => (self.key(number), value),
+                None => (key, value),
+            })
+            .collect()
+    }
+}
+
+/// The value of DE `number` in a JSON message, whatever its key style.
+pub fn find_value(object: &Map<String, Value>, number: u8) -> Option<&Value> {
+    object.iter().find_map(|(key, value)| {
+        DeKey::parse(key)
+            .and_then(|key| key.definition)
+            .filter(|definition| definition.number == number)
+            .map(|_| value)
+    })
+}
+
+// ============================================================================
+// Field Values
+// ============================================================================
+
+/// A data element value, typed by its registry format. Values are kept as
+/// received so they can be echoed and compared verbatim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    Numeric(String),
+    Text(String),
+    /// Hex digits.
+    Binary(String),
+}
+
+impl FieldValue {
+    pub fn new(definition: &FieldDefinition, value: String) -> Self {
+        match definition.format {
+            FieldFormat::Numeric => FieldValue::Numeric(value),
+            FieldFormat::Binary => FieldValue::Binary(value),
+            _ => FieldValue::Text(value),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            FieldValue::Numeric(value) | FieldValue::Text(value) | FieldValue::Binary(value) => {
+                value
+            }
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        match self {
+            FieldValue::Numeric(value) | FieldValue::Text(value) | FieldValue::Binary(value) => {
+                value
+            }
+        }
+    }
+
+    /// The value of a numeric field.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self {
+            FieldValue::Numeric(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The bytes of a binary field.
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        let FieldValue::Binary(hex) = self else {
+            return None;
+        };
+        if !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+}
+
+impl Serialize for FieldValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// ============================================================================
+// Data Elements
+// ============================================================================
+
+/// Registered data elements keyed by DE number, serialized as
+/// `{"de2": "...", "de48": "..."}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataElements(BTreeMap<u8, FieldValue>);
+
+impl DataElements {
+    pub fn get(&self, number: u8) -> Option<&FieldValue> {
+        self.0.get(&number)
+    }
+
+    /// Sets a field, typing the value from the registry.
+    pub fn set(&mut self, number: u8, value: impl Into<String>) -> Result<(), String> {
+        let definition =
+            field(number).ok_or_else(|| format!("DE{} is not in the field registry", number))?;
+        self.0
+            .insert(number, FieldValue::new(definition, value.into()));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, number: u8) -> Option<FieldValue> {
+        self.0.remove(&number)
+    }
+
+    /// Removes a field, returning its raw value.
+    pub fn take(&mut self, number: u8) -> Option<String> {
+        self.remove(number).map(FieldValue::into_string)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &FieldValue)> {
+        self.0.iter().map(|(number, value)| (*number, value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Moves every field of `other` into `self`, replacing duplicates.
+    pub fn extend(&mut self, other: DataElements) {
+        self.0.extend(other.0);
+    }
+}
+
+impl FieldSource for DataElements {
+    fn field(&self, name: &str) -> Option<&str> {
+        parse_key(name)
+            .and_then(|number| self.get(number))
+            .map(FieldValue::as_str)
+    }
+}
+
+impl Serialize for DataElements {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (number, value) in &self.0 {
+            map.serialize_entry(&format!("de{}", number), value)?;
+        }
+        map.end()
+    }
+}
+
+/// Documented as an open object of `deN` strings; which DEs are allowed
+/// is decided by the registry, not the schema.
+impl PartialSchema for DataElements {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .description(Some("Any other registered data element, keyed `deN`."))
+            .additional_properties(Some(ObjectBuilder::new().schema_type(Type::String)))
+            .into()
+    }
+}
+
+impl ToSchema for DataElements {}
+
+/// Deserializes one `de<N>` value, typed by the definition.
+struct FieldSeed(&'static FieldDefinition);
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = FieldValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<FieldValue, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(FieldValue::new(self.0, value))
+    }
+}
+
+/// Rejects a key that names no data element and isn't `mti`; taking the
+/// value first keeps the key in the error path.
+struct UnknownField(String);
+
+impl<'de> DeserializeSeed<'de> for UnknownField {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, _deserializer: D) -> Result<(), D::Error> {
+        Err(de::Error::custom(format_args!(
+            "unknown field `{}`, expected `mti` or a data element",
+            self.0
+        )))
+    }
+}
+
+// ============================================================================
+// Generic Message
+// ============================================================================
+
+/// Any ISO 8583 message: an MTI plus the data elements it carries, in the
+/// JSON shape `{"mti": "0100", "de2": "...", ...}`, though any key style is
+/// read. A DE number the registry doesn't know is skipped with a warning;
+/// any other key is rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Iso8583Message {
+    pub mti: String,
+    #[serde(flatten)]
+    pub fields: DataElements,
+}
+
+impl Iso8583Message {
+    pub fn new(mti: impl Into<String>) -> Self {
+        Iso8583Message {
+            mti: mti.into(),
+            fields: DataElements::default(),
+        }
+    }
+
+    pub fn get(&self, number: u8) -> Option<&FieldValue> {
+        self.fields.get(number)
+    }
+
+    pub fn set(&mut self, number: u8, value: impl Into<String>) -> Result<(), String> {
+        self.fields.set(number, value)
+    }
+
+    /// Removes a field, returning its raw value.
+    pub fn take(&mut self, number: u8) -> Option<String> {
+        self.fields.take(number)
+    }
+
+    /// Sets the present fields of a typed message. Every number must be in
+    /// the registry.
+    pub(crate) fn insert_all<const N: usize>(&mut self, fields: [(u8, Option<String>); N]) {
+        for (number, value) in fields {
+            if let Some(value) = value {
+                self.set(number, value)
+                    .expect("typed fields are registered");
+            }
+        }
+    }
+}
+
+impl FieldSource for Iso8583Message {
+    fn field(&self, name: &str) -> Option<&str> {
+        match name {
+            "mti" => Some(&self.mti),
+            _ => self.fields.field(name),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Iso8583Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MessageVisitor;
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = Iso8583Message;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an ISO 8583 message object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Iso8583Message, A::Error> {
+                let mut mti = None;
+                let mut fields = DataElements::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "mti" {
+                        if mti.is_some() {
+                            return Err(de::Error::duplicate_field("mti"));
+                        }
+                        mti = Some(map.next_value()?);
+                        continue;
+                    }
+                    match DeKey::parse(&key).map(|key| key.definition) {
+                        Some(Some(definition)) => {
+                            let value = map.next_value_seed(FieldSeed(definition))?;
+                            fields.0.insert(definition.number, value);
+                        }
+                        Some(None) => {
+                            map.next_value::<IgnoredAny>()?;
+                            warn!("Ignoring unregistered data element {}", key);
+                        }
+                        None => map.next_value_seed(UnknownField(key))?,
+                    }
+                }
+                Ok(Iso8583Message {
+                    mti: mti.ok_or_else(|| de::Error::missing_field("mti"))?,
+                    fields,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(MessageVisitor)
+    }
+}
@@ -0,0 +1,104 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::codes::ResponseCode;
+use crate::latency::Latency;
+use crate::pan::BinRange;
+use crate::processing::MessageMode;
+use crate::rules::{Action, Rule};
+
+// ============================================================================
+// Virtual Issuers
+// ============================================================================
+//
+// A single mock can stand in for several issuers, each selected by the
+// BIN of the PAN in DE2. An issuer profile carries:
+//
+// - an approval policy: rules and a default action, tried after the
+//   acquirer's and before the shared rules;
+// - a response latency, used when the matched rule sets none;
+// - stand-in processing (STIP) parameters: how often the issuer is
+//   unavailable, and what the network then decides on its behalf;
+// - a message mode, overriding the shared `message_mode`.
+//
+// When BIN ranges of two issuers overlap, the longest BIN wins, then the
+// earlier issuer in the file.
+
+pub const STAND_IN_MESSAGE: &str = "Approved by Stand-In";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuerProfile {
+    pub name: String,
+    /// BIN prefixes or ranges, as in TEST_BINS.
+    pub bins: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Used instead of the shared rules when none of `rules` match.
+    pub default: Option<Action>,
+    pub latency: Option<Latency>,
+    pub stip: Option<StipConfig>,
+    pub message_mode: Option<MessageMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StipConfig {
+    /// Fraction of authorizations for which the issuer does not answer,
+    /// between 0.0 and 1.0.
+    pub unavailable_rate: f64,
+    /// Largest DE4 amount the network approves on the issuer's behalf; no
+    /// stand-in approvals when absent.
+    pub approve_up_to: Option<u64>,
+    /// Answer when stand-in does not approve.
+    #[serde(default = "default_decline_code")]
+    pub decline_code: ResponseCode,
+}
+
+fn default_decline_code() -> ResponseCode {
+    ResponseCode::IssuerUnavailable
+}
+
+impl IssuerProfile {
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.bins.is_empty() {
+            return Err(format!("issuer {}: no bins", self.name));
+        }
+        for spec in &self.bins {
+            BinRange::parse(spec).map_err(|e| format!("issuer {}: {}", self.name, e))?;
+        }
+        if let Some(stip) = &self.stip {
+            if !(0.0..=1.0).contains(&stip.unavailable_rate) {
+                return Err(format!(
+                    "issuer {}: stip.unavailable_rate must be within 0.0..=1.0, got {}",
+                    self.name, stip.unavailable_rate
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Width of the longest of the issuer's BINs containing `pan`.
+    pub(crate) fn match_width(&self, pan: &str) -> Option<usize> {
+        self.bins
+            .iter()
+            .filter_map(|spec| BinRange::parse(spec).ok())
+            .filter(|range| range.contains(pan))
+            .map(|range| range.width())
+            .max()
+    }
+}
+
+impl StipConfig {
+    /// Rolls for the issuer being unavailable.
+    pub fn issuer_unavailable(&self) -> bool {
+        rand::thread_rng().gen_bool(self.unavailable_rate)
+    }
+
+    /// The network's decision for `amount` (DE4, minor units).
+    pub fn decide(&self, amount: u64) -> ResponseCode {
+        if self.approve_up_to.is_some_and(|limit| amount <= limit) {
+            ResponseCode::Approved
+        } else {
+            self.decline_code
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::Utc;
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::{Client, ClientBuilder};
+use rskafka::record::Record;
+use rskafka::BackoffConfig;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+
+use crate::settings::Settings;
+use crate::webhooks::Event;
+use crate::{transaction_key, AppState};
+
+// ============================================================================
+// Kafka Event Publishing (feature `kafka`)
+// ============================================================================
+//
+// With KAFKA_BROKERS set, every event of the live feed (authorizations,
+// reversals and completion advices) is also produced to KAFKA_TOPIC
+// (default `mock.transactions`), so fraud and analytics pipelines can be fed
+// straight from the mock. Records are keyed by `DE32:DE11`, keeping the
+// events of one transaction on one partition, and carry an `event` header.
+// KAFKA_FORMAT picks the value encoding:
+//
+//   json  the webhook envelope as compact JSON (default)
+//   avro  Avro binary of schemas/transaction_event.avsc, without schema
+//         registry framing; `data` becomes a map of strings
+//
+// Publishing runs in the background and never holds up the message flow.
+// While the brokers are unreachable, events queue up to the feed capacity
+// and older ones are then dropped; a produce that fails is logged and
+// dropped.
+
+pub const DEFAULT_TOPIC: &str = "mock.transactions";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How long one connect or produce keeps retrying before giving up.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaFormat {
+    Json,
+    Avro,
+}
+
+impl FromStr for KafkaFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value {
+            "json" => Ok(KafkaFormat::Json),
+            "avro" => Ok(KafkaFormat::Avro),
+            other => Err(format!(
+                "KAFKA_FORMAT must be json or avro, got {:?}",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    pub brokers: Vec<String>,
+    pub topic: String,
+    pub format: KafkaFormat,
+}
+
+impl KafkaConfig {
+    /// KAFKA_BROKERS, KAFKA_TOPIC and KAFKA_FORMAT; `None` without brokers.
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>, String> {
+        let Some(brokers) = settings.var("KAFKA_BROKERS") else {
+            return Ok(None);
+        };
+        Ok(Some(KafkaConfig {
+            brokers: brokers
+                .split(',')
+                .map(str::trim)
+                .filter(|broker| !broker.is_empty())
+                .map(String::from)
+                .collect(),
+            topic: settings
+                .var("KAFKA_TOPIC")
+                .unwrap_or_else(|| DEFAULT_TOPIC.to_string()),
+            format: settings
+                .var("KAFKA_FORMAT")
+                .map_or(Ok(KafkaFormat::Json), |format| format.parse())?,
+        }))
+    }
+}
+
+/// Starts producing the feed of `state` to Kafka in the background.
+pub fn spawn(config: KafkaConfig, state: &AppState) {
+    let events = state.feed.subscribe();
+    tokio::spawn(publish(config, events));
+}
+
+async fn connect(config: &KafkaConfig) -> Result<Vec<PartitionClient>, String> {
+    let client: Client = ClientBuilder::new(config.brokers.clone())
+        .client_id("mastercard-api")
+        .backoff_config(BackoffConfig {
+            max_backoff: RECONNECT_DELAY,
+            deadline: Some(REQUEST_DEADLINE),
+            ..BackoffConfig::default()
+        })
+        .build()
+        .await
+        .map_err(|e| e.to_string())?;
+    // An unknown topic is created on first produce, with one partition,
+    // when the brokers allow auto-creation.
+    let partitions: Vec<i32> = client
+        .list_topics()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|topic| topic.name == config.topic)
+        .map_or_else(|| vec![0], |topic| topic.partitions.into_iter().collect());
+    let mut clients = Vec::with_capacity(partitions.len());
+    for partition in partitions {
+        clients.push(
+            client
+                .partition_client(config.topic.clone(), partition, UnknownTopicHandling::Retry)
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    Ok(clients)
+}
+
+async fn publish(config: KafkaConfig, mut events: broadcast::Receiver<Event>) {
+    let partitions = loop {
+        match connect(&config).await {
+            Ok(partitions) => break partitions,
+            Err(e) => {
+                warn!("Kafka brokers {:?} unavailable: {}", config.brokers, e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    };
+    info!(
+        topic = %config.topic,
+        partitions = partitions.len(),
+        format = ?config.format,
+        "publishing events to Kafka"
+    );
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Kafka publisher fell behind, dropped {} events", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let key = event_key(&event);
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let partition = &partitions[(hasher.finish() % partitions.len() as u64) as usize];
+
+        let (value, content_type) = match config.format {
+            KafkaFormat::Json => (
+                serde_json::to_vec(&event).unwrap_or_default(),
+                "application/json",
+            ),
+            KafkaFormat::Avro => (avro::encode(&event), "avro/binary"),
+        };
+        let record = Record {
+            key: Some(key.into_bytes()),
+            value: Some(value),
+            headers: BTreeMap::from([
+                ("event".to_string(), event.event.name().as_bytes().to_vec()),
+                ("content-type".to_string(), content_type.as_bytes().to_vec()),
+            ]),
+            timestamp: Utc::now(),
+        };
+        if let Err(e) = partition
+            .produce(vec![record], Compression::NoCompression)
+            .await
+        {
+            warn!("Kafka produce of event {} failed: {}", event.id, e);
+        }
+    }
+}
+
+fn event_key(event: &Event) -> String {
+    let field = |name: &str| event.data.get(name).and_then(Value::as_str).unwrap_or("");
+    transaction_key(field("de32"), field("de11"))
+}
+
+/// Avro binary encoding of the `TransactionEvent` record.
+mod avro {
+    use serde_json::Value;
+
+    use crate::webhooks::Event;
+
+    fn long(out: &mut Vec<u8>, value: i64) {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        while zigzag >= 0x80 {
+            out.push((zigzag as u8 & 0x7f) | 0x80);
+            zigzag >>= 7;
+        }
+        out.push(zigzag as u8);
+    }
+
+    fn string(out: &mut Vec<u8>, value: &str) {
+        long(out, value.len() as i64);
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    pub(super) fn encode(event: &Event) -> Vec<u8> {
+        let mut out = Vec::new();
+        string(&mut out, &event.id.to_string());
+        string(&mut out, event.event.name());
+        long(&mut out, event.occurred_at.timestamp_millis());
+
+        let entries: Vec<(&String, String)> = event
+            .data
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| match value {
+                Value::String(text) => (key, text.clone()),
+                other => (key, other.to_string()),
+            })
+            .collect();
+        if !entries.is_empty() {
+            long(&mut out, entries.len() as i64);
+            for (key, value) in &entries {
+                string(&mut out, key);
+                string(&mut out, value);
+            }
+        }
+        long(&mut out, 0);
+
+        match &event.reference {
+            Some(reference) => {
+                long(&mut out, 1);
+                string(&mut out, reference);
+            }
+            None => long(&mut out, 0),
+        }
+        out
+    }
+}
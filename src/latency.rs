@@ -0,0 +1,56 @@
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
+
+// ============================================================================
+// Latency Injection
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Latency {
+    Fixed { ms: u64 },
+    Uniform { min_ms: u64, max_ms: u64 },
+    Normal { mean_ms: f64, std_dev_ms: f64 },
+}
+
+/// Default latency per endpoint, used when the matched rule sets none.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndpointLatency {
+    pub authorize: Option<Latency>,
+    pub reversal: Option<Latency>,
+}
+
+impl Latency {
+    pub fn sample(&self) -> Duration {
+        let mut rng = rand::thread_rng();
+        let ms = match *self {
+            Latency::Fixed { ms } => ms,
+            Latency::Uniform { min_ms, max_ms } if min_ms < max_ms => {
+                rng.gen_range(min_ms..=max_ms)
+            }
+            Latency::Uniform { min_ms, .. } => min_ms,
+            Latency::Normal {
+                mean_ms,
+                std_dev_ms,
+            } => match Normal::new(mean_ms, std_dev_ms) {
+                // Negative samples clamp to zero rather than being re-drawn.
+                Ok(normal) => normal.sample(&mut rng).max(0.0).round() as u64,
+                Err(_) => mean_ms.max(0.0) as u64,
+            },
+        };
+        Duration::from_millis(ms)
+    }
+}
+
+pub async fn inject(latency: Option<&Latency>) {
+    if let Some(latency) = latency {
+        let delay = latency.sample();
+        if !delay.is_zero() {
+            debug!("Injecting latency: {} ms", delay.as_millis());
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
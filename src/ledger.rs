@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+use crate::cards::{CardDeck, CardProfile};
+
+// ============================================================================
+// Simulated Account Ledger
+// ============================================================================
+//
+// Amounts are minor units. Approvals place a hold against the available
+// balance, completions draw it down as they capture, reversals release it,
+// and refunds/OCTs credit the ledger balance. Single-message approvals debit
+// the ledger balance directly.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub pan: String,
+    pub ledger_balance: i64,
+    pub credit_limit: i64,
+    /// Outstanding authorization holds keyed by transaction key (DE32:STAN).
+    pub holds: HashMap<String, i64>,
+}
+
+impl Account {
+    pub fn held(&self) -> i64 {
+        self.holds
+            .values()
+            .fold(0i64, |held, amount| held.saturating_add(*amount))
+    }
+
+    pub fn available_balance(&self) -> i64 {
+        self.ledger_balance
+            .saturating_add(self.credit_limit)
+            .saturating_sub(self.held())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LedgerError {
+    InsufficientFunds,
+}
+
+pub struct Ledger {
+    deck: RwLock<CardDeck>,
+    accounts: Mutex<HashMap<String, Account>>,
+}
+
+impl Ledger {
+    pub fn new(deck: CardDeck) -> Self {
+        Ledger {
+            deck: RwLock::new(deck),
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn open_account(&self, pan: &str) -> Account {
+        let deck = self.deck.read().unwrap();
+        let profile = deck.find(pan);
+        Account {
+            pan: pan.to_string(),
+            ledger_balance: profile
+                .and_then(|p| p.balance)
+                .unwrap_or(deck.defaults.balance),
+            credit_limit: profile
+                .and_then(|p| p.credit_limit)
+                .unwrap_or(deck.defaults.credit_limit),
+            holds: HashMap::new(),
+        }
+    }
+
+    /// Closes every account; they reopen from `deck` on next use.
+    pub fn reset(&self, deck: CardDeck) {
+        // Same lock order as `with_account`: accounts, then deck.
+        let mut accounts = self.accounts.lock().unwrap();
+        accounts.clear();
+        *self.deck.write().unwrap() = deck;
+    }
+
+    /// Adds or replaces a card in the deck and reopens its account from it.
+    pub fn seed_card(&self, profile: CardProfile) {
+        let mut accounts = self.accounts.lock().unwrap();
+        accounts.remove(&profile.pan);
+        let mut deck = self.deck.write().unwrap();
+        deck.cards.retain(|card| card.pan != profile.pan);
+        deck.cards.push(profile);
+    }
+
+    fn with_account<T>(&self, pan: &str, f: impl FnOnce(&mut Account) -> T) -> T {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts
+            .entry(pan.to_string())
+            .or_insert_with(|| self.open_account(pan));
+        f(account)
+    }
+
+    /// Places a hold for an approved authorization.
+    pub fn hold(&self, pan: &str, key: &str, amount: i64) -> Result<(), LedgerError> {
+        self.with_account(pan, |account| {
+            if amount > account.available_balance() {
+                return Err(LedgerError::InsufficientFunds);
+            }
+            account.holds.insert(key.to_string(), amount);
+            Ok(())
+        })
+    }
+
+    /// Debits an approved single-message transaction without a hold.
+    pub fn debit(&self, pan: &str, amount: i64) -> Result<(), LedgerError> {
+        self.with_account(pan, |account| {
+            if amount > account.available_balance() {
+                return Err(LedgerError::InsufficientFunds);
+            }
+            account.ledger_balance = account.ledger_balance.saturating_sub(amount);
+            Ok(())
+        })
+    }
+
+    /// Releases the hold placed by the authorization with this key, if any.
+    pub fn release(&self, pan: &str, key: &str) -> Option<i64> {
+        self.with_account(pan, |account| account.holds.remove(key))
+    }
+
+    /// Posts a captured amount: takes it off the hold, dropping the hold
+    /// once used up, and debits the ledger balance.
+    pub fn post(&self, pan: &str, key: &str, amount: i64) {
+        self.with_account(pan, |account| {
+            if let Some(held) = account.holds.get_mut(key) {
+                *held = held.saturating_sub(amount);
+                if *held <= 0 {
+                    account.holds.remove(key);
+                }
+            }
+            account.ledger_balance = account.ledger_balance.saturating_sub(amount);
+        })
+    }
+
+    /// Snapshot of an account, opening it if it has not been used yet.
+    pub fn account(&self, pan: &str) -> Account {
+        self.with_account(pan, |account| account.clone())
+    }
+
+    /// Accounts that have been opened, ordered by PAN.
+    pub fn accounts(&self) -> Vec<Account> {
+        let mut accounts: Vec<Account> = self.accounts.lock().unwrap().values().cloned().collect();
+        accounts.sort_by(|a, b| a.pan.cmp(&b.pan));
+        accounts
+    }
+
+    /// Overwrites the balance and/or credit limit, keeping outstanding holds.
+    pub fn update(&self, pan: &str, balance: Option<i64>, credit_limit: Option<i64>) -> Account {
+        self.with_account(pan, |account| {
+            if let Some(balance) = balance {
+                account.ledger_balance = balance;
+            }
+            if let Some(credit_limit) = credit_limit {
+                account.credit_limit = credit_limit;
+            }
+            account.clone()
+        })
+    }
+
+    /// Applies a signed adjustment to the ledger balance; it may go negative.
+    pub fn adjust(&self, pan: &str, amount: i64) -> Account {
+        self.with_account(pan, |account| {
+            account.ledger_balance = account.ledger_balance.saturating_add(amount);
+            account.clone()
+        })
+    }
+
+    /// The deck accounts open from, and every open account.
+    pub fn snapshot(&self) -> (CardDeck, Vec<Account>) {
+        let accounts = self.accounts();
+        (self.deck.read().unwrap().clone(), accounts)
+    }
+
+    /// Replaces the deck and all accounts.
+    pub fn restore(&self, deck: CardDeck, accounts: Vec<Account>) {
+        let mut open = self.accounts.lock().unwrap();
+        *open = accounts
+            .into_iter()
+            .map(|account| (account.pan.clone(), account))
+            .collect();
+        *self.deck.write().unwrap() = deck;
+    }
+
+    /// Closes an account; it reopens from the deck on next use.
+    pub fn close(&self, pan: &str) -> bool {
+        self.accounts.lock().unwrap().remove(pan).is_some()
+    }
+
+    /// Credits the ledger balance (refunds, payments, OCTs).
+    pub fn credit(&self, pan: &str, amount: i64) {
+        self.with_account(pan, |account| {
+            account.ledger_balance = account.ledger_balance.saturating_add(amount)
+        })
+    }
+}
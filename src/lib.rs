@@ -0,0 +1,113 @@
+//! Mastercard ISO 8583 mock issuer.
+//!
+//! The `mastercard-api` binary is a thin wrapper around this crate; Rust
+//! projects can embed the same simulator in their integration tests instead
+//! of spawning a process:
+//!
+//! ```no_run
+//! use mastercard_api::{server, AppState, Config};
+//!
+//! # async fn run() -> Result<(), String> {
+//! let state = AppState::new(Config::default())?;
+//! let addr = server::spawn(state.clone()).await.map_err(|e| e.to_string())?;
+//! // POST ISO 8583 JSON messages to http://{addr}/authorize ...
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod acquirers;
+pub mod admin;
+pub mod amount;
+pub mod audit;
+pub mod auth;
+pub mod batch;
+pub mod bins;
+pub mod builder;
+pub mod card_generator;
+pub mod cards;
+pub mod clearing;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod clock;
+pub mod codes;
+pub mod contactless;
+pub mod correlation;
+pub mod country;
+pub mod currency;
+pub mod cutover;
+pub mod dashboard;
+pub mod dcc;
+pub mod de48;
+pub mod de7;
+pub mod deferred;
+pub mod dispatch;
+pub mod dsrp;
+pub mod error;
+pub mod expiry;
+pub mod export;
+pub mod fallback;
+pub mod faults;
+pub mod feed;
+pub mod fraud;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod handlers;
+pub mod health;
+pub mod incidents;
+pub mod installments;
+pub mod interchange;
+pub mod iso8583;
+pub mod issuers;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod latency;
+pub mod ledger;
+pub mod logging;
+pub mod masking;
+pub mod matching;
+pub mod mcc;
+pub mod merchants;
+pub mod messages;
+pub mod monitor;
+pub mod openapi;
+pub mod originals;
+pub mod pan;
+pub mod pipeline;
+pub mod processing;
+pub mod profiles;
+pub mod random_decline;
+pub mod rate_limit;
+pub mod reference;
+pub mod refunds;
+pub mod replay;
+pub mod rules;
+pub mod scenarios;
+pub mod scripting;
+pub mod server;
+pub mod settings;
+pub mod settlement;
+pub mod snapshot;
+pub mod stan;
+pub mod state;
+pub mod store;
+pub mod stored_credentials;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+pub mod tokenization;
+pub mod tokens;
+pub mod transactions;
+pub mod ucaf;
+pub mod validation;
+pub mod webhooks;
+pub mod wire;
+
+pub use codes::{ProcessingCode, ResponseCode};
+pub use iso8583::Iso8583Message;
+pub use messages::{
+    AuthorizationRequest, AuthorizationResponse, CompletionRequest, CompletionResponse,
+    ReversalRequest, ReversalResponse,
+};
+pub use state::{AppState, Config};
+pub use store::{generate_rrn, transaction_key, Transaction, TransactionState};
@@ -0,0 +1,53 @@
+use std::io::IsTerminal;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use crate::settings::Settings;
+
+// ============================================================================
+// Logging
+// ============================================================================
+//
+//   LOG_LEVEL   filter directives, e.g. `info` (default) or
+//               `mastercard_api=debug` to include full message payloads
+//               (RUST_LOG is honoured when LOG_LEVEL is unset)
+//   LOG_FORMAT  `text` (default) or `json` for log aggregation
+//
+// Built with the `otel` feature, spans are also exported over OTLP when
+// OTEL_EXPORTER_OTLP_ENDPOINT is set (see `telemetry`).
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+pub fn init(settings: &Settings) -> LogFormat {
+    let filter = match settings.var("LOG_LEVEL") {
+        Some(directives) => EnvFilter::try_new(directives).ok(),
+        None => EnvFilter::try_from_default_env().ok(),
+    }
+    .unwrap_or_else(|| EnvFilter::new("info"));
+
+    let format = match settings.var("LOG_FORMAT").as_deref() {
+        Some("json") => LogFormat::Json,
+        Some("text") | None => LogFormat::Text,
+        Some(other) => panic!("LOG_FORMAT must be text or json, got {}", other),
+    };
+
+    #[cfg(feature = "otel")]
+    let otel = crate::telemetry::layer();
+    #[cfg(not(feature = "otel"))]
+    let otel: Option<tracing_subscriber::layer::Identity> = None;
+
+    let registry = tracing_subscriber::registry().with(filter).with(otel);
+    let ansi = std::io::stdout().is_terminal();
+    match format {
+        LogFormat::Json => registry
+            .with(fmt::layer().json().with_current_span(true).with_ansi(ansi))
+            .init(),
+        LogFormat::Text => registry.with(fmt::layer().with_ansi(ansi)).init(),
+    }
+    format
+}
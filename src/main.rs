@@ -1,281 +1,248 @@
-use axum::{
-    extract::{State, Json},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::post,
-    Router,
+use mastercard_api::settings::Settings;
+use mastercard_api::{
+    cutover, expiry, logging, masking, replay, scenarios, server, AppState, Config,
 };
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tower_http::cors::CorsLayer;
 use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::Ordering;
+use tracing::{info, warn};
 
 // ============================================================================
-// Data Structures for Mastercard ISO 8583
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AuthorizationRequest {
-    pub mti: String,
-    pub de2: String,
-    pub de3: String,
-    pub de4: String,
-    pub de7: String,
-    pub de11: String,
-    pub de18: String,
-    pub de32: String,
-    pub de48: String,
-    pub de49: String,
-    pub de61: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AuthorizationResponse {
-    pub mti: String,
-    pub de2: String,
-    pub de3: String,
-    pub de4: String,
-    pub de7: String,
-    pub de11: String,
-    pub de18: String,
-    pub de32: String,
-    pub de39: String,
-    pub de48: String,
-    pub de49: String,
-    pub de61: String,
-    pub response_message: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReversalRequest {
-    pub mti: String,
-    pub de2: String,
-    pub de3: String,
-    pub de4: String,
-    pub de7: String,
-    pub de11: String,
-    pub de18: String,
-    pub de22: String,
-    pub de32: String,
-    pub de39: String,
-    pub de48: String,
-    pub de49: String,
-    pub de61: String,
-    pub de90: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReversalResponse {
-    pub mti: String,
-    pub de2: String,
-    pub de3: String,
-    pub de4: String,
-    pub de7: String,
-    pub de11: String,
-    pub de18: String,
-    pub de32: String,
-    pub de39: String,
-    pub de48: String,
-    pub de49: String,
-    pub de61: String,
-    pub de90: String,
-    pub response_message: String,
-}
-
-// ============================================================================
-// Transaction Storage
-// ============================================================================
-
-#[derive(Debug, Clone)]
-pub struct Transaction {
-    pub pan: String,
-    pub amount: String,
-    pub stan: String,
-    pub timestamp: String,
-    pub response_code: String,
-}
-
-pub struct AppState {
-    pub authorized_transactions: Mutex<HashMap<String, Transaction>>,
-}
-
-// ============================================================================
-// Request Handlers
+// Main Application (Render-Compatible)
 // ============================================================================
 
-async fn authorize(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<AuthorizationRequest>,
-) -> impl IntoResponse {
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("scenarios") => process::exit(run_scenarios(&args[1..]).await),
+        Some("replay") => process::exit(run_replay(&args[1..]).await),
+        _ => {}
+    }
 
-    println!("\n========== AUTHORIZATION REQUEST ==========");
-    println!("{}", serde_json::to_string_pretty(&payload).unwrap());
+    let settings =
+        Settings::from_args(args).unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+    let log_format = logging::init(&settings);
+    // --log-sensitive or LOG_SENSITIVE=true: log, store and export PANs and
+    // track/PIN data unmasked. Local debugging only.
+    masking::set_log_sensitive(
+        settings
+            .var("LOG_SENSITIVE")
+            .is_some_and(|value| value == "true" || value == "1"),
+    );
+    if masking::log_sensitive() {
+        warn!("Sensitive logging enabled: PANs, track and PIN data are not masked");
+    }
 
-    if payload.mti != "0100" {
-        let response = AuthorizationResponse {
-            mti: "0110".to_string(),
-            de2: payload.de2.clone(),
-            de3: payload.de3.clone(),
-            de4: payload.de4.clone(),
-            de7: payload.de7.clone(),
-            de11: payload.de11.clone(),
-            de18: payload.de18.clone(),
-            de32: payload.de32.clone(),
-            de39: "03".to_string(),
-            de48: payload.de48.clone(),
-            de49: payload.de49.clone(),
-            de61: payload.de61.clone(),
-            response_message: "Invalid MTI for Authorization Request".to_string(),
-        };
+    let config = Config::from_settings(&settings);
+    let state = AppState::new(config).expect("Failed to initialize simulator");
 
-        return (StatusCode::OK, Json(response));
-    }
+    expiry::spawn_sweeper(state.clone());
+    cutover::spawn_scheduler(state.clone());
 
-    let response_code = if payload.de2.starts_with('4') {
-        "00"
+    let storage_backend = state.authorized_transactions.backend();
+    let admin_auth = if state.admin_api_keys.is_empty() {
+        "open (set ADMIN_API_KEYS to protect)"
     } else {
-        "05"
+        "API key required"
     };
+    let app = server::router(state.clone());
 
-    if response_code == "00" {
-        let transaction = Transaction {
-            pan: payload.de2.clone(),
-            amount: payload.de4.clone(),
-            stan: payload.de11.clone(),
-            timestamp: payload.de7.clone(),
-            response_code: response_code.to_string(),
-        };
+    // ✅ REQUIRED FOR RENDER
+    let port = settings.var("PORT").unwrap_or_else(|| "3000".to_string());
+    let host = settings.var("BIND_ADDR").unwrap_or_else(|| "0.0.0.0".to_string());
+    let bind_addr = format!("{}:{}", host, port);
 
-        state
-            .authorized_transactions
-            .lock()
-            .unwrap()
-            .insert(payload.de11.clone(), transaction);
+    let listener = tokio::net::TcpListener::bind(&bind_addr)
+        .await
+        .expect("Failed to bind to port");
+    state.listening.store(true, Ordering::Relaxed);
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = settings.var("GRPC_PORT") {
+        let grpc_addr: std::net::SocketAddr = format!("{}:{}", host, grpc_port)
+            .parse()
+            .expect("Invalid GRPC_PORT or BIND_ADDR for gRPC");
+        tokio::spawn(mastercard_api::grpc::serve(grpc_addr, app.clone()));
+        info!(addr = %grpc_addr, "gRPC listening");
+    }
+    #[cfg(not(feature = "grpc"))]
+    if settings.var("GRPC_PORT").is_some() {
+        warn!("GRPC_PORT is set, but this build has no gRPC support (feature `grpc`)");
     }
 
-    let response = AuthorizationResponse {
-        mti: "0110".to_string(),
-        de2: payload.de2.clone(),
-        de3: payload.de3.clone(),
-        de4: payload.de4.clone(),
-        de7: payload.de7.clone(),
-        de11: payload.de11.clone(),
-        de18: payload.de18.clone(),
-        de32: payload.de32.clone(),
-        de39: response_code.to_string(),
-        de48: payload.de48.clone(),
-        de49: payload.de49.clone(),
-        de61: payload.de61.clone(),
-        response_message: if response_code == "00" {
-            "Transaction Approved".to_string()
-        } else {
-            "Transaction Not Authorized".to_string()
-        },
-    };
+    #[cfg(feature = "kafka")]
+    if let Some(kafka) = mastercard_api::kafka::KafkaConfig::from_settings(&settings)
+        .unwrap_or_else(|e| panic!("Invalid Kafka configuration: {}", e))
+    {
+        mastercard_api::kafka::spawn(kafka, &state);
+    }
+    #[cfg(not(feature = "kafka"))]
+    if settings.var("KAFKA_BROKERS").is_some() {
+        warn!("KAFKA_BROKERS is set, but this build has no Kafka support (feature `kafka`)");
+    }
 
-    println!("\n========== AUTHORIZATION RESPONSE ==========");
-    println!("{}", serde_json::to_string_pretty(&response).unwrap());
+    if log_format == logging::LogFormat::Text {
+        println!("\n╔════════════════════════════════════════════════════════════════╗");
+        println!("║   Mastercard ISO 8583 Mock API Server (Rust + Axum)           ║");
+        println!("║   Server running on {}", bind_addr);
+        println!("║   Storage: {}", storage_backend);
+        println!("║   Admin API: {}", admin_auth);
+        println!("║                                                                ║");
+        println!("║   POST /authorize  → MTI 0100                                 ║");
+        println!("║   POST /reversal   → MTI 0400                                 ║");
+        println!("║   POST /completion → MTI 0220                                 ║");
+        println!("╚════════════════════════════════════════════════════════════════╝\n");
+    }
+    info!(addr = %bind_addr, storage = storage_backend, admin_api = admin_auth, "server listening");
 
-    (StatusCode::OK, Json(response))
+    server::serve(listener, app, server::Limits::from_settings(&settings)).await;
 }
 
-async fn reversal(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<ReversalRequest>,
-) -> impl IntoResponse {
-
-    println!("\n========== REVERSAL REQUEST ==========");
-    println!("{}", serde_json::to_string_pretty(&payload).unwrap());
+// ============================================================================
+// Scenario Runner and Session Replay
+// ============================================================================
 
-    if payload.mti != "0400" {
-        let response = ReversalResponse {
-            mti: "0410".to_string(),
-            de2: payload.de2.clone(),
-            de3: payload.de3.clone(),
-            de4: payload.de4.clone(),
-            de7: payload.de7.clone(),
-            de11: payload.de11.clone(),
-            de18: payload.de18.clone(),
-            de32: payload.de32.clone(),
-            de39: "03".to_string(),
-            de48: payload.de48.clone(),
-            de49: payload.de49.clone(),
-            de61: payload.de61.clone(),
-            de90: payload.de90.clone(),
-            response_message: "Invalid MTI for Reversal Request".to_string(),
-        };
+/// `--target`, or an embedded mock set up from the environment and
+/// CONFIG_FILE like the server.
+async fn target_url(target: Option<String>) -> Result<String, String> {
+    if let Some(url) = target {
+        return Ok(url);
+    }
+    let settings = Settings::from_args(Vec::new())
+        .map_err(|e| format!("Invalid configuration: {}", e))?;
+    let state = AppState::new(Config::from_settings(&settings))
+        .map_err(|e| format!("Invalid configuration: {}", e))?;
+    let addr = server::spawn(state)
+        .await
+        .map_err(|e| format!("cannot start the embedded mock: {}", e))?;
+    Ok(format!("http://{}", addr))
+}
 
-        return (StatusCode::OK, Json(response));
+/// `mastercard-api scenarios [--target <url>] <file or dir>...` runs the
+/// scenario files. Exits 1 when any scenario fails, 2 when they can't be
+/// run.
+async fn run_scenarios(args: &[String]) -> i32 {
+    let mut target = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => match args.next() {
+                Some(url) => target = Some(url.clone()),
+                None => {
+                    eprintln!("--target needs a URL");
+                    return 2;
+                }
+            },
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: mastercard-api scenarios [--target <url>] <file or dir>...");
+        return 2;
     }
 
-    let transactions = state.authorized_transactions.lock().unwrap();
-    let response_code = if transactions.contains_key(&payload.de11) {
-        "00"
-    } else {
-        "94"
+    let scenarios = match scenarios::load_all(&paths) {
+        Ok(scenarios) => scenarios,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
     };
 
-    let response = ReversalResponse {
-        mti: "0410".to_string(),
-        de2: payload.de2.clone(),
-        de3: payload.de3.clone(),
-        de4: payload.de4.clone(),
-        de7: payload.de7.clone(),
-        de11: payload.de11.clone(),
-        de18: payload.de18.clone(),
-        de32: payload.de32.clone(),
-        de39: response_code.to_string(),
-        de48: payload.de48.clone(),
-        de49: payload.de49.clone(),
-        de61: payload.de61.clone(),
-        de90: payload.de90.clone(),
-        response_message: if response_code == "00" {
-            "Reversal Approved".to_string()
-        } else {
-            "Duplicate Reversal or Original Not Found".to_string()
-        },
+    let base_url = match target_url(target).await {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
     };
 
-    println!("\n========== REVERSAL RESPONSE ==========");
-    println!("{}", serde_json::to_string_pretty(&response).unwrap());
-
-    (StatusCode::OK, Json(response))
+    let mut failed = 0;
+    for scenario in &scenarios {
+        let report = scenario.run(&base_url).await;
+        print!("{}", report);
+        if !report.passed() {
+            failed += 1;
+        }
+    }
+    println!(
+        "{} scenarios, {} passed, {} failed",
+        scenarios.len(),
+        scenarios.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
 }
 
-// ============================================================================
-// Main Application (Render-Compatible)
-// ============================================================================
-
-#[tokio::main]
-async fn main() {
-    let state = Arc::new(AppState {
-        authorized_transactions: Mutex::new(HashMap::new()),
-    });
-
-    let app = Router::new()
-        .route("/authorize", post(authorize))
-        .route("/reversal", post(reversal))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
-
-    // ✅ REQUIRED FOR RENDER
-    let port = env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let bind_addr = format!("0.0.0.0:{}", port);
-
-    let listener = tokio::net::TcpListener::bind(&bind_addr)
-        .await
-        .expect("Failed to bind to port");
+/// `mastercard-api replay [--target <url>] [--speed <n|max>] [--seed <n>]
+/// <audit log>` resends a recorded session. Exits 1 when any response
+/// differs from the recording, 2 when it can't be replayed.
+async fn run_replay(args: &[String]) -> i32 {
+    let mut target = None;
+    let mut pace = replay::Pace::Speed(1.0);
+    let mut seed = 0;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            path = Some(PathBuf::from(arg));
+            continue;
+        }
+        let Some(value) = args.next() else {
+            eprintln!("{} needs a value", arg);
+            return 2;
+        };
+        let parsed = match arg.as_str() {
+            "--target" => {
+                target = Some(value.clone());
+                Ok(())
+            }
+            "--speed" => replay::Pace::parse(value).map(|parsed| pace = parsed),
+            "--seed" => value
+                .parse()
+                .map(|parsed| seed = parsed)
+                .map_err(|_| format!("invalid seed {:?}", value)),
+            _ => Err(format!("unknown option {}", arg)),
+        };
+        if let Err(e) = parsed {
+            eprintln!("{}", e);
+            return 2;
+        }
+    }
+    let Some(path) = path else {
+        eprintln!(
+            "usage: mastercard-api replay [--target <url>] [--speed <n|max>] [--seed <n>] <audit log>"
+        );
+        return 2;
+    };
 
-    println!("\n╔════════════════════════════════════════════════════════════════╗");
-    println!("║   Mastercard ISO 8583 Mock API Server (Rust + Axum)           ║");
-    println!("║   Server running on {}", bind_addr);
-    println!("║                                                                ║");
-    println!("║   POST /authorize  → MTI 0100                                 ║");
-    println!("║   POST /reversal   → MTI 0400                                 ║");
-    println!("╚════════════════════════════════════════════════════════════════╝\n");
+    let entries = match replay::load(&path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let base_url = match target_url(target).await {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    let report = replay::run(&entries, &base_url, pace, seed).await;
+    print!("{}", report);
+    if report.divergences.is_empty() {
+        0
+    } else {
+        1
+    }
 }
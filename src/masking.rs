@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::de48::De48;
+
+// ============================================================================
+// PAN Masking / PCI-Safe Output
+// ============================================================================
+//
+// Anything that leaves the process (logs, stored payloads, query API,
+// exports, webhooks) goes through here: DE2 is masked to first 6 / last 4,
+// track and PIN data (DE35, DE45, DE52) is dropped, and CVC2 (DE48 SE92 or a
+// `cvc2` field) is replaced by a hash. `--log-sensitive` turns this off for
+// local debugging.
+
+/// Fields never written out: track 2, track 1, PIN block.
+const SUPPRESSED_FIELDS: &[&str] = &["de35", "de45", "de52"];
+const CVC2_SUBELEMENT: &str = "92";
+
+static LOG_SENSITIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_log_sensitive(enabled: bool) {
+    LOG_SENSITIVE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn log_sensitive() -> bool {
+    LOG_SENSITIVE.load(Ordering::Relaxed)
+}
+
+/// Keeps the first 6 and last 4 digits: 411111******1111.
+pub fn mask_pan(pan: &str) -> String {
+    if pan.len() <= 10 || !pan.is_ascii() {
+        return "*".repeat(pan.len());
+    }
+    format!(
+        "{}{}{}",
+        &pan[..6],
+        "*".repeat(pan.len() - 10),
+        &pan[pan.len() - 4..]
+    )
+}
+
+/// `sha256:` and the first 16 hex digits, enough to correlate values
+/// without revealing them.
+pub fn hash_value(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let hex: String = digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256:{}", hex)
+}
+
+/// Masks DE2, drops DE35/DE45/DE52 and hashes CVC2 in a JSON message payload.
+pub fn mask_payload(mut payload: Value) -> Value {
+    let Some(object) = payload.as_object_mut() else {
+        return payload;
+    };
+    if let Some(Value::String(pan)) = object.get_mut("de2") {
+        *pan = mask_pan(pan);
+    }
+    for field in SUPPRESSED_FIELDS {
+        object.remove(*field);
+    }
+    if let Some(Value::String(cvc2)) = object.get_mut("cvc2") {
+        *cvc2 = hash_value(cvc2);
+    }
+    if let Some(Value::String(de48)) = object.get_mut("de48") {
+        if let Ok(mut parsed) = De48::parse(de48) {
+            if let Some(cvc2) = parsed.get(CVC2_SUBELEMENT).map(hash_value) {
+                parsed.set(CVC2_SUBELEMENT, &cvc2);
+                *de48 = parsed.encode();
+            }
+        }
+    }
+    payload
+}
+
+/// PAN as it may appear in logs.
+pub fn pan_for_log(pan: &str) -> String {
+    if log_sensitive() {
+        pan.to_string()
+    } else {
+        mask_pan(pan)
+    }
+}
+
+/// A message rendered as JSON for logs.
+pub fn payload_for_log(message: &impl Serialize) -> String {
+    let value = serde_json::to_value(message).unwrap_or_default();
+    let value = if log_sensitive() {
+        value
+    } else {
+        mask_payload(value)
+    };
+    value.to_string()
+}
@@ -0,0 +1,306 @@
+use axum::{body::Bytes, extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::amount;
+use crate::clearing::{self, IpmMessage, FUNCTION_FIRST_PRESENTMENT};
+use crate::error::ApiJson;
+use crate::masking;
+use crate::{AppState, Transaction};
+
+// ============================================================================
+// Authorization-to-Clearing Matching
+// ============================================================================
+//
+// Matches clearing records (JSON, or the 1240s of an IPM file) to stored
+// authorizations by trace ID, RRN, then approval code, and reports each in
+// GET /clearing/report without touching transactions or balances.
+
+/// A clearing record in the simplified JSON form.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClearingRecord {
+    /// Masked in reports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de2: Option<String>,
+    /// Clearing amount in minor units.
+    pub de4: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de32: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de37: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de38: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de63: Option<String>,
+}
+
+impl ClearingRecord {
+    /// The record of a first presentment; `None` for other IPM messages.
+    pub fn from_ipm(message: &IpmMessage) -> Option<Self> {
+        if message.mti != "1240" || message.function_code() != Some(FUNCTION_FIRST_PRESENTMENT) {
+            return None;
+        }
+        let field = |number: u8| message.fields.get(&number).cloned();
+        Some(ClearingRecord {
+            de2: field(2),
+            de4: field(4).unwrap_or_default(),
+            de32: field(94),
+            de37: field(37),
+            de38: field(38),
+            de63: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStatus {
+    Matched,
+    AmountExceeded,
+    Duplicate,
+    Unmatched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKey {
+    De63,
+    Rrn,
+    ApprovalCode,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MatchResult {
+    pub record: ClearingRecord,
+    pub status: MatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_by: Option<MatchKey>,
+    /// Store key (DE32:STAN) of the matched authorization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_amount: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct MatchingReport {
+    pub total: usize,
+    pub matched: usize,
+    pub amount_exceeded: usize,
+    pub duplicate: usize,
+    pub unmatched: usize,
+    pub items: Vec<MatchResult>,
+}
+
+impl MatchingReport {
+    fn new(items: Vec<MatchResult>) -> Self {
+        let count = |status| items.iter().filter(|item| item.status == status).count();
+        MatchingReport {
+            total: items.len(),
+            matched: count(MatchStatus::Matched),
+            amount_exceeded: count(MatchStatus::AmountExceeded),
+            duplicate: count(MatchStatus::Duplicate),
+            unmatched: count(MatchStatus::Unmatched),
+            items,
+        }
+    }
+}
+
+/// Same BIN and last four digits; never for PANs too short (or, as
+/// clearing records may carry, not ASCII) to have both.
+fn same_card(record_pan: &str, pan: &str) -> bool {
+    record_pan.len() >= 10
+        && pan.len() >= 10
+        && card_ends(record_pan).is_some_and(|ends| card_ends(pan) == Some(ends))
+}
+
+/// First six and last four characters.
+fn card_ends(pan: &str) -> Option<(&str, &str)> {
+    Some((pan.get(..6)?, pan.get(pan.len() - 4..)?))
+}
+
+/// The authorization `record` clears, and which key found it.
+fn find<'a>(
+    record: &ClearingRecord,
+    transactions: &'a [Transaction],
+) -> Option<(MatchKey, &'a Transaction)> {
+    let candidates = || {
+        transactions.iter().filter(|transaction| {
+            record
+                .de32
+                .as_deref()
+                .is_none_or(|de32| transaction.acquirer_id == de32)
+        })
+    };
+    let by = |key: MatchKey, value: Option<&str>, test: &dyn Fn(&Transaction, &str) -> bool| {
+        let value = value.filter(|value| !value.is_empty())?;
+        candidates()
+            .find(|transaction| test(transaction, value))
+            .map(|transaction| (key, transaction))
+    };
+    by(
+        MatchKey::De63,
+        record.de63.as_deref(),
+        &|transaction, de63| transaction.request_field("de63") == Some(de63),
+    )
+    .or_else(|| {
+        by(
+            MatchKey::Rrn,
+            record.de37.as_deref(),
+            &|transaction, rrn| transaction.rrn == rrn,
+        )
+    })
+    .or_else(|| {
+        by(
+            MatchKey::ApprovalCode,
+            record.de38.as_deref(),
+            &|transaction, code| {
+                transaction.approval_code() == Some(code)
+                    && record
+                        .de2
+                        .as_deref()
+                        .is_some_and(|pan| same_card(pan, &transaction.pan))
+            },
+        )
+    })
+}
+
+/// Match results so far and the authorizations they matched.
+#[derive(Debug, Default)]
+pub struct MatchLog {
+    inner: Mutex<(Vec<MatchResult>, HashSet<String>)>,
+}
+
+impl MatchLog {
+    /// Matches `records` against `transactions` and adds the results to
+    /// the log.
+    pub fn submit(
+        &self,
+        records: Vec<ClearingRecord>,
+        transactions: &[Transaction],
+        tolerance_percent: f64,
+    ) -> MatchingReport {
+        let mut inner = self.inner.lock().unwrap();
+        let (log, matched) = &mut *inner;
+        let items: Vec<MatchResult> = records
+            .into_iter()
+            .map(|mut record| {
+                let found = find(&record, transactions);
+                let status = match found {
+                    None => MatchStatus::Unmatched,
+                    Some((_, transaction)) if !matched.insert(transaction.key()) => {
+                        MatchStatus::Duplicate
+                    }
+                    Some((_, transaction)) => {
+                        let authorized =
+                            amount::parse_minor_units(&transaction.amount).unwrap_or(0) as f64;
+                        let cleared =
+                            amount::parse_minor_units(&record.de4).unwrap_or(i64::MAX) as f64;
+                        if cleared > authorized * (1.0 + tolerance_percent / 100.0) {
+                            MatchStatus::AmountExceeded
+                        } else {
+                            MatchStatus::Matched
+                        }
+                    }
+                };
+                record.de2 = record.de2.as_deref().map(masking::mask_pan);
+                MatchResult {
+                    record,
+                    status,
+                    matched_by: found.map(|(key, _)| key),
+                    transaction: found.map(|(_, transaction)| transaction.key()),
+                    authorized_amount: found.map(|(_, transaction)| transaction.amount.clone()),
+                }
+            })
+            .collect();
+        log.extend(items.iter().cloned());
+        MatchingReport::new(items)
+    }
+
+    pub fn report(&self) -> MatchingReport {
+        MatchingReport::new(self.inner.lock().unwrap().0.clone())
+    }
+
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = Default::default();
+    }
+}
+
+// ============================================================================
+// Endpoints
+// ============================================================================
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ClearingBatch {
+    pub records: Vec<ClearingRecord>,
+}
+
+fn match_records(
+    state: &AppState,
+    records: Vec<ClearingRecord>,
+) -> Result<Json<MatchingReport>, (StatusCode, String)> {
+    let transactions = state
+        .authorized_transactions
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let report = state
+        .clearing_matches
+        .submit(records, &transactions, state.clearing_tolerance);
+    info!(
+        "Clearing matched: {} of {} ({} over tolerance, {} duplicate, {} unmatched)",
+        report.matched, report.total, report.amount_exceeded, report.duplicate, report.unmatched
+    );
+    Ok(Json(report))
+}
+
+/// Matches clearing records to stored authorizations.
+#[utoipa::path(
+    post,
+    path = "/clearing",
+    tag = "clearing",
+    request_body = ClearingBatch,
+    responses((status = 200, description = "Results for these records", body = MatchingReport))
+)]
+pub async fn submit_clearing(
+    State(state): State<Arc<AppState>>,
+    ApiJson(batch): ApiJson<ClearingBatch>,
+) -> Result<Json<MatchingReport>, (StatusCode, String)> {
+    match_records(&state, batch.records)
+}
+
+/// Matches the first presentments of an IPM file, blocked or not.
+#[utoipa::path(
+    post,
+    path = "/clearing/ipm",
+    tag = "clearing",
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Results for the file's presentments", body = MatchingReport),
+        (status = 400, description = "Not an IPM file", body = String),
+    )
+)]
+pub async fn submit_ipm(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<MatchingReport>, (StatusCode, String)> {
+    let messages = clearing::decode_file(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let records = messages
+        .iter()
+        .filter_map(ClearingRecord::from_ipm)
+        .collect();
+    match_records(&state, records)
+}
+
+/// Every result since transactions were last reset.
+#[utoipa::path(
+    get,
+    path = "/clearing/report",
+    tag = "clearing",
+    responses((status = 200, body = MatchingReport))
+)]
+pub async fn matching_report(State(state): State<Arc<AppState>>) -> Json<MatchingReport> {
+    Json(state.clearing_matches.report())
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// ============================================================================
+// ISO 18245 Merchant Category Codes
+// ============================================================================
+//
+// The MCCs DE18 is checked against, with their descriptions. The table is
+// served at /reference/mccs so client test data can be drawn from the same
+// list the mock validates with.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Mcc {
+    pub code: &'static str,
+    pub description: &'static str,
+}
+
+const fn m(code: &'static str, description: &'static str) -> Mcc {
+    Mcc { code, description }
+}
+
+pub const MCCS: &[Mcc] = &[
+    m("0742", "Veterinary Services"),
+    m("0763", "Agricultural Cooperatives"),
+    m("0780", "Landscaping and Horticultural Services"),
+    m("1520", "General Contractors - Residential and Commercial"),
+    m("1711", "Heating, Plumbing and Air Conditioning Contractors"),
+    m("1731", "Electrical Contractors"),
+    m("1799", "Special Trade Contractors"),
+    m("2741", "Miscellaneous Publishing and Printing"),
+    m("3000", "Airlines"),
+    m("3351", "Car Rental Agencies"),
+    m("3501", "Hotels and Motels"),
+    m("4011", "Railroads - Freight"),
+    m("4111", "Commuter Transport and Ferries"),
+    m("4112", "Passenger Railways"),
+    m("4121", "Taxicabs and Limousines"),
+    m("4131", "Bus Lines"),
+    m("4214", "Motor Freight Carriers and Trucking"),
+    m("4215", "Courier Services"),
+    m("4411", "Cruise Lines"),
+    m("4457", "Boat Rentals and Leasing"),
+    m("4468", "Marinas and Marine Service"),
+    m("4511", "Airlines and Air Carriers"),
+    m("4722", "Travel Agencies and Tour Operators"),
+    m("4784", "Tolls and Bridge Fees"),
+    m("4789", "Transportation Services"),
+    m("4812", "Telecommunication Equipment and Telephone Sales"),
+    m("4814", "Telecommunication Services"),
+    m("4816", "Computer Network and Information Services"),
+    m("4829", "Money Transfer"),
+    m("4899", "Cable, Satellite and Other Pay Television"),
+    m("4900", "Utilities - Electric, Gas, Water and Sanitary"),
+    m("5013", "Motor Vehicle Supplies and New Parts"),
+    m("5045", "Computers, Peripherals and Software"),
+    m("5111", "Stationery and Office Supplies"),
+    m("5122", "Drugs and Druggists' Sundries"),
+    m("5200", "Home Supply Warehouse Stores"),
+    m("5211", "Lumber and Building Materials Stores"),
+    m("5251", "Hardware Stores"),
+    m("5261", "Lawn and Garden Supply Stores"),
+    m("5300", "Wholesale Clubs"),
+    m("5309", "Duty Free Stores"),
+    m("5310", "Discount Stores"),
+    m("5311", "Department Stores"),
+    m("5331", "Variety Stores"),
+    m("5399", "Miscellaneous General Merchandise"),
+    m("5411", "Grocery Stores and Supermarkets"),
+    m("5422", "Freezer and Locker Meat Provisioners"),
+    m("5441", "Candy, Nut and Confectionery Stores"),
+    m("5451", "Dairy Products Stores"),
+    m("5462", "Bakeries"),
+    m("5499", "Miscellaneous Food Stores"),
+    m("5511", "Car and Truck Dealers - New and Used"),
+    m("5521", "Car and Truck Dealers - Used Only"),
+    m("5532", "Automotive Tire Stores"),
+    m("5533", "Automotive Parts and Accessories Stores"),
+    m("5541", "Service Stations"),
+    m("5542", "Automated Fuel Dispensers"),
+    m("5551", "Boat Dealers"),
+    m("5611", "Men's and Boys' Clothing Stores"),
+    m("5621", "Women's Ready-to-Wear Stores"),
+    m("5631", "Women's Accessory and Specialty Shops"),
+    m("5641", "Children's and Infants' Wear Stores"),
+    m("5651", "Family Clothing Stores"),
+    m("5655", "Sports and Riding Apparel Stores"),
+    m("5661", "Shoe Stores"),
+    m("5691", "Men's and Women's Clothing Stores"),
+    m("5699", "Miscellaneous Apparel and Accessory Shops"),
+    m("5712", "Furniture and Home Furnishings Stores"),
+    m("5722", "Household Appliance Stores"),
+    m("5732", "Electronics Stores"),
+    m("5733", "Music Stores"),
+    m("5734", "Computer Software Stores"),
+    m("5735", "Record Stores"),
+    m("5811", "Caterers"),
+    m("5812", "Eating Places and Restaurants"),
+    m("5813", "Bars, Taverns and Nightclubs"),
+    m("5814", "Fast Food Restaurants"),
+    m("5815", "Digital Goods - Media"),
+    m("5816", "Digital Goods - Games"),
+    m("5817", "Digital Goods - Applications"),
+    m("5818", "Digital Goods - Large Merchant"),
+    m("5912", "Drug Stores and Pharmacies"),
+    m("5921", "Package Stores - Beer, Wine and Liquor"),
+    m("5931", "Used Merchandise and Secondhand Stores"),
+    m("5932", "Antique Shops"),
+    m("5940", "Bicycle Shops"),
+    m("5941", "Sporting Goods Stores"),
+    m("5942", "Book Stores"),
+    m("5943", "Stationery Stores"),
+    m("5944", "Jewelry Stores"),
+    m("5945", "Hobby, Toy and Game Shops"),
+    m("5946", "Camera and Photographic Supply Stores"),
+    m("5947", "Gift, Card, Novelty and Souvenir Shops"),
+    m("5948", "Luggage and Leather Goods Stores"),
+    m("5949", "Sewing and Fabric Stores"),
+    m("5964", "Direct Marketing - Catalog Merchant"),
+    m("5965", "Direct Marketing - Combination Catalog and Retail"),
+    m("5966", "Direct Marketing - Outbound Telemarketing"),
+    m("5967", "Direct Marketing - Inbound Teleservices"),
+    m("5968", "Direct Marketing - Continuity/Subscription"),
+    m("5969", "Direct Marketing - Other"),
+    m("5970", "Artist's Supply and Craft Shops"),
+    m("5977", "Cosmetic Stores"),
+    m("5983", "Fuel Dealers"),
+    m("5992", "Florists"),
+    m("5993", "Cigar Stores and Stands"),
+    m("5994", "News Dealers and Newsstands"),
+    m("5995", "Pet Shops, Pet Food and Supplies"),
+    m("5999", "Miscellaneous and Specialty Retail Stores"),
+    m("6010", "Financial Institutions - Manual Cash Disbursements"),
+    m(
+        "6011",
+        "Financial Institutions - Automated Cash Disbursements",
+    ),
+    m("6012", "Financial Institutions - Merchandise and Services"),
+    m("6050", "Quasi Cash - Customer Financial Institution"),
+    m("6051", "Quasi Cash - Merchant"),
+    m("6211", "Securities - Brokers and Dealers"),
+    m("6300", "Insurance Sales, Underwriting and Premiums"),
+    m("6513", "Real Estate Agents and Managers - Rentals"),
+    m("6536", "MoneySend Intracountry"),
+    m("6537", "MoneySend Intercountry"),
+    m("6538", "MoneySend Funding"),
+    m("6540", "Stored Value Card Purchase/Load"),
+    m("7011", "Lodging - Hotels, Motels and Resorts"),
+    m("7032", "Sporting and Recreational Camps"),
+    m("7210", "Laundry, Cleaning and Garment Services"),
+    m("7230", "Beauty and Barber Shops"),
+    m("7261", "Funeral Services and Crematories"),
+    m("7273", "Dating Services"),
+    m("7298", "Health and Beauty Spas"),
+    m("7299", "Miscellaneous Personal Services"),
+    m("7311", "Advertising Services"),
+    m("7372", "Computer Programming and Data Processing"),
+    m(
+        "7392",
+        "Management, Consulting and Public Relations Services",
+    ),
+    m("7399", "Business Services"),
+    m("7512", "Automobile Rental Agency"),
+    m("7523", "Parking Lots and Garages"),
+    m("7538", "Automotive Service Shops"),
+    m("7542", "Car Washes"),
+    m("7622", "Electronics Repair Shops"),
+    m("7832", "Motion Picture Theaters"),
+    m("7841", "Video Tape Rental Stores"),
+    m("7911", "Dance Halls, Studios and Schools"),
+    m("7922", "Theatrical Producers and Ticket Agencies"),
+    m("7941", "Commercial Sports and Sports Clubs"),
+    m("7991", "Tourist Attractions and Exhibits"),
+    m("7992", "Public Golf Courses"),
+    m("7994", "Video Game Arcades"),
+    m("7995", "Gambling - Betting, Lottery and Casino Chips"),
+    m("7996", "Amusement Parks, Carnivals and Circuses"),
+    m("7997", "Membership Clubs and Country Clubs"),
+    m("7999", "Recreation Services"),
+    m("8011", "Doctors and Physicians"),
+    m("8021", "Dentists and Orthodontists"),
+    m("8043", "Opticians and Optical Goods"),
+    m("8062", "Hospitals"),
+    m("8099", "Medical Services and Health Practitioners"),
+    m("8111", "Legal Services and Attorneys"),
+    m("8211", "Elementary and Secondary Schools"),
+    m("8220", "Colleges and Universities"),
+    m("8299", "Schools and Educational Services"),
+    m("8398", "Charitable and Social Service Organizations"),
+    m("8641", "Civic, Social and Fraternal Associations"),
+    m("8651", "Political Organizations"),
+    m("8661", "Religious Organizations"),
+    m("8999", "Professional Services"),
+    m("9211", "Court Costs, Including Alimony and Child Support"),
+    m("9222", "Fines"),
+    m("9311", "Tax Payments"),
+    m("9399", "Government Services"),
+    m("9402", "Postal Services - Government Only"),
+];
+
+pub fn lookup(code: &str) -> Option<&'static Mcc> {
+    MCCS.iter().find(|mcc| mcc.code == code)
+}
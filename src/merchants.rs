@@ -0,0 +1,247 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::error::ApiJson;
+use crate::mcc;
+use crate::rules::FieldSource;
+use crate::AppState;
+
+// ============================================================================
+// Merchant Registry (`/admin/merchants`)
+// ============================================================================
+//
+// MCCs (DE18) registered per acquirer (DE32), part of the rules; rules
+// match on `merchant.registered`, absent for acquirers with no entry.
+
+pub const REGISTERED_FIELD: &str = "merchant.registered";
+
+/// Registered MCCs by acquirer ID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MerchantRegistry(BTreeMap<String, BTreeSet<String>>);
+
+impl MerchantRegistry {
+    /// Checks that every acquirer ID is digits and every MCC is known.
+    pub fn check(&self) -> Result<(), String> {
+        for (de32, mccs) in &self.0 {
+            check_acquirer(de32)?;
+            for mcc in mccs {
+                check_mcc(mcc).map_err(|e| format!("merchants {}: {}", de32, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether acquirer `de32` registered `mcc`; `None` if the registry has
+    /// no entry for the acquirer.
+    pub fn registered(&self, de32: &str, mcc: &str) -> Option<bool> {
+        self.0.get(de32).map(|mccs| mccs.contains(mcc))
+    }
+
+    /// Registers `mcc` for `de32`; false if it already was.
+    pub fn add(&mut self, de32: &str, mcc: &str) -> bool {
+        self.0
+            .entry(de32.to_string())
+            .or_default()
+            .insert(mcc.to_string())
+    }
+
+    /// Unregisters `mcc` for `de32`, keeping the acquirer's entry even when
+    /// it is left empty; false if it wasn't registered.
+    pub fn remove(&mut self, de32: &str, mcc: &str) -> bool {
+        self.0.get_mut(de32).is_some_and(|mccs| mccs.remove(mcc))
+    }
+
+    /// Drops acquirer `de32`'s entry, so its merchants are no longer checked.
+    pub fn remove_acquirer(&mut self, de32: &str) -> bool {
+        self.0.remove(de32).is_some()
+    }
+
+    fn entries(&self) -> Vec<AcquirerMerchants> {
+        self.0
+            .iter()
+            .map(|(de32, mccs)| AcquirerMerchants {
+                acquirer: de32.clone(),
+                mccs: mccs.iter().cloned().collect(),
+            })
+            .collect()
+    }
+}
+
+fn check_acquirer(de32: &str) -> Result<(), String> {
+    if de32.is_empty() || de32.len() > 11 || !de32.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("acquirer ID {:?} is not 1 to 11 digits", de32));
+    }
+    Ok(())
+}
+
+fn check_mcc(mcc: &str) -> Result<(), String> {
+    match mcc::lookup(mcc) {
+        Some(_) => Ok(()),
+        None => Err(format!("unknown merchant category code {:?}", mcc)),
+    }
+}
+
+/// A message as rules see it, plus `merchant.registered`.
+pub struct WithMerchant<'a, M> {
+    pub message: &'a M,
+    pub registry: &'a MerchantRegistry,
+}
+
+impl<M: FieldSource> FieldSource for WithMerchant<'_, M> {
+    fn field(&self, name: &str) -> Option<&str> {
+        if name != REGISTERED_FIELD {
+            return self.message.field(name);
+        }
+        let de32 = self.message.field("de32")?;
+        let mcc = self.message.field("de18")?;
+        match self.registry.registered(de32, mcc)? {
+            true => Some("true"),
+            false => Some("false"),
+        }
+    }
+}
+
+// ============================================================================
+// Admin Endpoints
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AcquirerMerchants {
+    /// DE32.
+    pub acquirer: String,
+    /// Registered DE18 values.
+    pub mccs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterMerchant {
+    #[schema(example = "123456")]
+    pub acquirer: String,
+    #[schema(example = "5411")]
+    pub mcc: String,
+}
+
+type AdminError = (StatusCode, String);
+
+/// Applies `change` to the active rules' registry.
+fn update(
+    state: &AppState,
+    change: impl FnOnce(&mut MerchantRegistry) -> Result<(), AdminError>,
+) -> Result<Vec<AcquirerMerchants>, AdminError> {
+    state
+        .update_rules(|rules| {
+            change(&mut rules.merchants)?;
+            Ok(rules.merchants.entries())
+        })
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/merchants",
+    tag = "admin",
+    responses((status = 200, body = [AcquirerMerchants])),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_merchants(State(state): State<Arc<AppState>>) -> Json<Vec<AcquirerMerchants>> {
+    Json(state.rules().merchants.entries())
+}
+
+/// Registers an MCC for an acquirer, adding the acquirer if it has no
+/// entry yet.
+#[utoipa::path(
+    post,
+    path = "/admin/merchants",
+    tag = "admin",
+    request_body = RegisterMerchant,
+    responses(
+        (status = 200, body = [AcquirerMerchants]),
+        (status = 422, description = "Invalid acquirer ID or unknown MCC", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn register_merchant(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<RegisterMerchant>,
+) -> Result<Json<Vec<AcquirerMerchants>>, AdminError> {
+    let invalid = |e| (StatusCode::UNPROCESSABLE_ENTITY, e);
+    check_acquirer(&body.acquirer).map_err(invalid)?;
+    check_mcc(&body.mcc).map_err(invalid)?;
+    let entries = update(&state, |registry| {
+        registry.add(&body.acquirer, &body.mcc);
+        Ok(())
+    })?;
+    info!(
+        "Admin: MCC {} registered for acquirer {}",
+        body.mcc, body.acquirer
+    );
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/merchants/{acquirer}/{mcc}",
+    tag = "admin",
+    params(("acquirer" = String, Path), ("mcc" = String, Path)),
+    responses(
+        (status = 200, body = [AcquirerMerchants]),
+        (status = 404, description = "MCC not registered for the acquirer", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn unregister_merchant(
+    State(state): State<Arc<AppState>>,
+    Path((acquirer, mcc)): Path<(String, String)>,
+) -> Result<Json<Vec<AcquirerMerchants>>, AdminError> {
+    let entries = update(&state, |registry| match registry.remove(&acquirer, &mcc) {
+        true => Ok(()),
+        false => Err((
+            StatusCode::NOT_FOUND,
+            format!("MCC {} not registered for acquirer {}", mcc, acquirer),
+        )),
+    })?;
+    info!("Admin: MCC {} unregistered for acquirer {}", mcc, acquirer);
+    Ok(Json(entries))
+}
+
+/// Removes an acquirer's entry; its merchants are no longer checked.
+#[utoipa::path(
+    delete,
+    path = "/admin/merchants/{acquirer}",
+    tag = "admin",
+    params(("acquirer" = String, Path)),
+    responses(
+        (status = 200, body = [AcquirerMerchants]),
+        (status = 404, description = "Acquirer not in the registry", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn remove_acquirer(
+    State(state): State<Arc<AppState>>,
+    Path(acquirer): Path<String>,
+) -> Result<Json<Vec<AcquirerMerchants>>, AdminError> {
+    let entries = update(&state, |registry| {
+        match registry.remove_acquirer(&acquirer) {
+            true => Ok(()),
+            false => Err((
+                StatusCode::NOT_FOUND,
+                format!("acquirer {} not in the registry", acquirer),
+            )),
+        }
+    })?;
+    info!(
+        "Admin: acquirer {} removed from the merchant registry",
+        acquirer
+    );
+    Ok(Json(entries))
+}
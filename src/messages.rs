@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::codes::{ProcessingCode, ResponseCode};
+use crate::iso8583::{DataElements, Iso8583Message};
+use crate::processing;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Data Structures for Mastercard ISO 8583
+// ============================================================================
+//
+// Requests are typed views over an `Iso8583Message`: the data elements a
+// handler works with become fields, and anything else the acquirer sent is
+// kept in `additional` so it is stored, logged and visible to rules instead
+// of being dropped. Request fields hold the values as received, so a
+// malformed DE3 or DE39 can still be echoed with a format error; responses
+// carry a typed `ResponseCode`. A DE the message left out reads as empty;
+// the `mandatory` pipeline stage has answered the message already if its
+// MTI requires that DE.
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(from = "Iso8583Message")]
+pub struct AuthorizationRequest {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    pub de7: String,
+    pub de11: String,
+    pub de18: String,
+    pub de32: String,
+    pub de48: String,
+    pub de49: String,
+    pub de61: String,
+    /// DCC original amount; DE4 is then in the billing currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de6: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de22: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de35: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de37: Option<String>,
+    /// Card acceptor terminal ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de41: Option<String>,
+    /// Card acceptor ID code (merchant).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de42: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de43: Option<String>,
+    /// DCC original currency; DE49 is then the billing currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de51: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub de52: Option<String>,
+    #[serde(flatten)]
+    pub additional: DataElements,
+}
+
+impl From<Iso8583Message> for AuthorizationRequest {
+    fn from(mut message: Iso8583Message) -> Self {
+        AuthorizationRequest {
+            de2: message.take(2).unwrap_or_default(),
+            de3: message.take(3).unwrap_or_default(),
+            de4: message.take(4).unwrap_or_default(),
+            de7: message.take(7).unwrap_or_default(),
+            de11: message.take(11).unwrap_or_default(),
+            de18: message.take(18).unwrap_or_default(),
+            de32: message.take(32).unwrap_or_default(),
+            de48: message.take(48).unwrap_or_default(),
+            de49: message.take(49).unwrap_or_default(),
+            de61: message.take(61).unwrap_or_default(),
+            de6: message.take(6),
+            de22: message.take(22),
+            de35: message.take(35),
+            de37: message.take(37),
+            de41: message.take(41),
+            de42: message.take(42),
+            de43: message.take(43),
+            de51: message.take(51),
+            de52: message.take(52),
+            mti: message.mti,
+            additional: message.fields,
+        }
+    }
+}
+
+impl From<AuthorizationRequest> for Iso8583Message {
+    fn from(request: AuthorizationRequest) -> Self {
+        let mut message = Iso8583Message {
+            mti: request.mti,
+            fields: request.additional,
+        };
+        message.insert_all([
+            (2, Some(request.de2)),
+            (3, Some(request.de3)),
+            (4, Some(request.de4)),
+            (6, request.de6),
+            (7, Some(request.de7)),
+            (11, Some(request.de11)),
+            (18, Some(request.de18)),
+            (22, request.de22),
+            (32, Some(request.de32)),
+            (35, request.de35),
+            (37, request.de37),
+            (41, request.de41),
+            (42, request.de42),
+            (43, request.de43),
+            (48, Some(request.de48)),
+            (49, Some(request.de49)),
+            (51, request.de51),
+            (52, request.de52),
+            (61, Some(request.de61)),
+        ]);
+        message
+    }
+}
+
+impl AuthorizationRequest {
+    pub fn processing_code(&self) -> Result<ProcessingCode, String> {
+        self.de3.parse()
+    }
+}
+
+impl FieldSource for AuthorizationRequest {
+    fn field(&self, name: &str) -> Option<&str> {
+        let value = match name {
+            "mti" => &self.mti,
+            "de2" => &self.de2,
+            "de3" => &self.de3,
+            "de4" => &self.de4,
+            "de7" => &self.de7,
+            "de11" => &self.de11,
+            "de18" => &self.de18,
+            "de32" => &self.de32,
+            "de48" => &self.de48,
+            "de49" => &self.de49,
+            "de61" => &self.de61,
+            "de6" => return self.de6.as_deref(),
+            "de22" => return self.de22.as_deref(),
+            "de35" => return self.de35.as_deref(),
+            "de37" => return self.de37.as_deref(),
+            "de41" => return self.de41.as_deref(),
+            "de42" => return self.de42.as_deref(),
+            "de43" => return self.de43.as_deref(),
+            "de51" => return self.de51.as_deref(),
+            _ => return self.additional.field(name),
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthorizationResponse {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    /// DCC original amount, scaled with DE4 on a partial approval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de6: Option<String>,
+    pub de7: String,
+    pub de11: String,
+    /// Settlement date (MMDD) of the open business day, with
+    /// SETTLEMENT_CUTOVER set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de15: Option<String>,
+    pub de18: String,
+    pub de32: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de37: Option<String>,
+    /// Approval code of an approval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de38: Option<String>,
+    pub de39: ResponseCode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de41: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de42: Option<String>,
+    /// Additional response data: the offending DE number on a format error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de44: Option<String>,
+    pub de48: String,
+    pub de49: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de51: Option<String>,
+    pub de61: String,
+    /// Trace ID of an approval, for MITs to refer to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de63: Option<String>,
+    /// Installment terms on an approved installment purchase.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de112: Option<String>,
+    pub response_message: String,
+}
+
+impl AuthorizationResponse {
+    /// Builds a 0110 (0210 to a financial request) that echoes the request
+    /// with the given DE39.
+    pub fn echo(
+        request: &AuthorizationRequest,
+        de39: ResponseCode,
+        response_message: &str,
+    ) -> Self {
+        AuthorizationResponse {
+            mti: processing::response_mti(&request.mti).to_string(),
+            de2: request.de2.clone(),
+            de3: request.de3.clone(),
+            de4: request.de4.clone(),
+            de6: request.de6.clone(),
+            de7: request.de7.clone(),
+            de11: request.de11.clone(),
+            de15: None,
+            de18: request.de18.clone(),
+            de32: request.de32.clone(),
+            de37: request.de37.clone(),
+            de38: None,
+            de39,
+            de41: request.de41.clone(),
+            de42: request.de42.clone(),
+            de44: None,
+            de48: request.de48.clone(),
+            de49: request.de49.clone(),
+            de51: request.de51.clone(),
+            de61: request.de61.clone(),
+            de63: None,
+            de112: None,
+            response_message: response_message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(from = "Iso8583Message")]
+pub struct ReversalRequest {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    pub de7: String,
+    pub de11: String,
+    pub de18: String,
+    pub de22: String,
+    pub de32: String,
+    pub de39: String,
+    pub de48: String,
+    pub de49: String,
+    pub de61: String,
+    pub de90: String,
+    #[serde(flatten)]
+    pub additional: DataElements,
+}
+
+impl From<Iso8583Message> for ReversalRequest {
+    fn from(mut message: Iso8583Message) -> Self {
+        ReversalRequest {
+            de2: message.take(2).unwrap_or_default(),
+            de3: message.take(3).unwrap_or_default(),
+            de4: message.take(4).unwrap_or_default(),
+            de7: message.take(7).unwrap_or_default(),
+            de11: message.take(11).unwrap_or_default(),
+            de18: message.take(18).unwrap_or_default(),
+            de22: message.take(22).unwrap_or_default(),
+            de32: message.take(32).unwrap_or_default(),
+            de39: message.take(39).unwrap_or_default(),
+            de48: message.take(48).unwrap_or_default(),
+            de49: message.take(49).unwrap_or_default(),
+            de61: message.take(61).unwrap_or_default(),
+            de90: message.take(90).unwrap_or_default(),
+            mti: message.mti,
+            additional: message.fields,
+        }
+    }
+}
+
+impl From<ReversalRequest> for Iso8583Message {
+    fn from(request: ReversalRequest) -> Self {
+        let mut message = Iso8583Message {
+            mti: request.mti,
+            fields: request.additional,
+        };
+        message.insert_all([
+            (2, Some(request.de2)),
+            (3, Some(request.de3)),
+            (4, Some(request.de4)),
+            (7, Some(request.de7)),
+            (11, Some(request.de11)),
+            (18, Some(request.de18)),
+            (22, Some(request.de22)),
+            (32, Some(request.de32)),
+            (39, Some(request.de39)),
+            (48, Some(request.de48)),
+            (49, Some(request.de49)),
+            (61, Some(request.de61)),
+            (90, Some(request.de90)),
+        ]);
+        message
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReversalResponse {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    pub de7: String,
+    pub de11: String,
+    pub de18: String,
+    pub de32: String,
+    pub de39: ResponseCode,
+    /// Additional response data: the offending DE number on a format error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de44: Option<String>,
+    pub de48: String,
+    pub de49: String,
+    pub de61: String,
+    pub de90: String,
+    pub response_message: String,
+}
+
+impl ReversalResponse {
+    /// Builds a 0410 that echoes the request with the given DE39.
+    pub fn echo(request: &ReversalRequest, de39: ResponseCode, response_message: &str) -> Self {
+        ReversalResponse {
+            mti: "0410".to_string(),
+            de2: request.de2.clone(),
+            de3: request.de3.clone(),
+            de4: request.de4.clone(),
+            de7: request.de7.clone(),
+            de11: request.de11.clone(),
+            de18: request.de18.clone(),
+            de32: request.de32.clone(),
+            de39,
+            de44: None,
+            de48: request.de48.clone(),
+            de49: request.de49.clone(),
+            de61: request.de61.clone(),
+            de90: request.de90.clone(),
+            response_message: response_message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(from = "Iso8583Message")]
+pub struct CompletionRequest {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    pub de7: String,
+    pub de11: String,
+    pub de32: String,
+    pub de49: String,
+    /// Optional; empty when absent.
+    pub de90: String,
+    #[serde(flatten)]
+    pub additional: DataElements,
+}
+
+impl From<Iso8583Message> for CompletionRequest {
+    fn from(mut message: Iso8583Message) -> Self {
+        CompletionRequest {
+            de2: message.take(2).unwrap_or_default(),
+            de3: message.take(3).unwrap_or_default(),
+            de4: message.take(4).unwrap_or_default(),
+            de7: message.take(7).unwrap_or_default(),
+            de11: message.take(11).unwrap_or_default(),
+            de32: message.take(32).unwrap_or_default(),
+            de49: message.take(49).unwrap_or_default(),
+            de90: message.take(90).unwrap_or_default(),
+            mti: message.mti,
+            additional: message.fields,
+        }
+    }
+}
+
+impl From<CompletionRequest> for Iso8583Message {
+    fn from(request: CompletionRequest) -> Self {
+        let mut message = Iso8583Message {
+            mti: request.mti,
+            fields: request.additional,
+        };
+        message.insert_all([
+            (2, Some(request.de2)),
+            (3, Some(request.de3)),
+            (4, Some(request.de4)),
+            (7, Some(request.de7)),
+            (11, Some(request.de11)),
+            (32, Some(request.de32)),
+            (49, Some(request.de49)),
+            (90, Some(request.de90).filter(|de90| !de90.is_empty())),
+        ]);
+        message
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompletionResponse {
+    pub mti: String,
+    pub de2: String,
+    pub de3: String,
+    pub de4: String,
+    pub de7: String,
+    pub de11: String,
+    pub de32: String,
+    pub de39: ResponseCode,
+    /// Additional response data: the offending DE number on a format error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub de44: Option<String>,
+    pub de49: String,
+    pub de90: String,
+    pub response_message: String,
+}
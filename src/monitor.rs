@@ -0,0 +1,154 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::AppState;
+
+// ============================================================================
+// Live Stats (`/monitor`)
+// ============================================================================
+//
+// A server-sent events stream of aggregate stats for test dashboards that
+// have no metrics stack: one `stats` event per second, computed over the
+// last `window` seconds (default 10, at most 300):
+//
+//   tps            messages per second through the pipeline's `metrics`
+//                  stage (/authorize, /reversal, /completion, /iso8583)
+//   approval_rate  share of authorizations answered with an approval code
+//   p99_latency_ms 99th percentile of the time to respond, injected latency
+//                  included
+//
+// Samples older than MAX_WINDOW are discarded as new ones arrive.
+
+const DEFAULT_WINDOW: u64 = 10;
+const MAX_WINDOW: u64 = 300;
+const INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct Monitor {
+    /// When each message was answered, and how long it took.
+    messages: Mutex<VecDeque<(Instant, Duration)>>,
+    /// When each authorization was answered, and whether it was approved.
+    authorizations: Mutex<VecDeque<(Instant, bool)>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MonitorStats {
+    pub at: DateTime<Utc>,
+    pub window_seconds: u64,
+    pub messages: usize,
+    pub tps: f64,
+    pub authorizations: usize,
+    /// Approved authorizations over all authorizations, 0 to 1; absent
+    /// without authorizations in the window.
+    pub approval_rate: Option<f64>,
+    /// Absent without messages in the window.
+    pub p99_latency_ms: Option<f64>,
+}
+
+fn prune<T>(samples: &mut VecDeque<(Instant, T)>, now: Instant) {
+    let horizon = Duration::from_secs(MAX_WINDOW);
+    while samples
+        .front()
+        .is_some_and(|(at, _)| now.duration_since(*at) > horizon)
+    {
+        samples.pop_front();
+    }
+}
+
+impl Monitor {
+    pub fn record_message(&self, latency: Duration) {
+        let now = Instant::now();
+        let mut messages = self.messages.lock().unwrap();
+        prune(&mut messages, now);
+        messages.push_back((now, latency));
+    }
+
+    pub fn record_authorization(&self, approved: bool) {
+        let now = Instant::now();
+        let mut authorizations = self.authorizations.lock().unwrap();
+        prune(&mut authorizations, now);
+        authorizations.push_back((now, approved));
+    }
+
+    /// Stats over the last `window` seconds.
+    pub fn stats(&self, window: u64) -> MonitorStats {
+        let now = Instant::now();
+        let since = |at: &Instant| now.duration_since(*at) <= Duration::from_secs(window);
+
+        let mut latencies: Vec<Duration> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(at, _)| since(at))
+            .map(|(_, latency)| *latency)
+            .collect();
+        latencies.sort();
+        // Nearest-rank percentile.
+        let rank = (latencies.len() as f64 * 0.99).ceil() as usize;
+        let p99_latency_ms = rank
+            .checked_sub(1)
+            .map(|index| latencies[index].as_secs_f64() * 1000.0);
+
+        let (authorizations, approved) = self
+            .authorizations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(at, _)| since(at))
+            .fold((0, 0), |(total, approved), (_, ok)| {
+                (total + 1, approved + usize::from(*ok))
+            });
+
+        MonitorStats {
+            at: Utc::now(),
+            window_seconds: window,
+            messages: latencies.len(),
+            tps: latencies.len() as f64 / window as f64,
+            authorizations,
+            approval_rate: (authorizations > 0).then(|| approved as f64 / authorizations as f64),
+            p99_latency_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct MonitorQuery {
+    /// Seconds the stats cover (default 10, at most 300).
+    pub window: Option<u64>,
+}
+
+/// Streams aggregate stats as server-sent events, one per second.
+#[utoipa::path(
+    get,
+    path = "/monitor",
+    tag = "health",
+    params(MonitorQuery),
+    responses((status = 200, description = "`text/event-stream` of `stats` events", body = MonitorStats))
+)]
+pub async fn stats_stream(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MonitorQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let window = query.window.unwrap_or(DEFAULT_WINDOW).clamp(1, MAX_WINDOW);
+    let ticks = tokio::time::interval(INTERVAL);
+    let events = stream::unfold((state, ticks), move |(state, mut ticks)| async move {
+        ticks.tick().await;
+        let event = Event::default()
+            .event("stats")
+            .json_data(state.monitor.stats(window))
+            .unwrap_or_else(|_| Event::default().comment("stats unavailable"));
+        Some((Ok(event), (state, ticks)))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,123 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    admin, audit, batch, bins, clearing, clock, cutover, dashboard, deferred, dispatch, export,
+    feed, handlers, health, incidents, matching, merchants, monitor, originals, profiles,
+    reference, settlement, snapshot, tokens, transactions, webhooks,
+};
+
+// ============================================================================
+// OpenAPI Document
+// ============================================================================
+//
+// Derived from the handler annotations; served at /openapi.json with
+// Swagger UI at /docs. Admin routes list both key schemes: either header
+// is accepted when ADMIN_API_KEYS is set, and neither is needed otherwise.
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Mastercard ISO 8583 Mock",
+        description = "Mock issuer for Mastercard ISO 8583 authorization, reversal and completion messages in JSON."
+    ),
+    paths(
+        handlers::authorize,
+        batch::authorize_batch,
+        deferred::get_deferred,
+        handlers::reversal,
+        handlers::completion,
+        dispatch::dispatch,
+        transactions::list_transactions,
+        transactions::get_transaction,
+        feed::transaction_socket,
+        matching::submit_clearing,
+        matching::submit_ipm,
+        matching::matching_report,
+        reference::list_currencies,
+        reference::list_mccs,
+        bins::list_bins,
+        bins::lookup_bin,
+        health::health,
+        health::ready,
+        monitor::stats_stream,
+        dashboard::dashboard,
+        admin::list_cards,
+        admin::get_card_status,
+        admin::set_card_status,
+        admin::reset_pin_tries,
+        admin::generate_cards,
+        tokens::list_tokens,
+        tokens::provision_token,
+        tokens::delete_token,
+        admin::list_accounts,
+        admin::get_account,
+        admin::update_account,
+        admin::adjust_balance,
+        admin::close_account,
+        admin::store_stats,
+        admin::active_rules,
+        admin::get_faults,
+        admin::set_faults,
+        incidents::get_incident,
+        incidents::start_incident,
+        incidents::clear_incident,
+        merchants::list_merchants,
+        merchants::register_merchant,
+        merchants::unregister_merchant,
+        merchants::remove_acquirer,
+        profiles::get_profile,
+        profiles::switch_profile,
+        audit::tail_audit_log,
+        export::export_transactions,
+        clearing::ipm_file,
+        settlement::settlement_report,
+        cutover::force_cutover,
+        clock::get_clock,
+        clock::set_clock,
+        clock::reset_clock,
+        originals::lookup_original,
+        admin::seed,
+        snapshot::save_snapshot,
+        snapshot::restore_snapshot,
+        webhooks::list_webhooks,
+        webhooks::register_webhook,
+        webhooks::delete_webhook,
+        admin::reset_all,
+        admin::reset_transactions,
+        admin::reset_counters,
+        admin::reload_card_profiles,
+    ),
+    tags(
+        (name = "messages", description = "ISO 8583 messages as JSON"),
+        (name = "transactions", description = "Stored transactions"),
+        (name = "clearing", description = "Clearing records matched to authorizations"),
+        (name = "reference", description = "Bundled reference tables"),
+        (name = "health", description = "Liveness and readiness probes, live stats"),
+        (name = "admin", description = "Simulator state; needs an API key when ADMIN_API_KEYS is set"),
+    ),
+    modifiers(&SecuritySchemes)
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
+
+/// `/openapi.json` and the Swagger UI at `/docs`.
+pub fn routes() -> SwaggerUi {
+    SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi())
+}
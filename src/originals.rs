@@ -0,0 +1,181 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::masking;
+use crate::store::{transaction_key, TransactionStore};
+use crate::{AppState, Transaction};
+
+// ============================================================================
+// Locating Original Transactions
+// ============================================================================
+//
+// A 0400 names the authorization it reverses in several ways, and not every
+// acquirer keeps all of them. The original is looked up by each key in turn,
+// always within the reversal's acquirer (DE32):
+//
+//   stan           DE11 of the reversal, the store key
+//   de90           the original STAN in DE90 (positions 5 to 10)
+//   rrn            DE37, the retrieval reference number of the 0110
+//   approval_code  DE38 of the 0110, on the same card (DE2)
+//
+// The order is REVERSAL_MATCH (default all four, as above); an acquirer
+// profile's `reversal_match` replaces it for that acquirer, e.g. `[rrn]`
+// for one that only retains the RRN. `GET /admin/transactions/lookup`
+// runs the same search.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OriginalKey {
+    Stan,
+    De90,
+    Rrn,
+    ApprovalCode,
+}
+
+pub const DEFAULT_PRECEDENCE: [OriginalKey; 4] = [
+    OriginalKey::Stan,
+    OriginalKey::De90,
+    OriginalKey::Rrn,
+    OriginalKey::ApprovalCode,
+];
+
+/// Parses a comma-separated REVERSAL_MATCH, e.g. `rrn,stan`.
+pub fn parse_precedence(value: &str) -> Result<Vec<OriginalKey>, String> {
+    let keys = value
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| match key {
+            "stan" => Ok(OriginalKey::Stan),
+            "de90" => Ok(OriginalKey::De90),
+            "rrn" => Ok(OriginalKey::Rrn),
+            "approval_code" => Ok(OriginalKey::ApprovalCode),
+            other => Err(format!(
+                "unknown match key {:?} (stan, de90, rrn or approval_code)",
+                other
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err("no match keys given".to_string());
+    }
+    Ok(keys)
+}
+
+/// A random six-digit DE38 for an approval.
+pub fn generate_approval_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+/// What a message knows of its original; empty values are ignored.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct OriginalReference {
+    /// Acquirer the original was authorized for.
+    pub de32: String,
+    pub de11: Option<String>,
+    pub de90: Option<String>,
+    pub de37: Option<String>,
+    pub de38: Option<String>,
+    /// Needed to match by approval code.
+    pub de2: Option<String>,
+}
+
+impl OriginalReference {
+    fn value(&self, key: OriginalKey) -> Option<&str> {
+        let value = match key {
+            OriginalKey::Stan => self.de11.as_deref(),
+            OriginalKey::De90 => self
+                .de90
+                .as_deref()
+                .and_then(|de90| de90.get(4..10))
+                .filter(|stan| stan.bytes().all(|b| b.is_ascii_digit())),
+            OriginalKey::Rrn => self.de37.as_deref(),
+            OriginalKey::ApprovalCode => self.de38.as_deref(),
+        };
+        value.filter(|value| !value.is_empty())
+    }
+
+    /// The first original found, in `precedence` order, and the key that
+    /// found it.
+    pub fn find(
+        &self,
+        store: &dyn TransactionStore,
+        precedence: &[OriginalKey],
+    ) -> Result<Option<(OriginalKey, Transaction)>, String> {
+        for &key in precedence {
+            let Some(value) = self.value(key) else {
+                continue;
+            };
+            let found = match key {
+                OriginalKey::Stan | OriginalKey::De90 => {
+                    store.get(&transaction_key(&self.de32, value))?
+                }
+                OriginalKey::Rrn => store
+                    .find_by_rrn(value)?
+                    .filter(|transaction| transaction.acquirer_id == self.de32),
+                OriginalKey::ApprovalCode => {
+                    let Some(pan) = self.de2.as_deref() else {
+                        continue;
+                    };
+                    store
+                        .find_by_approval_code(value)?
+                        .into_iter()
+                        .find(|transaction| {
+                            transaction.acquirer_id == self.de32 && transaction.pan == pan
+                        })
+                }
+            };
+            if let Some(transaction) = found {
+                return Ok(Some((key, transaction)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OriginalMatch {
+    pub matched_by: OriginalKey,
+    /// PAN masked.
+    pub transaction: Transaction,
+}
+
+/// Finds the original a reversal with these fields would reverse.
+#[utoipa::path(
+    get,
+    path = "/admin/transactions/lookup",
+    tag = "admin",
+    params(OriginalReference),
+    responses(
+        (status = 200, body = OriginalMatch),
+        (status = 404, description = "No original found", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn lookup_original(
+    State(state): State<Arc<AppState>>,
+    Query(reference): Query<OriginalReference>,
+) -> Result<Json<OriginalMatch>, (StatusCode, String)> {
+    let precedence = state.reversal_match(&reference.de32);
+    match reference.find(state.authorized_transactions.as_ref(), &precedence) {
+        Ok(Some((matched_by, mut transaction))) => {
+            transaction.pan = masking::mask_pan(&transaction.pan);
+            Ok(Json(OriginalMatch {
+                matched_by,
+                transaction,
+            }))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("no original found for acquirer {}", reference.de32),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
@@ -0,0 +1,117 @@
+use rand::Rng;
+
+use crate::card_generator::luhn_check_digit;
+
+// ============================================================================
+// DE2 Primary Account Number
+// ============================================================================
+//
+// Authorizations are only decided for PANs that could exist on the network:
+// 13 to 19 digits, a correct Luhn check digit, and a BIN in the Mastercard
+// (51-55, 2221-2720) or Maestro (50, 56-69) ranges. Test BINs outside those
+// ranges (TEST_BINS) are accepted as well, so a deck of Visa-style test PANs
+// keeps working. Anything else is declined with DE39 `14`.
+
+pub const PAN_LENGTHS: std::ops::RangeInclusive<usize> = 13..=19;
+
+/// Mastercard and Maestro BIN ranges.
+const NETWORK_BINS: &[&str] = &["222100-272099", "51-55", "50", "56-69"];
+
+/// A BIN prefix (`"541333"`) or an inclusive range of equal-length
+/// prefixes (`"510000-559999"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinRange {
+    Prefix(String),
+    Range { start: u64, end: u64, width: usize },
+}
+
+impl BinRange {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        match spec.split_once('-') {
+            None if digits(spec) => Ok(BinRange::Prefix(spec.to_string())),
+            Some((start, end)) if digits(start) && start.len() == end.len() && digits(end) => {
+                let (start_n, end_n) = (start.parse().unwrap(), end.parse().unwrap());
+                if start_n > end_n {
+                    return Err(format!("empty BIN range {}", spec));
+                }
+                Ok(BinRange::Range {
+                    start: start_n,
+                    end: end_n,
+                    width: start.len(),
+                })
+            }
+            _ => Err(format!("invalid BIN {:?}", spec)),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            BinRange::Prefix(prefix) => prefix.len(),
+            BinRange::Range { width, .. } => *width,
+        }
+    }
+
+    pub fn contains(&self, pan: &str) -> bool {
+        match self {
+            BinRange::Prefix(prefix) => pan.starts_with(prefix.as_str()),
+            BinRange::Range { start, end, width } => pan
+                .get(..*width)
+                .and_then(|bin| bin.parse::<u64>().ok())
+                .is_some_and(|bin| (*start..=*end).contains(&bin)),
+        }
+    }
+
+    /// A random prefix from the range.
+    pub(crate) fn pick(&self, rng: &mut impl Rng) -> String {
+        match self {
+            BinRange::Prefix(prefix) => prefix.clone(),
+            BinRange::Range { start, end, width } => {
+                format!("{:0width$}", rng.gen_range(*start..=*end), width = width)
+            }
+        }
+    }
+}
+
+/// Comma-separated BIN prefixes and ranges, as in TEST_BINS.
+pub fn parse_bins(specs: &str) -> Result<Vec<BinRange>, String> {
+    specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .map(BinRange::parse)
+        .collect()
+}
+
+pub fn luhn_valid(pan: &str) -> bool {
+    match pan.len().checked_sub(1).map(|end| pan.split_at(end)) {
+        Some((partial, check)) if pan.bytes().all(|b| b.is_ascii_digit()) => {
+            check == luhn_check_digit(partial).to_string()
+        }
+        _ => false,
+    }
+}
+
+/// Checks length, check digit and BIN, in that order.
+pub fn check(pan: &str, test_bins: &[BinRange]) -> Result<(), String> {
+    if !PAN_LENGTHS.contains(&pan.len()) || !pan.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!(
+            "PAN of {} characters is not 13 to 19 digits",
+            pan.len()
+        ));
+    }
+    if !luhn_valid(pan) {
+        return Err("PAN fails the Luhn check".to_string());
+    }
+    let network = NETWORK_BINS
+        .iter()
+        .map(|spec| BinRange::parse(spec).expect("network BINs are valid"))
+        .any(|bin| bin.contains(pan));
+    if !network && !test_bins.iter().any(|bin| bin.contains(pan)) {
+        return Err(format!(
+            "BIN {} is not a Mastercard, Maestro or test BIN",
+            &pan[..6]
+        ));
+    }
+    Ok(())
+}
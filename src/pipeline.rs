@@ -0,0 +1,331 @@
+use axum::{
+    async_trait,
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::codes::ResponseCode;
+use crate::dispatch::{self, MessageContext};
+use crate::error::ApiError;
+use crate::iso8583::{Iso8583Message, KeyStyle};
+use crate::masking;
+use crate::validation::{self, ValidationMode};
+use crate::AppState;
+
+// ============================================================================
+// Message Pipeline
+// ============================================================================
+//
+// Stages that apply to every message run around its handler: `before` in
+// order, `after` in reverse. Embedders add their own to `Config::pipeline`;
+// the README lists the built-in ones.
+
+pub const METRICS: &str = "metrics";
+pub const LOG: &str = "log";
+pub const MANDATORY: &str = "mandatory";
+pub const INCIDENT: &str = "incident";
+
+#[async_trait]
+pub trait Stage: Send + Sync {
+    /// Unique within a pipeline.
+    fn name(&self) -> &str;
+
+    /// Runs before the handler. Returning a response answers the message
+    /// with it instead.
+    async fn before(
+        &self,
+        _state: &AppState,
+        _context: &MessageContext,
+        _message: &mut Iso8583Message,
+    ) -> Result<(), Response> {
+        Ok(())
+    }
+
+    /// Runs after the handler, or the stage that answered, for every stage
+    /// whose `before` ran.
+    async fn after(
+        &self,
+        _state: &AppState,
+        _context: &MessageContext,
+        _message: &Iso8583Message,
+        response: Response,
+    ) -> Response {
+        response
+    }
+}
+
+struct Metrics;
+
+#[async_trait]
+impl Stage for Metrics {
+    fn name(&self) -> &str {
+        METRICS
+    }
+
+    async fn after(
+        &self,
+        state: &AppState,
+        context: &MessageContext,
+        _message: &Iso8583Message,
+        response: Response,
+    ) -> Response {
+        state.monitor.record_message(context.received.elapsed());
+        response
+    }
+}
+
+struct Log;
+
+#[async_trait]
+impl Stage for Log {
+    fn name(&self) -> &str {
+        LOG
+    }
+
+    async fn before(
+        &self,
+        _state: &AppState,
+        _context: &MessageContext,
+        message: &mut Iso8583Message,
+    ) -> Result<(), Response> {
+        debug!(request = %masking::payload_for_log(message), "{} request", message.mti);
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        _state: &AppState,
+        context: &MessageContext,
+        message: &Iso8583Message,
+        response: Response,
+    ) -> Response {
+        debug!(
+            status = response.status().as_u16(),
+            elapsed_ms = context.received.elapsed().as_millis() as u64,
+            "{} answered",
+            message.mti
+        );
+        response
+    }
+}
+
+struct Mandatory;
+
+#[async_trait]
+impl Stage for Mandatory {
+    fn name(&self) -> &str {
+        MANDATORY
+    }
+
+    async fn before(
+        &self,
+        state: &AppState,
+        context: &MessageContext,
+        message: &mut Iso8583Message,
+    ) -> Result<(), Response> {
+        // Anything without a response MTI is the handler's to refuse.
+        let Some(response_mti) = dispatch::response_mti(&message.mti) else {
+            return Ok(());
+        };
+        let rules = state.rules();
+        let Err(violation) =
+            validation::check_mandatory(message, rules.mandatory_fields(&message.mti))
+        else {
+            return Ok(());
+        };
+        if ValidationMode::requested(&context.headers, state.validation_mode)
+            == ValidationMode::Lenient
+        {
+            warn!("Lenient validation, ignoring {}", violation);
+            return Ok(());
+        }
+        info!("Format error in {}: {}", message.mti, violation);
+        let mut answer = dispatch::echo(message.clone(), response_mti, ResponseCode::FormatError);
+        answer
+            .set(44, violation.de44())
+            .expect("DE44 is registered");
+        Err(dispatch::reply(&answer, validation::FORMAT_ERROR_MESSAGE))
+    }
+}
+
+struct Incident;
+
+#[async_trait]
+impl Stage for Incident {
+    fn name(&self) -> &str {
+        INCIDENT
+    }
+
+    async fn before(
+        &self,
+        state: &AppState,
+        _context: &MessageContext,
+        message: &mut Iso8583Message,
+    ) -> Result<(), Response> {
+        let Some(response_mti) = dispatch::response_mti(&message.mti) else {
+            return Ok(());
+        };
+        let Some(code) = state.incidents.strike() else {
+            return Ok(());
+        };
+        info!("Incident answers {} with {}", message.mti, code);
+        let answer = dispatch::echo(message.clone(), response_mti, code);
+        Err(dispatch::reply(&answer, code.description()))
+    }
+}
+
+/// Stages in the order their `before` runs.
+#[derive(Clone)]
+pub struct Pipeline(Vec<Arc<dyn Stage>>);
+
+impl Pipeline {
+    /// `metrics`, `log`, `mandatory`, then `incident`.
+    pub fn builtin() -> Self {
+        Pipeline(vec![
+            Arc::new(Metrics),
+            Arc::new(Log),
+            Arc::new(Mandatory),
+            Arc::new(Incident),
+        ])
+    }
+
+    /// No stages at all.
+    pub fn empty() -> Self {
+        Pipeline(Vec::new())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|stage| stage.name() == name)
+    }
+
+    /// Adds a stage last, next to the handler.
+    pub fn push(&mut self, stage: impl Stage + 'static) {
+        self.0.push(Arc::new(stage));
+    }
+
+    /// Adds a stage just before the stage called `name`.
+    pub fn insert_before(&mut self, name: &str, stage: impl Stage + 'static) -> Result<(), String> {
+        let index = self
+            .position(name)
+            .ok_or_else(|| format!("no stage named {}", name))?;
+        self.0.insert(index, Arc::new(stage));
+        Ok(())
+    }
+
+    /// Removes the stage called `name`; false if there is none.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let index = self.position(name);
+        index.map(|index| self.0.remove(index)).is_some()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Runs `message` through the stages and `handler`.
+    pub async fn run<F, Fut>(
+        &self,
+        state: &AppState,
+        context: &MessageContext,
+        mut message: Iso8583Message,
+        handler: F,
+    ) -> Response
+    where
+        F: FnOnce(Iso8583Message) -> Fut,
+        Fut: Future<Output = Response>,
+    {
+        let mut entered = 0;
+        let mut answer = None;
+        for stage in &self.0 {
+            entered += 1;
+            if let Err(response) = stage.before(state, context, &mut message).await {
+                answer = Some(response);
+                break;
+            }
+        }
+        let mut response = match answer {
+            Some(response) => response,
+            None => handler(message.clone()).await,
+        };
+        for stage in self.0[..entered].iter().rev() {
+            response = stage.after(state, context, &message, response).await;
+        }
+        response
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Runs the pipeline around a message endpoint whose body is the message.
+pub async fn apply(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let received = Instant::now();
+    let (mut parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, state.max_body_bytes).await else {
+        return ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "body_too_large",
+            format!("request body exceeds {} bytes", state.max_body_bytes),
+        )
+        .into_response();
+    };
+    // The handler rejects it with the error envelope.
+    let Some((message, style)) = parse(&bytes) else {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let context = MessageContext {
+        peer,
+        headers: parts.headers.clone(),
+        received,
+    };
+    let pipeline = state.pipeline.clone();
+    let response = pipeline
+        .run(&state, &context, message, |message| async move {
+            let body = serde_json::to_vec(&message).expect("a message serializes");
+            parts.headers.remove(header::CONTENT_LENGTH);
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        })
+        .await;
+    match style {
+        KeyStyle::Prefixed => response,
+        style => dispatch::rewrite(response, |object| style.restyle(object)).await,
+    }
+}
+
+/// The message in a body, and the key style its response is written in.
+fn parse(bytes: &[u8]) -> Option<(Iso8583Message, KeyStyle)> {
+    let Ok(Value::Object(object)) = serde_json::from_slice::<Value>(bytes) else {
+        return None;
+    };
+    let style = KeyStyle::detect(&object);
+    let message = Iso8583Message::deserialize(Value::Object(object)).ok()?;
+    Some((message, style))
+}
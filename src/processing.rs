@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Dual- and Single-Message Processing
+// ============================================================================
+//
+// Credit programs are dual-message: the 0100 authorization only holds the
+// amount, which is posted later by a completion or clearing. Debit
+// Mastercard programs are commonly single-message: a 0200 financial
+// request is approved and debited in one step, and no clearing record
+// follows.
+//
+// `message_mode` in the rules file sets the mode for every card; an issuer
+// profile's `message_mode` overrides it for the issuer's BINs. `/authorize`
+// takes the request MTI of the card's mode and answers with the matching
+// response MTI.
+
+pub const AUTHORIZATION_REQUEST: &str = "0100";
+pub const FINANCIAL_REQUEST: &str = "0200";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageMode {
+    /// Authorize and hold; post on completion.
+    #[default]
+    Dual,
+    /// Authorize and post at once.
+    Single,
+}
+
+impl MessageMode {
+    pub fn request_mti(self) -> &'static str {
+        match self {
+            MessageMode::Dual => AUTHORIZATION_REQUEST,
+            MessageMode::Single => FINANCIAL_REQUEST,
+        }
+    }
+
+    pub fn response_mti(self) -> &'static str {
+        response_mti(self.request_mti())
+    }
+}
+
+/// 0210 for a financial request, 0110 for anything else sent to
+/// `/authorize`.
+pub fn response_mti(request_mti: &str) -> &'static str {
+    if request_mti == FINANCIAL_REQUEST {
+        "0210"
+    } else {
+        "0110"
+    }
+}
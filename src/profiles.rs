@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::cards::CardDeck;
+use crate::rules::RuleSet;
+use crate::AppState;
+
+// ============================================================================
+// Simulation Profiles (`/admin/profile`)
+// ============================================================================
+//
+// Named bundles of a rule set and card deck, switched at runtime: the
+// built-ins from config/profiles, `default` and any in PROFILES_DIR.
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "happy-path",
+        include_str!("../config/profiles/happy-path.yaml"),
+    ),
+    ("degraded", include_str!("../config/profiles/degraded.yaml")),
+    ("chaos", include_str!("../config/profiles/chaos.yaml")),
+    (
+        "certification",
+        include_str!("../config/profiles/certification.yaml"),
+    ),
+];
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: Option<RuleSet>,
+    pub cards: Option<CardDeck>,
+    /// The file it was read from; `None` for built-ins.
+    pub path: Option<String>,
+}
+
+/// A profile file; `rules` and `cards` are a rules file and a card deck.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    description: Option<String>,
+    rules: Option<serde_yaml::Value>,
+    cards: Option<serde_yaml::Value>,
+}
+
+impl Profile {
+    pub fn from_yaml(name: &str, yaml: &str) -> Result<Self, String> {
+        let file: ProfileFile =
+            serde_yaml::from_str(yaml).map_err(|e| format!("invalid profile {}: {}", name, e))?;
+        let section = |value: serde_yaml::Value| {
+            serde_yaml::to_string(&value).map_err(|e| format!("profile {}: {}", name, e))
+        };
+        Ok(Profile {
+            name: name.to_string(),
+            description: file.description,
+            rules: file
+                .rules
+                .map(|rules| RuleSet::from_yaml(&section(rules)?))
+                .transpose()
+                .map_err(|e| format!("profile {}: {}", name, e))?,
+            cards: file
+                .cards
+                .map(|cards| CardDeck::from_yaml(&section(cards)?))
+                .transpose()
+                .map_err(|e| format!("profile {}: {}", name, e))?,
+            path: None,
+        })
+    }
+}
+
+/// The built-in profiles.
+pub fn builtin() -> Vec<Profile> {
+    BUILTIN
+        .iter()
+        .map(|(name, yaml)| Profile::from_yaml(name, yaml).expect("invalid built-in profile"))
+        .collect()
+}
+
+/// The built-ins, then every `<name>.yaml` in `dir`.
+pub fn load(dir: Option<&str>) -> Result<Vec<Profile>, String> {
+    let mut profiles = builtin();
+    let Some(dir) = dir else {
+        return Ok(profiles);
+    };
+    let entries = fs::read_dir(dir).map_err(|e| format!("cannot read {}: {}", dir, e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let name = match (path.file_stem(), path.extension()) {
+            (Some(stem), Some(ext)) if ext == "yaml" || ext == "yml" => {
+                stem.to_string_lossy().into_owned()
+            }
+            _ => continue,
+        };
+        if name == DEFAULT_PROFILE {
+            return Err(format!("{}: `default` is reserved", path.display()));
+        }
+        let yaml = fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let mut profile = Profile::from_yaml(&name, &yaml)?;
+        profile.path = Some(path.display().to_string());
+        profiles.push(profile);
+    }
+    Ok(profiles)
+}
+
+pub struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+    /// The rules the mock started with, for `default` and profiles
+    /// without rules of their own.
+    default_rules: RuleSet,
+    active: RwLock<String>,
+}
+
+impl Profiles {
+    /// Later profiles replace earlier ones of the same name.
+    pub fn new(profiles: Vec<Profile>, default_rules: RuleSet) -> Self {
+        Profiles {
+            profiles: profiles
+                .into_iter()
+                .map(|profile| (profile.name.clone(), profile))
+                .collect(),
+            default_rules,
+            active: RwLock::new(DEFAULT_PROFILE.to_string()),
+        }
+    }
+
+    pub fn active(&self) -> String {
+        self.active.read().unwrap().clone()
+    }
+
+    fn summaries(&self) -> Vec<ProfileSummary> {
+        std::iter::once(ProfileSummary {
+            name: DEFAULT_PROFILE.to_string(),
+            description: Some("RULES_FILE and CARDS_FILE, as started".to_string()),
+            rules: true,
+            cards: true,
+            path: None,
+        })
+        .chain(self.profiles.values().map(|profile| ProfileSummary {
+            name: profile.name.clone(),
+            description: profile.description.clone(),
+            rules: profile.rules.is_some(),
+            cards: profile.cards.is_some(),
+            path: profile.path.clone(),
+        }))
+        .collect()
+    }
+}
+
+/// Installs profile `name`'s rules and card deck.
+pub fn activate(state: &AppState, name: &str) -> Result<(), String> {
+    let profile = match name {
+        DEFAULT_PROFILE => None,
+        _ => Some(
+            state
+                .profiles
+                .profiles
+                .get(name)
+                .ok_or_else(|| format!("no profile named {}", name))?,
+        ),
+    };
+    let rules = profile
+        .and_then(|profile| profile.rules.clone())
+        .unwrap_or_else(|| state.profiles.default_rules.clone());
+    let deck = match profile.and_then(|profile| profile.cards.clone()) {
+        Some(deck) => deck,
+        None => state.default_deck()?,
+    };
+
+    state.replace_rules(rules)?;
+    state.cards.reload(&deck);
+    state.tokens.reload(&deck.tokens);
+    state.ledger.reset(deck);
+    *state.profiles.active.write().unwrap() = name.to_string();
+    info!("Simulation profile {} active", name);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProfileSummary {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether it brings its own rules and card deck; otherwise the
+    /// default's apply.
+    pub rules: bool,
+    pub cards: bool,
+    /// File in PROFILES_DIR; absent for built-ins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProfileStatus {
+    pub active: String,
+    pub profiles: Vec<ProfileSummary>,
+}
+
+fn status(state: &AppState) -> ProfileStatus {
+    ProfileStatus {
+        active: state.profiles.active(),
+        profiles: state.profiles.summaries(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/profile",
+    tag = "admin",
+    responses((status = 200, body = ProfileStatus)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_profile(State(state): State<Arc<AppState>>) -> Json<ProfileStatus> {
+    Json(status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/profile/{name}",
+    tag = "admin",
+    params(("name" = String, Path)),
+    responses(
+        (status = 200, body = ProfileStatus),
+        (status = 404, description = "Unknown profile", body = String),
+        (status = 422, description = "The profile's rules or CARDS_FILE could not be loaded", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn switch_profile(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ProfileStatus>, (StatusCode, String)> {
+    if name != DEFAULT_PROFILE && !state.profiles.profiles.contains_key(&name) {
+        return Err((StatusCode::NOT_FOUND, format!("no profile named {}", name)));
+    }
+    activate(&state, &name).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    Ok(Json(status(&state)))
+}
@@ -0,0 +1,77 @@
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::Distribution;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::codes::ResponseCode;
+
+// ============================================================================
+// Probabilistic Decline Mode
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomDeclineConfig {
+    /// Fraction of approvals to turn into declines, between 0.0 and 1.0.
+    pub rate: f64,
+    /// Fixed seed for reproducible runs; a random seed is used when absent.
+    pub seed: Option<u64>,
+    #[serde(default = "default_codes")]
+    pub codes: Vec<WeightedCode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedCode {
+    pub code: ResponseCode,
+    pub weight: u32,
+}
+
+fn default_codes() -> Vec<WeightedCode> {
+    vec![WeightedCode {
+        code: ResponseCode::DoNotHonor,
+        weight: 1,
+    }]
+}
+
+pub struct RandomDecline {
+    rate: f64,
+    codes: Vec<ResponseCode>,
+    weights: WeightedIndex<u32>,
+    rng: Mutex<StdRng>,
+}
+
+impl RandomDecline {
+    pub fn new(config: &RandomDeclineConfig, seed_override: Option<u64>) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&config.rate) {
+            return Err(format!(
+                "random_decline.rate must be within 0.0..=1.0, got {}",
+                config.rate
+            ));
+        }
+
+        let weights = WeightedIndex::new(config.codes.iter().map(|c| c.weight))
+            .map_err(|e| format!("random_decline.codes: {}", e))?;
+
+        let rng = match seed_override.or(config.seed) {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Ok(RandomDecline {
+            rate: config.rate,
+            codes: config.codes.iter().map(|c| c.code).collect(),
+            weights,
+            rng: Mutex::new(rng),
+        })
+    }
+
+    /// Returns a decline code for this authorization, or `None` to leave it alone.
+    pub fn roll(&self) -> Option<ResponseCode> {
+        let mut rng = self.rng.lock().unwrap();
+        if !rng.gen_bool(self.rate) {
+            return None;
+        }
+        Some(self.codes[self.weights.sample(&mut *rng)])
+    }
+}
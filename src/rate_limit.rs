@@ -0,0 +1,182 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use crate::codes::ResponseCode;
+use crate::rules::RuleSet;
+
+// ============================================================================
+// Rate Limiting (token bucket per source)
+// ============================================================================
+//
+// Each source (client IP or DE32) gets a bucket holding up to `burst`
+// tokens, refilled at `requests_per_second`. A message arriving at an empty
+// bucket is answered with DE39 91 or HTTP 429, so clients' backpressure
+// handling can be exercised. An acquirer profile's `rate_limit` gives that
+// DE32 its own bucket, whatever the shared `key`, and applies even without
+// a shared `rate_limit` section.
+
+pub const DECLINE_MESSAGE: &str = "Rate Limit Exceeded";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitKey {
+    #[default]
+    Ip,
+    De32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitResponse {
+    /// Answer with the usual response message carrying `response_code`.
+    #[default]
+    De39,
+    /// Reject with HTTP 429 and `Retry-After`.
+    Http,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub key: LimitKey,
+    #[serde(flatten)]
+    pub limit: Limit,
+    #[serde(default)]
+    pub respond_with: LimitResponse,
+    #[serde(default = "default_response_code")]
+    pub response_code: ResponseCode,
+    /// Limits for specific sources (IP address or DE32 value).
+    #[serde(default)]
+    pub overrides: HashMap<String, Limit>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Limit {
+    pub requests_per_second: f64,
+    /// Bucket size; defaults to one second's worth of requests.
+    pub burst: Option<f64>,
+}
+
+impl Limit {
+    fn burst(&self) -> f64 {
+        self.burst.unwrap_or(self.requests_per_second).max(1.0)
+    }
+}
+
+fn default_response_code() -> ResponseCode {
+    ResponseCode::IssuerUnavailable
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: DateTime<Utc>,
+}
+
+/// What a handler should do with a message.
+pub enum Verdict {
+    Allow,
+    /// Answer with this DE39.
+    Decline(ResponseCode),
+    /// Send this response as is.
+    Reject(Response),
+}
+
+pub struct RateLimiter {
+    config: Option<RateLimitConfig>,
+    /// Limits from acquirer profiles, keyed by DE32.
+    acquirers: HashMap<String, Limit>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// `None` when neither the rules nor any acquirer profile set a limit.
+    pub fn from_rules(rules: &RuleSet) -> Result<Option<Self>, String> {
+        let acquirers: HashMap<_, _> = rules
+            .acquirers
+            .iter()
+            .filter_map(|(de32, profile)| Some((de32.clone(), profile.rate_limit?)))
+            .collect();
+        if rules.rate_limit.is_none() && acquirers.is_empty() {
+            return Ok(None);
+        }
+        let limits = rules
+            .rate_limit
+            .iter()
+            .flat_map(|config| std::iter::once(&config.limit).chain(config.overrides.values()))
+            .chain(acquirers.values());
+        for limit in limits {
+            if limit.requests_per_second.is_nan() || limit.requests_per_second <= 0.0 {
+                return Err(format!(
+                    "rate_limit.requests_per_second must be positive, got {}",
+                    limit.requests_per_second
+                ));
+            }
+        }
+        Ok(Some(RateLimiter {
+            config: rules.rate_limit.clone(),
+            acquirers,
+            buckets: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Takes a token from the message's bucket.
+    pub fn check(&self, peer: IpAddr, de32: &str, now: DateTime<Utc>) -> Verdict {
+        let (source, limit) = match (self.acquirers.get(de32), &self.config) {
+            (Some(limit), _) => (format!("acquirer:{}", de32), limit),
+            (None, Some(config)) => {
+                let source = match config.key {
+                    LimitKey::Ip => peer.to_string(),
+                    LimitKey::De32 => de32.to_string(),
+                };
+                let limit = config.overrides.get(&source).unwrap_or(&config.limit);
+                (source, limit)
+            }
+            (None, None) => return Verdict::Allow,
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(source).or_insert(Bucket {
+            tokens: limit.burst(),
+            refilled_at: now,
+        });
+        // The virtual clock can be set back; that refills nothing.
+        let elapsed = (now - bucket.refilled_at)
+            .to_std()
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        bucket.tokens = (bucket.tokens + elapsed * limit.requests_per_second).min(limit.burst());
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Verdict::Allow;
+        }
+
+        let respond_with = self.config.as_ref().map(|c| c.respond_with);
+        match respond_with.unwrap_or_default() {
+            LimitResponse::De39 => Verdict::Decline(
+                self.config
+                    .as_ref()
+                    .map_or_else(default_response_code, |c| c.response_code),
+            ),
+            LimitResponse::Http => {
+                let wait = (1.0 - bucket.tokens) / limit.requests_per_second;
+                let retry_after = wait.ceil().max(1.0).to_string();
+                Verdict::Reject(
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(header::RETRY_AFTER, retry_after)],
+                        DECLINE_MESSAGE,
+                    )
+                        .into_response(),
+                )
+            }
+        }
+    }
+}
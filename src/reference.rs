@@ -0,0 +1,31 @@
+use axum::Json;
+
+use crate::currency::{Currency, CURRENCIES};
+use crate::mcc::{Mcc, MCCS};
+
+// ============================================================================
+// Reference Tables
+// ============================================================================
+//
+// The tables the mock validates DE18 and DE49-DE51 against, served as they
+// are so client test data and the mock can't drift apart.
+
+#[utoipa::path(
+    get,
+    path = "/reference/currencies",
+    tag = "reference",
+    responses((status = 200, description = "ISO 4217 currencies with their exponents", body = [Currency]))
+)]
+pub async fn list_currencies() -> Json<&'static [Currency]> {
+    Json(CURRENCIES)
+}
+
+#[utoipa::path(
+    get,
+    path = "/reference/mccs",
+    tag = "reference",
+    responses((status = 200, description = "ISO 18245 merchant category codes", body = [Mcc]))
+)]
+pub async fn list_mccs() -> Json<&'static [Mcc]> {
+    Json(MCCS)
+}
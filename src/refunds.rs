@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::amount;
+use crate::codes::ResponseCode;
+use crate::de48::De48;
+use crate::originals::{OriginalKey, OriginalReference};
+use crate::rules::{Action, FieldSource};
+use crate::store::TransactionStore;
+use crate::{AuthorizationRequest, Transaction, TransactionState};
+
+// ============================================================================
+// Linked Refunds
+// ============================================================================
+//
+// A credit (refund or OCT) can name the purchase it pays back: the
+// original's RRN in DE48 subelement 21, or its STAN in DE90 as a reversal
+// would. The original is looked up within the credit's acquirer (DE32), by
+// RRN first, and the credits linked to it are totalled on it
+// (`refunded_amount`):
+//
+//   original not found            12  Original Not Found
+//   original reversed or expired  12  Original Not Refundable
+//   total above the original's    `refunds.exceeded_code` (default 13)
+//   settled amount                    Refund Exceeds Original Amount
+//
+// Credits that name no original are standalone and not limited. They go
+// through the rules like any authorization, unless the rules file sets
+// `refunds.standalone`: that action then answers every one of them, e.g.
+// to decline credits without an original outright.
+
+pub const ORIGINAL_RRN_SUBELEMENT: &str = "21";
+pub const EXCEEDED_MESSAGE: &str = "Refund Exceeds Original Amount";
+
+const PRECEDENCE: [OriginalKey; 2] = [OriginalKey::Rrn, OriginalKey::De90];
+
+/// Held while a refund is added to its original's total.
+static RECORDING: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundConfig {
+    /// Declines a linked refund above what is left of its original.
+    #[serde(default = "default_exceeded_code")]
+    pub exceeded_code: ResponseCode,
+    /// Answers credits that name no original instead of the rules.
+    pub standalone: Option<Action>,
+}
+
+fn default_exceeded_code() -> ResponseCode {
+    ResponseCode::InvalidAmount
+}
+
+impl Default for RefundConfig {
+    fn default() -> Self {
+        RefundConfig {
+            exceeded_code: default_exceeded_code(),
+            standalone: None,
+        }
+    }
+}
+
+/// The original a credit names; `None` for a standalone credit.
+pub fn original_reference(payload: &AuthorizationRequest) -> Option<OriginalReference> {
+    let de37 = De48::parse(&payload.de48)
+        .ok()
+        .and_then(|de48| de48.get(ORIGINAL_RRN_SUBELEMENT).map(str::to_string));
+    let de90 = payload.additional.field("de90").map(str::to_string);
+    if de37.is_none() && de90.is_none() {
+        return None;
+    }
+    Some(OriginalReference {
+        de32: payload.de32.clone(),
+        de37,
+        de90,
+        ..OriginalReference::default()
+    })
+}
+
+impl Transaction {
+    /// Minor units refunded against this transaction so far.
+    pub fn refunded_total(&self) -> i64 {
+        self.refunded_amount
+            .as_deref()
+            .and_then(amount::parse_minor_units)
+            .unwrap_or(0)
+    }
+}
+
+/// Finds a linked credit's original and checks that `amount` fits in what
+/// its earlier refunds left of it.
+pub fn find_original(
+    store: &dyn TransactionStore,
+    reference: &OriginalReference,
+    amount: i64,
+    config: &RefundConfig,
+) -> Result<Transaction, (ResponseCode, &'static str)> {
+    let original = reference
+        .find(store, &PRECEDENCE)
+        .unwrap_or_else(|e| {
+            warn!("Failed to look up refunded transaction: {}", e);
+            None
+        })
+        .map(|(_, original)| original)
+        .ok_or((ResponseCode::InvalidTransaction, "Original Not Found"))?;
+    check(&original, amount, config)?;
+    Ok(original)
+}
+
+fn check(
+    original: &Transaction,
+    amount: i64,
+    config: &RefundConfig,
+) -> Result<(), (ResponseCode, &'static str)> {
+    if matches!(
+        original.state,
+        TransactionState::Reversed | TransactionState::Expired
+    ) {
+        return Err((ResponseCode::InvalidTransaction, "Original Not Refundable"));
+    }
+    let settled = amount::parse_minor_units(original.settled_amount()).unwrap_or(0);
+    if original.refunded_total().saturating_add(amount) > settled {
+        return Err((config.exceeded_code, EXCEEDED_MESSAGE));
+    }
+    Ok(())
+}
+
+/// Adds an approved refund of `amount` to the total of the original with
+/// this key. Checks it again first, one refund at a time: others may have
+/// been recorded since `find_original`, and then it is declined.
+pub fn record(
+    store: &dyn TransactionStore,
+    key: &str,
+    amount: i64,
+    config: &RefundConfig,
+) -> Result<(), (ResponseCode, &'static str)> {
+    let _recording = RECORDING.lock().unwrap();
+    let mut original = match store.get(key) {
+        Ok(Some(original)) => original,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            warn!("Failed to look up refunded transaction {}: {}", key, e);
+            return Ok(());
+        }
+    };
+    check(&original, amount, config)?;
+    original.refunded_amount = Some(format!(
+        "{:012}",
+        original.refunded_total().saturating_add(amount)
+    ));
+    if let Err(e) = store.insert(key, original) {
+        warn!("Failed to record refund of {}: {}", key, e);
+    }
+    Ok(())
+}
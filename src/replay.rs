@@ -0,0 +1,289 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::audit::AuditEntry;
+use crate::de7;
+use crate::generate_rrn;
+
+// ============================================================================
+// Session Replay
+// ============================================================================
+//
+// A session is recorded by the audit log (AUDIT_LOG): one NDJSON line per
+// message exchange with its arrival time, request and response. With
+// `--log-sensitive` the requests keep their full PANs; otherwise DE2 is
+// masked and track/PIN data dropped, so card-profile behaviour won't
+// reproduce.
+//
+// Replay resends the requests one at a time in arrival order, each at its
+// recorded offset into the session divided by the speed. STANs are
+// regenerated from a seed, the same one for every message that shared an
+// acquirer and STAN (so reversals still find their authorization), and DE7
+// is shifted by the time between recording and replay. DE90 and DE37
+// follow. Each response is compared with the recorded one.
+
+const MESSAGE_PATHS: &[&str] = &["/authorize", "/reversal", "/completion"];
+
+/// Reads a recorded session, keeping only message exchanges with a JSON
+/// request, in arrival order.
+pub fn load(path: &Path) -> Result<Vec<AuditEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {}", path.display(), index + 1, e))?;
+        if MESSAGE_PATHS.contains(&entry.path.as_str()) && entry.request.is_object() {
+            entries.push(entry);
+        }
+    }
+    entries.sort_by_key(|entry| entry.at);
+    Ok(entries)
+}
+
+/// How fast to resend: the recorded gaps divided by `speed`, or none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pace {
+    Speed(f64),
+    Unpaced,
+}
+
+impl Pace {
+    /// `1` (as recorded), `10` (ten times faster) or `max` (no waiting).
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if value == "max" {
+            return Ok(Pace::Unpaced);
+        }
+        match value.parse::<f64>() {
+            Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(Pace::Speed(speed)),
+            _ => Err(format!(
+                "invalid speed {:?} (use a positive number or max)",
+                value
+            )),
+        }
+    }
+
+    /// Time into the replay for a message recorded `offset` into the
+    /// session.
+    fn wait(self, offset: ChronoDuration) -> Duration {
+        match self {
+            Pace::Unpaced => Duration::ZERO,
+            Pace::Speed(speed) => offset
+                .to_std()
+                .map(|gap| gap.div_f64(speed))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// ============================================================================
+// STAN and DE7 Regeneration
+// ============================================================================
+
+/// Rewrites STAN-, time- and RRN-bearing fields of recorded requests.
+pub struct Regenerator {
+    stans: HashMap<(String, String), String>,
+    next_stan: u32,
+    shift: ChronoDuration,
+}
+
+impl Regenerator {
+    /// STANs count up from a start drawn from `seed`; DE7 moves by
+    /// `shift`.
+    pub fn new(seed: u64, shift: ChronoDuration) -> Self {
+        Regenerator {
+            stans: HashMap::new(),
+            next_stan: StdRng::seed_from_u64(seed).gen_range(1..=999_999),
+            shift,
+        }
+    }
+
+    fn stan(&mut self, acquirer_id: &str, original: &str) -> String {
+        let key = (acquirer_id.to_string(), original.to_string());
+        if let Some(stan) = self.stans.get(&key) {
+            return stan.clone();
+        }
+        let stan = format!("{:06}", self.next_stan);
+        self.next_stan = self.next_stan % 999_999 + 1;
+        self.stans.insert(key, stan.clone());
+        stan
+    }
+
+    /// Shifted MMDDhhmmss, read in the year nearest `recorded_at`;
+    /// unparseable values are kept.
+    fn transmission_time(&self, value: &str, recorded_at: DateTime<Utc>) -> String {
+        match de7::parse(value, recorded_at) {
+            Some(time) => de7::format(time + self.shift),
+            None => value.to_string(),
+        }
+    }
+
+    /// The request with DE11, DE7, DE37 and the STAN and time inside DE90
+    /// regenerated.
+    pub fn rewrite(&mut self, entry: &AuditEntry) -> Value {
+        let mut request = entry.request.clone();
+        let Some(message) = request.as_object_mut() else {
+            return request;
+        };
+        let text = |name: &str| {
+            message
+                .get(name)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let acquirer_id = text("de32").unwrap_or_default();
+        let (stan, de7, de37) = (text("de11"), text("de7"), text("de37"));
+        let de90 = text("de90").filter(|de90| de90.len() >= 20 && de90.is_ascii());
+
+        if let Some(stan) = stan {
+            let new_stan = self.stan(&acquirer_id, &stan);
+            if de37.is_some_and(|rrn| rrn.ends_with(&stan)) {
+                message.insert(
+                    "de37".to_string(),
+                    Value::String(generate_rrn(&new_stan, Utc::now())),
+                );
+            }
+            message.insert("de11".to_string(), Value::String(new_stan));
+        }
+        if let Some(de7) = de7 {
+            let de7 = self.transmission_time(&de7, entry.at);
+            message.insert("de7".to_string(), Value::String(de7));
+        }
+        // DE90: original MTI (4), STAN (6), transmission time (10), then
+        // the institution IDs.
+        if let Some(de90) = de90 {
+            let stan = self.stan(&acquirer_id, &de90[4..10]);
+            let de7 = self.transmission_time(&de90[10..20], entry.at);
+            let de90 = format!("{}{}{}{}", &de90[..4], stan, de7, &de90[20..]);
+            message.insert("de90".to_string(), Value::String(de90));
+        }
+        request
+    }
+}
+
+// ============================================================================
+// Runner
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// 1-based position in the session.
+    pub index: usize,
+    pub path: String,
+    pub stan: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    /// Requests recorded with a masked PAN.
+    pub masked: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for divergence in &self.divergences {
+            writeln!(
+                f,
+                "{:>6}  {} STAN {}: {}",
+                divergence.index, divergence.path, divergence.stan, divergence.detail
+            )?;
+        }
+        if self.masked > 0 {
+            writeln!(
+                f,
+                "{} requests were recorded with masked PANs (record with --log-sensitive to keep them)",
+                self.masked
+            )?;
+        }
+        writeln!(
+            f,
+            "{} messages replayed, {} differ from the recording",
+            self.replayed,
+            self.divergences.len()
+        )
+    }
+}
+
+/// The recorded and replayed status and DE39, when they differ.
+fn compare(entry: &AuditEntry, status: u16, response: &Value) -> Option<String> {
+    let recorded_code = entry.response["de39"].as_str();
+    let replayed_code = response["de39"].as_str();
+    if entry.status.is_some_and(|recorded| recorded != status) {
+        return Some(format!(
+            "HTTP {} (recorded {})",
+            status,
+            entry.status.unwrap_or_default()
+        ));
+    }
+    (recorded_code != replayed_code).then(|| {
+        format!(
+            "DE39 {} (recorded {})",
+            replayed_code.unwrap_or("none"),
+            recorded_code.unwrap_or("none")
+        )
+    })
+}
+
+/// Resends `entries` to the mock at `base_url`.
+pub async fn run(entries: &[AuditEntry], base_url: &str, pace: Pace, seed: u64) -> ReplayReport {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("reqwest client with default TLS");
+    let base_url = base_url.trim_end_matches('/');
+    let mut report = ReplayReport::default();
+    let Some(first) = entries.first() else {
+        return report;
+    };
+    let mut regenerator = Regenerator::new(seed, Utc::now() - first.at);
+
+    // Offsets are measured from the start so slow responses don't add up.
+    let started = tokio::time::Instant::now();
+    for (index, entry) in entries.iter().enumerate() {
+        tokio::time::sleep_until(started + pace.wait(entry.at - first.at)).await;
+
+        let request = regenerator.rewrite(entry);
+        if request["de2"].as_str().is_some_and(|pan| pan.contains('*')) {
+            report.masked += 1;
+        }
+        let stan = request["de11"].as_str().unwrap_or_default().to_string();
+        let url = format!("{}{}", base_url, entry.path);
+        let mut post = client.post(&url).json(&request);
+        if let Some(id) = &entry.correlation_id {
+            post = post.header("x-correlation-id", id);
+        }
+
+        report.replayed += 1;
+        let detail = match post.send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let body = response.json().await.unwrap_or(Value::Null);
+                compare(entry, status, &body)
+            }
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(detail) = detail {
+            report.divergences.push(Divergence {
+                index: index + 1,
+                path: entry.path.clone(),
+                stan,
+                detail,
+            });
+        }
+    }
+    report
+}
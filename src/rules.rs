@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::acquirers::AcquirerProfile;
+use crate::codes::ResponseCode;
+use crate::contactless::ContactlessConfig;
+use crate::dcc::DccConfig;
+use crate::expiry::HoldExpiryConfig;
+use crate::fallback::ChipFallbackConfig;
+use crate::faults::Fault;
+use crate::fraud::FraudConfig;
+use crate::interchange::InterchangeConfig;
+use crate::issuers::IssuerProfile;
+use crate::latency::{EndpointLatency, Latency};
+use crate::merchants::MerchantRegistry;
+use crate::processing::MessageMode;
+use crate::random_decline::RandomDeclineConfig;
+use crate::rate_limit::RateLimitConfig;
+use crate::refunds::RefundConfig;
+use crate::tokenization::TokenizationConfig;
+use crate::{iso8583, validation};
+
+// ============================================================================
+// Field Access
+// ============================================================================
+
+/// Anything that can expose its data elements by name (`"de2"`, `"de61"`, ...).
+pub trait FieldSource {
+    fn field(&self, name: &str) -> Option<&str>;
+}
+
+// ============================================================================
+// Rule Definitions (loaded from YAML)
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    pub default: Action,
+    #[serde(default)]
+    pub endpoint_latency: EndpointLatency,
+    pub random_decline: Option<RandomDeclineConfig>,
+    pub fraud_scoring: Option<FraudConfig>,
+    #[serde(default)]
+    pub contactless: ContactlessConfig,
+    pub chip_fallback: Option<ChipFallbackConfig>,
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Eligibility thresholds for tokenization authorization requests.
+    #[serde(default)]
+    pub tokenization: TokenizationConfig,
+    /// Acquirer profiles keyed by DE32.
+    #[serde(default)]
+    pub acquirers: HashMap<String, AcquirerProfile>,
+    /// Simulated interchange rates for exports and settlement.
+    pub interchange: Option<InterchangeConfig>,
+    /// FX rates and markup limit for DCC authorizations.
+    pub dcc: Option<DccConfig>,
+    /// Dual-message unless an issuer overrides it.
+    #[serde(default)]
+    pub message_mode: MessageMode,
+    /// Issuer personalities selected by the BIN of DE2.
+    #[serde(default)]
+    pub issuers: Vec<IssuerProfile>,
+    /// How long uncaptured authorizations hold funds.
+    #[serde(default)]
+    pub hold_expiry: HoldExpiryConfig,
+    /// Limits on refunds linked to their original, and the rule for
+    /// standalone credits.
+    #[serde(default)]
+    pub refunds: RefundConfig,
+    /// Mandatory DEs by MTI, replacing the built-in list for that MTI.
+    #[serde(default)]
+    pub mandatory_fields: HashMap<String, Vec<u8>>,
+    /// Registered MCCs by acquirer, for `merchant.registered`.
+    #[serde(default)]
+    pub merchants: MerchantRegistry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    /// Higher priority rules are evaluated first.
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub when: Conditions,
+    pub then: Action,
+}
+
+/// All present conditions must match for the rule to fire.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Conditions {
+    pub amount: Option<AmountRange>,
+    pub mcc: Option<Vec<String>>,
+    pub bin_prefix: Option<Vec<String>>,
+    /// DE41 terminal IDs; trailing spaces are ignored.
+    pub terminal_id: Option<Vec<String>>,
+    /// DE42 card acceptor IDs; trailing spaces are ignored.
+    pub card_acceptor_id: Option<Vec<String>>,
+    pub de61: Option<Vec<SubfieldMatch>>,
+    #[serde(default)]
+    pub fields: HashMap<String, FieldMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmountRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+/// Fixed-position subfield of DE61 (1-based position, as in the Mastercard spec).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubfieldMatch {
+    pub position: usize,
+    #[serde(default = "default_subfield_length")]
+    pub length: usize,
+    pub equals: String,
+}
+
+fn default_subfield_length() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldMatch {
+    pub equals: Option<String>,
+    pub one_of: Option<Vec<String>>,
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Action {
+    pub response_code: ResponseCode,
+    /// Shorthand for a fixed `latency`.
+    pub delay_ms: Option<u64>,
+    pub latency: Option<Latency>,
+    pub partial_amount: Option<String>,
+    pub fault: Option<Fault>,
+    /// Decline e-commerce without full 3DS authentication (UCAF) with 65.
+    #[serde(default)]
+    pub require_3ds: bool,
+    /// Answer 202 at once and deliver the 0110 after this long.
+    pub defer: Option<Latency>,
+}
+
+impl Action {
+    /// Answers with `response_code` and nothing else.
+    pub fn respond(response_code: ResponseCode) -> Self {
+        Action {
+            response_code,
+            delay_ms: None,
+            latency: None,
+            partial_amount: None,
+            fault: None,
+            require_3ds: false,
+            defer: None,
+        }
+    }
+
+    pub fn latency(&self) -> Option<Latency> {
+        self.latency
+            .clone()
+            .or_else(|| self.delay_ms.map(|ms| Latency::Fixed { ms }))
+    }
+}
+
+// ============================================================================
+// Loading
+// ============================================================================
+
+impl RuleSet {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Self::from_yaml(&contents)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let mut rule_set: RuleSet =
+            serde_yaml::from_str(yaml).map_err(|e| format!("invalid rules: {}", e))?;
+        rule_set.sort();
+        for (de32, profile) in rule_set.acquirers.iter_mut() {
+            profile.check(de32)?;
+        }
+        for issuer in &rule_set.issuers {
+            issuer.check()?;
+        }
+        if let Some(dcc) = &mut rule_set.dcc {
+            dcc.check()?;
+        }
+        rule_set.merchants.check()?;
+        for (mti, fields) in &rule_set.mandatory_fields {
+            if let Some(number) = fields.iter().find(|&&n| iso8583::field(n).is_none()) {
+                return Err(format!(
+                    "mandatory_fields {}: DE{} is not a registered data element",
+                    mti, number
+                ));
+            }
+        }
+        Ok(rule_set)
+    }
+
+    /// Mastercard credit BINs (2-series and 51-55) approve; Maestro and
+    /// test BINs decline with 05.
+    pub fn builtin() -> Self {
+        RuleSet {
+            rules: vec![Rule {
+                name: "approve-mastercard".to_string(),
+                priority: 0,
+                when: Conditions {
+                    bin_prefix: Some(
+                        ["2", "51", "52", "53", "54", "55"]
+                            .map(String::from)
+                            .to_vec(),
+                    ),
+                    ..Conditions::default()
+                },
+                then: Action::respond(ResponseCode::Approved),
+            }],
+            default: Action::respond(ResponseCode::DoNotHonor),
+            endpoint_latency: EndpointLatency::default(),
+            random_decline: None,
+            fraud_scoring: None,
+            contactless: ContactlessConfig::default(),
+            chip_fallback: None,
+            rate_limit: None,
+            tokenization: TokenizationConfig::default(),
+            acquirers: HashMap::new(),
+            interchange: None,
+            dcc: None,
+            message_mode: MessageMode::default(),
+            issuers: Vec::new(),
+            hold_expiry: HoldExpiryConfig::default(),
+            refunds: RefundConfig::default(),
+            mandatory_fields: HashMap::new(),
+            merchants: MerchantRegistry::default(),
+        }
+    }
+
+    fn sort(&mut self) {
+        // Stable sort keeps file order for rules sharing a priority.
+        self.rules.sort_by_key(|rule| Reverse(rule.priority));
+        for acquirer in self.acquirers.values_mut() {
+            acquirer.rules.sort_by_key(|rule| Reverse(rule.priority));
+        }
+        for issuer in &mut self.issuers {
+            issuer.rules.sort_by_key(|rule| Reverse(rule.priority));
+        }
+    }
+
+    /// The issuer whose BINs contain `pan`, the longest BIN winning.
+    pub fn issuer(&self, pan: &str) -> Option<&IssuerProfile> {
+        self.issuers
+            .iter()
+            .filter_map(|issuer| issuer.match_width(pan).map(|width| (width, issuer)))
+            .rev()
+            .max_by_key(|(width, _)| *width)
+            .map(|(_, issuer)| issuer)
+    }
+
+    /// The DEs a message of type `mti` must carry.
+    pub fn mandatory_fields(&self, mti: &str) -> &[u8] {
+        self.mandatory_fields
+            .get(mti)
+            .map(Vec::as_slice)
+            .unwrap_or_else(|| validation::default_mandatory_fields(mti))
+    }
+
+    /// The message mode for `pan`: its issuer's, else the shared one.
+    pub fn message_mode(&self, pan: &str) -> MessageMode {
+        self.issuer(pan)
+            .and_then(|issuer| issuer.message_mode)
+            .unwrap_or(self.message_mode)
+    }
+
+    /// Returns the first matching rule's name and action, or the default action.
+    /// The message's acquirer (DE32) rules and default take precedence, then
+    /// those of the card's issuer.
+    pub fn evaluate(&self, message: &impl FieldSource) -> (Option<&str>, &Action) {
+        if let Some(acquirer) = message.field("de32").and_then(|id| self.acquirers.get(id)) {
+            if let Some(rule) = acquirer
+                .rules
+                .iter()
+                .find(|rule| rule.when.matches(message))
+            {
+                return (Some(rule.name.as_str()), &rule.then);
+            }
+            if let Some(default) = &acquirer.default {
+                return (None, default);
+            }
+        }
+
+        if let Some(issuer) = message.field("de2").and_then(|pan| self.issuer(pan)) {
+            if let Some(rule) = issuer.rules.iter().find(|rule| rule.when.matches(message)) {
+                return (Some(rule.name.as_str()), &rule.then);
+            }
+            if let Some(default) = &issuer.default {
+                return (None, default);
+            }
+        }
+
+        self.rules
+            .iter()
+            .find(|rule| rule.when.matches(message))
+            .map(|rule| (Some(rule.name.as_str()), &rule.then))
+            .unwrap_or((None, &self.default))
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+impl Conditions {
+    pub fn matches(&self, message: &impl FieldSource) -> bool {
+        if let Some(range) = &self.amount {
+            let amount = match message.field("de4").and_then(|v| v.parse::<u64>().ok()) {
+                Some(amount) => amount,
+                None => return false,
+            };
+            if range.min.is_some_and(|min| amount < min)
+                || range.max.is_some_and(|max| amount > max)
+            {
+                return false;
+            }
+        }
+
+        if let Some(mccs) = &self.mcc {
+            let mcc = message.field("de18").unwrap_or_default();
+            if !mccs.iter().any(|m| m == mcc) {
+                return false;
+            }
+        }
+
+        if let Some(prefixes) = &self.bin_prefix {
+            let pan = message.field("de2").unwrap_or_default();
+            if !prefixes.iter().any(|p| pan.starts_with(p.as_str())) {
+                return false;
+            }
+        }
+
+        for (ids, name) in [
+            (&self.terminal_id, "de41"),
+            (&self.card_acceptor_id, "de42"),
+        ] {
+            if let Some(ids) = ids {
+                let id = message.field(name).unwrap_or_default().trim_end();
+                if !ids.iter().any(|i| i.trim_end() == id) {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(subfields) = &self.de61 {
+            let de61 = message.field("de61").unwrap_or_default();
+            if !subfields.iter().all(|s| s.matches(de61)) {
+                return false;
+            }
+        }
+
+        self.fields
+            .iter()
+            .all(|(name, matcher)| matcher.matches(message.field(name)))
+    }
+}
+
+impl SubfieldMatch {
+    fn matches(&self, de61: &str) -> bool {
+        let start = self.position.saturating_sub(1);
+        start
+            .checked_add(self.length)
+            .and_then(|end| de61.get(start..end))
+            == Some(self.equals.as_str())
+    }
+}
+
+impl FieldMatch {
+    fn matches(&self, value: Option<&str>) -> bool {
+        let value = match value {
+            Some(value) => value,
+            None => return false,
+        };
+        if self.equals.as_deref().is_some_and(|e| e != value) {
+            return false;
+        }
+        if self
+            .one_of
+            .as_ref()
+            .is_some_and(|list| !list.iter().any(|v| v == value))
+        {
+            return false;
+        }
+        if self
+            .prefix
+            .as_deref()
+            .is_some_and(|p| !value.starts_with(p))
+        {
+            return false;
+        }
+        true
+    }
+}
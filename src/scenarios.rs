@@ -0,0 +1,271 @@
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::builder;
+use crate::TransactionState;
+
+// ============================================================================
+// Scenario Files (loaded from YAML)
+// ============================================================================
+//
+// A scenario is a sequence of messages sent to a running mock, each with
+// the response fields, HTTP status or stored state it must get.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub name: Option<String>,
+    pub send: Endpoint,
+    pub message: Value,
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endpoint {
+    Authorize,
+    Reversal,
+    Completion,
+}
+
+impl Endpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Endpoint::Authorize => "/authorize",
+            Endpoint::Reversal => "/reversal",
+            Endpoint::Completion => "/completion",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Expectation {
+    pub status: Option<u16>,
+    pub state: Option<TransactionState>,
+    #[serde(flatten)]
+    pub fields: BTreeMap<String, Option<String>>,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        serde_yaml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Loads every scenario named by `paths`; directories contribute their
+/// `.yaml`/`.yml` files in name order.
+pub fn load_all(paths: &[PathBuf]) -> Result<Vec<Scenario>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries =
+                fs::read_dir(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| {
+                    file.extension()
+                        .is_some_and(|ext| ext == "yaml" || ext == "yml")
+                })
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files.iter().map(|file| Scenario::load(file)).collect()
+}
+
+// ============================================================================
+// Runner
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.failures.is_empty())
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "PASS" } else { "FAIL" };
+        writeln!(f, "{}  {} ({} steps)", verdict, self.name, self.steps.len())?;
+        for (index, step) in self.steps.iter().enumerate() {
+            for failure in &step.failures {
+                writeln!(f, "      step {} ({}): {}", index + 1, step.name, failure)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces `{{stan}}` and `{{de7}}` in every string of the message.
+fn substitute(value: &mut Value, variables: &[(&str, &str)]) {
+    match value {
+        Value::String(text) => {
+            for (name, replacement) in variables {
+                *text = text.replace(name, replacement);
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| substitute(item, variables)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|item| substitute(item, variables)),
+        _ => {}
+    }
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Scenario {
+    /// Sends every step to the mock at `base_url`, in order. A failing
+    /// step doesn't stop the run, so one report shows every mismatch.
+    pub async fn run(&self, base_url: &str) -> ScenarioReport {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("reqwest client with default TLS");
+        let base_url = base_url.trim_end_matches('/');
+        let stan = format!("{:06}", rand::thread_rng().gen_range(1..=999_999));
+        let de7 = builder::transmission_time();
+        let variables = [("{{stan}}", stan.as_str()), ("{{de7}}", de7.as_str())];
+
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let mut message = step.message.clone();
+            substitute(&mut message, &variables);
+            let name = step
+                .name
+                .clone()
+                .unwrap_or_else(|| step.send.path()[1..].to_string());
+            let failures = step.check(&client, base_url, &message).await;
+            steps.push(StepReport { name, failures });
+        }
+        ScenarioReport {
+            name: self.name.clone(),
+            steps,
+        }
+    }
+}
+
+impl Step {
+    async fn check(
+        &self,
+        client: &reqwest::Client,
+        base_url: &str,
+        message: &Value,
+    ) -> Vec<String> {
+        let url = format!("{}{}", base_url, self.send.path());
+        let response = match client.post(&url).json(message).send().await {
+            Ok(response) => response,
+            Err(e) => return vec![format!("POST {}: {}", url, e)],
+        };
+        let status = response.status().as_u16();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+
+        let mut failures = Vec::new();
+        let expected_status = self.expect.status.unwrap_or(200);
+        if status != expected_status {
+            failures.push(format!(
+                "HTTP status: expected {}, got {} {}",
+                expected_status, status, body
+            ));
+            return failures;
+        }
+
+        for (field, expected) in &self.expect.fields {
+            let actual = body.get(field).filter(|value| !value.is_null());
+            let failure = match (expected.as_deref(), actual) {
+                (None, Some(value)) => Some(format!("expected absent, got {}", value)),
+                (None, None) | (Some("*"), Some(_)) => None,
+                (Some(_), None) => Some("expected present, but absent".to_string()),
+                (Some(expected), Some(value)) if field_text(value) == expected => None,
+                (Some(expected), Some(value)) => {
+                    Some(format!("expected {:?}, got {}", expected, value))
+                }
+            };
+            if let Some(failure) = failure {
+                failures.push(format!("{}: {}", field, failure));
+            }
+        }
+
+        if let Some(expected) = self.expect.state {
+            match stored_state(client, base_url, message).await {
+                Ok(state) if state == expected => {}
+                Ok(state) => {
+                    failures.push(format!("state: expected {:?}, got {:?}", expected, state))
+                }
+                Err(e) => failures.push(format!("state: {}", e)),
+            }
+        }
+        failures
+    }
+}
+
+/// State of the transaction stored under the message's DE32 and DE11.
+async fn stored_state(
+    client: &reqwest::Client,
+    base_url: &str,
+    message: &Value,
+) -> Result<TransactionState, String> {
+    let acquirer_id = message["de32"].as_str().unwrap_or_default();
+    let stan = message["de11"].as_str().unwrap_or_default();
+    let url = format!("{}/transactions", base_url);
+    let page: Value = client
+        .get(&url)
+        .query(&[("acquirer_id", acquirer_id), ("stan", stan)])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("GET {}: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("GET {}: {}", url, e))?;
+
+    let transaction = page["transactions"]
+        .as_array()
+        .and_then(|transactions| transactions.last())
+        .ok_or_else(|| {
+            format!(
+                "no stored transaction for DE32 {:?}, STAN {:?}",
+                acquirer_id, stan
+            )
+        })?;
+    serde_json::from_value(transaction["state"].clone()).map_err(|e| e.to_string())
+}
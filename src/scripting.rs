@@ -0,0 +1,96 @@
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Mutex;
+
+use crate::codes::ResponseCode;
+use crate::rules::Action;
+
+// ============================================================================
+// Rhai Response Hooks
+// ============================================================================
+//
+// The script runs once per authorization with two variables in scope:
+//
+//   request  - the parsed request as a map (request.de2, request.de4, ...)
+//   state    - a map that survives between calls, for counters and the like
+//
+// It may evaluate to `()` (no change) or a map with any of
+// `response_code`, `response_message`, `delay_ms`, `partial_amount`.
+
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct ScriptHook {
+    engine: Engine,
+    ast: AST,
+    scope: Mutex<Scope<'static>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptOverride {
+    pub response_code: Option<ResponseCode>,
+    pub response_message: Option<String>,
+    pub delay_ms: Option<u64>,
+    pub partial_amount: Option<String>,
+}
+
+impl ScriptHook {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Self::compile(&source)
+    }
+
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("script compile error: {}", e))?;
+
+        let mut scope = Scope::new();
+        scope.push("state", Map::new());
+
+        Ok(ScriptHook {
+            engine,
+            ast,
+            scope: Mutex::new(scope),
+        })
+    }
+
+    pub fn run(&self, request: &impl Serialize) -> Result<Option<ScriptOverride>, String> {
+        let request = rhai::serde::to_dynamic(request).map_err(|e| e.to_string())?;
+
+        let mut scope = self.scope.lock().unwrap();
+        scope.set_value("request", request);
+
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| format!("script runtime error: {}", e))?;
+
+        if result.is_unit() {
+            return Ok(None);
+        }
+
+        rhai::serde::from_dynamic(&result)
+            .map(Some)
+            .map_err(|e| format!("script returned an invalid override: {}", e))
+    }
+}
+
+impl ScriptOverride {
+    pub fn apply(&self, action: &mut Action) {
+        if let Some(code) = self.response_code {
+            action.response_code = code;
+        }
+        if self.delay_ms.is_some() {
+            action.delay_ms = self.delay_ms;
+            action.latency = None;
+        }
+        if self.partial_amount.is_some() {
+            action.partial_amount = self.partial_amount.clone();
+        }
+    }
+}
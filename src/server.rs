@@ -0,0 +1,341 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, DefaultBodyLimit},
+    http::Request,
+    middleware,
+    routing::{delete, get, post},
+    Router,
+};
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+use tracing::{debug, warn};
+
+use crate::faults::CutShort;
+use crate::settings::Settings;
+use crate::{
+    acquirers, admin, audit, auth, batch, bins, clearing, clock, correlation, cutover, dashboard,
+    deferred, dispatch, expiry, export, feed, handlers, health, incidents, matching, merchants,
+    monitor, openapi, originals, pipeline, profiles, reference, settlement, snapshot, tokens,
+    transactions, webhooks, AppState,
+};
+
+// ============================================================================
+// Routes
+// ============================================================================
+
+/// The full HTTP API: message, transaction, health and admin routes, and
+/// the OpenAPI document describing them.
+pub fn router(state: Arc<AppState>) -> Router {
+    let admin_routes = Router::new()
+        .route("/admin/cards", get(admin::list_cards))
+        .route(
+            "/admin/cards/:pan/status",
+            get(admin::get_card_status).put(admin::set_card_status),
+        )
+        .route("/admin/cards/:pan/pin-reset", post(admin::reset_pin_tries))
+        .route("/admin/cards/generate", post(admin::generate_cards))
+        .route(
+            "/admin/tokens",
+            get(tokens::list_tokens).post(tokens::provision_token),
+        )
+        .route("/admin/tokens/:token", delete(tokens::delete_token))
+        .route("/admin/accounts", get(admin::list_accounts))
+        .route(
+            "/admin/accounts/:pan",
+            get(admin::get_account)
+                .put(admin::update_account)
+                .delete(admin::close_account),
+        )
+        .route("/admin/accounts/:pan/adjust", post(admin::adjust_balance))
+        .route("/admin/store", get(admin::store_stats))
+        .route("/admin/transactions/lookup", get(originals::lookup_original))
+        .route("/admin/rules", get(admin::active_rules))
+        .route(
+            "/admin/incident",
+            get(incidents::get_incident)
+                .put(incidents::start_incident)
+                .delete(incidents::clear_incident),
+        )
+        .route(
+            "/admin/merchants",
+            get(merchants::list_merchants).post(merchants::register_merchant),
+        )
+        .route(
+            "/admin/merchants/:acquirer",
+            delete(merchants::remove_acquirer),
+        )
+        .route(
+            "/admin/merchants/:acquirer/:mcc",
+            delete(merchants::unregister_merchant),
+        )
+        .route(
+            "/admin/faults",
+            get(admin::get_faults).put(admin::set_faults),
+        )
+        .route("/admin/profile", get(profiles::get_profile))
+        .route("/admin/profile/:name", post(profiles::switch_profile))
+        .route("/admin/audit", get(audit::tail_audit_log))
+        .route("/admin/export", get(export::export_transactions))
+        .route("/admin/clearing/ipm", get(clearing::ipm_file))
+        .route("/admin/settlement", get(settlement::settlement_report))
+        .route("/admin/settlement/cutover", post(cutover::force_cutover))
+        .route(
+            "/admin/clock",
+            get(clock::get_clock)
+                .put(clock::set_clock)
+                .delete(clock::reset_clock),
+        )
+        .route("/admin/seed", post(admin::seed))
+        .route("/admin/snapshot", post(snapshot::save_snapshot))
+        .route("/admin/restore", post(snapshot::restore_snapshot))
+        .route(
+            "/admin/webhooks",
+            get(webhooks::list_webhooks).post(webhooks::register_webhook),
+        )
+        .route("/admin/webhooks/:id", delete(webhooks::delete_webhook))
+        .route("/admin/reset", post(admin::reset_all))
+        .route("/admin/reset/transactions", post(admin::reset_transactions))
+        .route("/admin/reset/counters", post(admin::reset_counters))
+        .route("/admin/reset/cards", post(admin::reload_card_profiles))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_api_key,
+        ));
+
+    let message_routes = Router::new()
+        .route("/authorize", post(handlers::authorize))
+        .route("/reversal", post(handlers::reversal))
+        .route("/completion", post(handlers::completion))
+        // A batch and /iso8583 run their messages through the pipeline
+        // themselves.
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            pipeline::apply,
+        ))
+        .route("/authorize/batch", post(batch::authorize_batch))
+        .route("/iso8583", post(dispatch::dispatch))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            acquirers::verify_signature,
+        ))
+        .route_layer(middleware::from_fn_with_state(state.clone(), audit::record));
+
+    let app = Router::new()
+        .route("/health", get(health::health))
+        .route("/ready", get(health::ready))
+        .route("/monitor", get(monitor::stats_stream))
+        .route("/dashboard", get(dashboard::dashboard))
+        .merge(message_routes)
+        .route("/authorize/:reference", get(deferred::get_deferred))
+        .route("/transactions", get(transactions::list_transactions))
+        .route("/transactions/:rrn", get(transactions::get_transaction))
+        .route("/ws/transactions", get(feed::transaction_socket))
+        .route("/clearing", post(matching::submit_clearing))
+        .route("/clearing/ipm", post(matching::submit_ipm))
+        .route("/clearing/report", get(matching::matching_report))
+        .route("/reference/currencies", get(reference::list_currencies))
+        .route("/reference/mccs", get(reference::list_mccs))
+        .route("/reference/bins", get(bins::list_bins))
+        .route("/reference/bins/:pan", get(bins::lookup_bin))
+        .merge(admin_routes)
+        .merge(openapi::routes())
+        .layer(middleware::from_fn(correlation::echo_header))
+        .layer(DefaultBodyLimit::max(state.max_body_bytes))
+        .layer(CorsLayer::permissive())
+        .with_state(state);
+    #[cfg(feature = "otel")]
+    let app = app.layer(middleware::from_fn(crate::telemetry::propagate));
+    app
+}
+
+/// Serves the simulator on an ephemeral localhost port in the background,
+/// with the hold expiry sweeper and cutover scheduler running, and returns
+/// the bound address. Meant for embedding in integration tests.
+pub async fn spawn(state: Arc<AppState>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    state.listening.store(true, Ordering::Relaxed);
+    expiry::spawn_sweeper(state.clone());
+    cutover::spawn_scheduler(state.clone());
+    tokio::spawn(serve(listener, router(state), Limits::default()));
+    Ok(addr)
+}
+
+// ============================================================================
+// HTTP Listener Limits
+// ============================================================================
+//
+// `axum::serve` has no knobs for slow or runaway clients, so connections are
+// accepted here and served by hyper directly:
+//
+//   MAX_BODY_BYTES       request body limit, answered with 413 (default 64 KiB)
+//   HEADER_READ_TIMEOUT  time allowed to send the request headers, e.g. `10s`
+//                        (default 10s); slower clients are disconnected
+//   MAX_CONNECTIONS      open connections; further clients wait in the accept
+//                        backlog until one closes (default unlimited)
+
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+const DEFAULT_HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub header_read_timeout: Duration,
+    pub max_connections: Option<NonZeroUsize>,
+}
+
+impl Limits {
+    /// HEADER_READ_TIMEOUT and MAX_CONNECTIONS, panicking on invalid values.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Limits {
+            header_read_timeout: settings.var("HEADER_READ_TIMEOUT").map_or(
+                DEFAULT_HEADER_READ_TIMEOUT,
+                |timeout| {
+                    expiry::parse_duration(&timeout)
+                        .and_then(|timeout| timeout.to_std().map_err(|e| e.to_string()))
+                        .expect("Invalid HEADER_READ_TIMEOUT")
+                },
+            ),
+            max_connections: settings.var("MAX_CONNECTIONS").map(|max| {
+                max.parse()
+                    .expect("MAX_CONNECTIONS must be a positive integer")
+            }),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            header_read_timeout: DEFAULT_HEADER_READ_TIMEOUT,
+            max_connections: None,
+        }
+    }
+}
+
+pub async fn serve(listener: TcpListener, app: Router, limits: Limits) {
+    let permits = limits
+        .max_connections
+        .map(|max| Arc::new(Semaphore::new(max.get())));
+
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(limits.header_read_timeout);
+    let builder = Arc::new(builder);
+
+    loop {
+        let permit = match &permits {
+            Some(permits) => Some(permits.clone().acquire_owned().await.unwrap()),
+            None => None,
+        };
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                // Out of file descriptors (EMFILE/ENFILE) fails every accept
+                // until a connection closes; don't spin on it.
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let connection = Connection {
+            stream,
+            reset: Arc::new(AtomicBool::new(false)),
+        };
+        let reset = connection.reset.clone();
+        let app = app.clone();
+        let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+            request.extensions_mut().insert(ConnectInfo(peer));
+            let (app, reset) = (app.clone(), reset.clone());
+            async move {
+                let response = app.oneshot(request.map(Body::new)).await?;
+                if response
+                    .extensions()
+                    .get::<CutShort>()
+                    .is_some_and(|cut| cut.reset)
+                {
+                    reset.store(true, Ordering::Relaxed);
+                }
+                Ok::<_, Infallible>(response)
+            }
+        });
+
+        let builder = builder.clone();
+        tokio::spawn(async move {
+            if let Err(e) = builder
+                .serve_connection_with_upgrades(TokioIo::new(connection), service)
+                .await
+            {
+                debug!("Connection from {} closed: {}", peer, e);
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// An accepted connection, reset with a RST instead of closed once a
+/// response asks for it (`faults::CutShort`).
+struct Connection {
+    stream: TcpStream,
+    reset: Arc<AtomicBool>,
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if !self.reset.load(Ordering::Relaxed) {
+            return;
+        }
+        // Deprecated because a non-zero linger blocks on close; a zero one
+        // discards unsent data and sends the RST at once.
+        #[allow(deprecated)]
+        if let Err(e) = self.stream.set_linger(Some(Duration::ZERO)) {
+            warn!("Failed to set up connection reset: {}", e);
+        }
+    }
+}
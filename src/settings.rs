@@ -0,0 +1,500 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use crate::de7::SkewMode;
+use crate::expiry;
+use crate::latency::Latency;
+use crate::pan;
+use crate::rules::RuleSet;
+use crate::validation::ValidationMode;
+
+// ============================================================================
+// Layered Settings
+// ============================================================================
+//
+// Every setting has an environment variable name (PORT, STORAGE, ...). Its
+// value comes from, in order of precedence:
+//
+//   1. the command line: --storage <spec>, --port <n>, --log-sensitive
+//   2. the environment variable
+//   3. the TOML file named by --config <path> or CONFIG_FILE
+//
+// and otherwise its built-in default. The file is checked as a whole at
+// startup so every mistake is reported at once.
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub server: ServerSection,
+    #[serde(default)]
+    pub storage: StorageSection,
+    #[serde(default)]
+    pub cards: CardsSection,
+    #[serde(default)]
+    pub validation: ValidationSection,
+    #[serde(default)]
+    pub rules: RulesSection,
+    #[serde(default)]
+    pub faults: FaultsSection,
+    #[serde(default)]
+    pub completion: CompletionSection,
+    pub clearing: ClearingSection,
+    #[serde(default)]
+    pub settlement: SettlementSection,
+    #[serde(default)]
+    pub admin: AdminSection,
+    #[serde(default)]
+    pub webhooks: WebhooksSection,
+    #[serde(default)]
+    pub kafka: KafkaSection,
+    #[serde(default)]
+    pub audit: AuditSection,
+    #[serde(default)]
+    pub logging: LoggingSection,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSection {
+    /// BIND_ADDR
+    pub bind: Option<String>,
+    /// PORT
+    pub port: Option<u16>,
+    /// GRPC_PORT
+    pub grpc_port: Option<u16>,
+    /// MAX_BODY_BYTES
+    pub max_body_bytes: Option<usize>,
+    /// HEADER_READ_TIMEOUT
+    pub header_read_timeout: Option<String>,
+    /// MAX_CONNECTIONS
+    pub max_connections: Option<NonZeroUsize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReversalMode {
+    Strict,
+    Idempotent,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageSection {
+    /// STORAGE
+    pub backend: Option<String>,
+    /// MAX_TRANSACTIONS
+    pub max_transactions: Option<NonZeroUsize>,
+    /// TRANSACTION_TTL
+    pub transaction_ttl: Option<String>,
+    /// REVERSAL_MODE
+    pub reversal_mode: Option<ReversalMode>,
+    /// REVERSAL_MATCH
+    pub reversal_match: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardsSection {
+    /// CARDS_FILE
+    pub file: Option<String>,
+    /// BIN_TABLE
+    pub bin_table: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationSection {
+    /// CLOCK_SKEW
+    pub clock_skew: Option<String>,
+    /// CLOCK_SKEW_MODE
+    pub clock_skew_mode: Option<SkewMode>,
+    /// VALIDATION_MODE
+    pub mode: Option<ValidationMode>,
+    /// TEST_BINS
+    pub test_bins: Option<Vec<String>>,
+    /// UNIQUE_STANS
+    pub unique_stans: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulesSection {
+    /// RULES_FILE
+    pub file: Option<String>,
+    /// SCRIPT_FILE
+    pub script: Option<String>,
+    /// RANDOM_SEED
+    pub random_seed: Option<u64>,
+    /// PROFILES_DIR
+    pub profiles_dir: Option<String>,
+    /// PROFILE
+    pub profile: Option<String>,
+}
+
+/// Defaults for rule sets that don't set their own.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultsSection {
+    pub authorize_latency: Option<Latency>,
+    pub reversal_latency: Option<Latency>,
+    /// Fault for the default action (messages no rule matched).
+    pub default_fault: Option<crate::faults::Fault>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompletionSection {
+    /// CAPTURE_TOLERANCE
+    pub capture_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClearingSection {
+    /// CLEARING_AMOUNT_TOLERANCE
+    pub amount_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettlementSection {
+    /// SETTLEMENT_CUTOVER
+    pub cutover: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminSection {
+    /// ADMIN_API_KEYS
+    pub api_keys: Option<Vec<String>>,
+    /// SNAPSHOT_DIR
+    pub snapshot_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhooksSection {
+    /// WEBHOOK_MAX_ATTEMPTS
+    pub max_attempts: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSection {
+    /// KAFKA_BROKERS
+    pub brokers: Option<Vec<String>>,
+    /// KAFKA_TOPIC
+    pub topic: Option<String>,
+    /// KAFKA_FORMAT
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditSection {
+    /// AUDIT_LOG
+    pub path: Option<String>,
+    /// AUDIT_LOG_MAX_BYTES
+    pub max_bytes: Option<u64>,
+    /// AUDIT_LOG_FILES
+    pub files: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormatSetting {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingSection {
+    /// LOG_LEVEL
+    pub level: Option<String>,
+    /// LOG_FORMAT
+    pub format: Option<LogFormatSetting>,
+    /// LOG_SENSITIVE
+    pub sensitive: Option<bool>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let file: ConfigFile = toml::from_str(&content).map_err(|e| format!("{}: {}", path, e))?;
+        file.validate()
+            .map_err(|problems| format!("{}:\n  {}", path, problems.join("\n  ")))?;
+        Ok(file)
+    }
+
+    /// Checks what the TOML types can't: durations, the storage spec and
+    /// that referenced files exist.
+    fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        let durations = [
+            (
+                "server.header_read_timeout",
+                &self.server.header_read_timeout,
+            ),
+            ("storage.transaction_ttl", &self.storage.transaction_ttl),
+            ("validation.clock_skew", &self.validation.clock_skew),
+        ];
+        for (key, value) in durations {
+            if let Some(Err(e)) = value.as_deref().map(expiry::parse_duration) {
+                problems.push(format!("{}: {} (use e.g. 90s, 30m, 12h or 7d)", key, e));
+            }
+        }
+
+        for spec in self.validation.test_bins.iter().flatten() {
+            if let Err(e) = pan::BinRange::parse(spec) {
+                problems.push(format!("validation.test_bins: {}", e));
+            }
+        }
+
+        if let Some(backend) = &self.storage.backend {
+            let known = backend == "memory"
+                || backend.starts_with("sqlite:")
+                || backend.starts_with("redis://");
+            if !known {
+                problems.push(format!(
+                    "storage.backend: {:?} is not memory, sqlite:<path> or redis://host:port",
+                    backend
+                ));
+            }
+        }
+
+        let files = [
+            ("cards.file", &self.cards.file),
+            ("rules.file", &self.rules.file),
+            ("rules.script", &self.rules.script),
+        ];
+        for (key, path) in files {
+            if let Some(path) = path {
+                if !Path::new(path).is_file() {
+                    problems.push(format!("{}: {} does not exist", key, path));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// The file's values keyed by environment variable name.
+    fn values(&self) -> HashMap<&'static str, String> {
+        let mut values = HashMap::new();
+        let mut put = |name, value: Option<String>| {
+            if let Some(value) = value {
+                values.insert(name, value);
+            }
+        };
+
+        put("BIND_ADDR", self.server.bind.clone());
+        put("PORT", self.server.port.map(|v| v.to_string()));
+        put("GRPC_PORT", self.server.grpc_port.map(|v| v.to_string()));
+        put(
+            "MAX_BODY_BYTES",
+            self.server.max_body_bytes.map(|v| v.to_string()),
+        );
+        put(
+            "HEADER_READ_TIMEOUT",
+            self.server.header_read_timeout.clone(),
+        );
+        put(
+            "MAX_CONNECTIONS",
+            self.server.max_connections.map(|v| v.to_string()),
+        );
+        put("STORAGE", self.storage.backend.clone());
+        put(
+            "MAX_TRANSACTIONS",
+            self.storage.max_transactions.map(|v| v.to_string()),
+        );
+        put("TRANSACTION_TTL", self.storage.transaction_ttl.clone());
+        put(
+            "REVERSAL_MODE",
+            self.storage.reversal_mode.map(|mode| match mode {
+                ReversalMode::Strict => "strict".to_string(),
+                ReversalMode::Idempotent => "idempotent".to_string(),
+            }),
+        );
+        put(
+            "REVERSAL_MATCH",
+            self.storage
+                .reversal_match
+                .as_ref()
+                .map(|keys| keys.join(",")),
+        );
+        put("CARDS_FILE", self.cards.file.clone());
+        put("BIN_TABLE", self.cards.bin_table.clone());
+        put("CLOCK_SKEW", self.validation.clock_skew.clone());
+        put(
+            "CLOCK_SKEW_MODE",
+            self.validation.clock_skew_mode.map(|mode| match mode {
+                SkewMode::Reject => "reject".to_string(),
+                SkewMode::Flag => "flag".to_string(),
+            }),
+        );
+        put(
+            "VALIDATION_MODE",
+            self.validation.mode.map(|mode| match mode {
+                ValidationMode::Strict => "strict".to_string(),
+                ValidationMode::Lenient => "lenient".to_string(),
+            }),
+        );
+        put(
+            "TEST_BINS",
+            self.validation
+                .test_bins
+                .as_ref()
+                .map(|bins| bins.join(",")),
+        );
+        put(
+            "UNIQUE_STANS",
+            self.validation.unique_stans.map(|v| v.to_string()),
+        );
+        put("RULES_FILE", self.rules.file.clone());
+        put("SCRIPT_FILE", self.rules.script.clone());
+        put("RANDOM_SEED", self.rules.random_seed.map(|v| v.to_string()));
+        put("PROFILES_DIR", self.rules.profiles_dir.clone());
+        put("PROFILE", self.rules.profile.clone());
+        put(
+            "CAPTURE_TOLERANCE",
+            self.completion.capture_tolerance.map(|v| v.to_string()),
+        );
+        put(
+            "CLEARING_AMOUNT_TOLERANCE",
+            self.clearing.amount_tolerance.map(|v| v.to_string()),
+        );
+        put("SETTLEMENT_CUTOVER", self.settlement.cutover.clone());
+        put(
+            "ADMIN_API_KEYS",
+            self.admin.api_keys.as_ref().map(|keys| keys.join(",")),
+        );
+        put("SNAPSHOT_DIR", self.admin.snapshot_dir.clone());
+        put(
+            "WEBHOOK_MAX_ATTEMPTS",
+            self.webhooks.max_attempts.map(|v| v.to_string()),
+        );
+        put(
+            "KAFKA_BROKERS",
+            self.kafka.brokers.as_ref().map(|brokers| brokers.join(",")),
+        );
+        put("KAFKA_TOPIC", self.kafka.topic.clone());
+        put("KAFKA_FORMAT", self.kafka.format.clone());
+        put("AUDIT_LOG", self.audit.path.clone());
+        put(
+            "AUDIT_LOG_MAX_BYTES",
+            self.audit.max_bytes.map(|v| v.to_string()),
+        );
+        put("AUDIT_LOG_FILES", self.audit.files.map(|v| v.to_string()));
+        put("LOG_LEVEL", self.logging.level.clone());
+        put(
+            "LOG_FORMAT",
+            self.logging.format.map(|format| match format {
+                LogFormatSetting::Text => "text".to_string(),
+                LogFormatSetting::Json => "json".to_string(),
+            }),
+        );
+        put(
+            "LOG_SENSITIVE",
+            self.logging.sensitive.map(|v| v.to_string()),
+        );
+        values
+    }
+}
+
+/// Resolved view over the command line, environment and config file.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    cli: HashMap<&'static str, String>,
+    file: HashMap<&'static str, String>,
+    faults: FaultsSection,
+}
+
+impl Settings {
+    /// Environment variables only, as in the embedded simulator.
+    pub fn from_env() -> Self {
+        Settings::default()
+    }
+
+    /// Reads the process arguments and the config file they (or
+    /// CONFIG_FILE) name.
+    pub fn load() -> Result<Self, String> {
+        Settings::from_args(env::args().skip(1).collect())
+    }
+
+    /// Like `load`, for an explicit argument list (without the program
+    /// name).
+    pub fn from_args(args: Vec<String>) -> Result<Self, String> {
+        let mut cli = HashMap::new();
+        let mut config_path = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let name = match flag.as_str() {
+                "--log-sensitive" => {
+                    cli.insert("LOG_SENSITIVE", "true".to_string());
+                    continue;
+                }
+                "--config" => None,
+                "--storage" => Some("STORAGE"),
+                "--port" => Some("PORT"),
+                _ => return Err(format!("unknown argument {}", flag)),
+            };
+            let value = inline
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            match name {
+                Some(name) => {
+                    cli.insert(name, value);
+                }
+                None => config_path = Some(value),
+            }
+        }
+
+        let file = match config_path.or_else(|| env::var("CONFIG_FILE").ok()) {
+            Some(path) => ConfigFile::load(&path)?,
+            None => ConfigFile::default(),
+        };
+        Ok(Settings {
+            cli,
+            file: file.values(),
+            faults: file.faults,
+        })
+    }
+
+    /// The value of setting `name` (its environment variable name).
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.cli
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+            .or_else(|| self.file.get(name).cloned())
+    }
+
+    /// Applies the file's `[faults]` defaults where the rule set has none.
+    pub fn apply_fault_defaults(&self, rules: &mut RuleSet) {
+        if rules.endpoint_latency.authorize.is_none() {
+            rules.endpoint_latency.authorize = self.faults.authorize_latency.clone();
+        }
+        if rules.endpoint_latency.reversal.is_none() {
+            rules.endpoint_latency.reversal = self.faults.reversal_latency.clone();
+        }
+        if rules.default.fault.is_none() {
+            rules.default.fault = self.faults.default_fault.clone();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::amount::{self, Amount};
+use crate::bins::BinTable;
+use crate::codes::ProcessingCode;
+use crate::currency;
+use crate::interchange::InterchangeConfig;
+use crate::{AppState, Transaction, TransactionState};
+
+// ============================================================================
+// Settlement Summary
+// ============================================================================
+//
+// Totals for one business day (the UTC date of DE7) per acquirer and
+// currency, as a reconciliation team would compare them with the network's
+// settlement report. Every stored transaction is an approval; it counts at
+// its captured amount once captured. Debits are `approved`, and those
+// reversed since are also `reversed`; credits (refunds and payment
+// transactions, DE3 `20` / `28`) that stand are `refunded`. `net` is
+// approved minus reversed minus refunded. With an `interchange` table in
+// the rules, `interchange_fee` sums the fees of the debits that stand.
+//
+// With SETTLEMENT_CUTOVER set, business days run from cutover to cutover
+// instead (see `cutover`), and a closed day's report is the one frozen at
+// its cutover.
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct SettlementQuery {
+    /// Business day, `YYYY-MM-DD`; the open one (today, UTC, without
+    /// SETTLEMENT_CUTOVER) when absent.
+    pub date: Option<NaiveDate>,
+    pub acquirer_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Total {
+    pub count: u64,
+    /// Minor units of the currency.
+    pub amount: i64,
+}
+
+impl Total {
+    fn add(&mut self, amount: i64) {
+        self.count += 1;
+        self.amount = self.amount.saturating_add(amount);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SettlementTotals {
+    pub acquirer_id: String,
+    /// DE49, ISO 4217 numeric.
+    pub currency: String,
+    /// Alphabetic code, when the currency is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency_alpha: Option<&'static str>,
+    pub approved: Total,
+    pub reversed: Total,
+    pub refunded: Total,
+    /// Approved minus reversed minus refunded, in minor units.
+    pub net: i64,
+    /// `net` with the currency's decimals, e.g. `15.00 USD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_display: Option<String>,
+    /// Minor units; present when the rules have an interchange table.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interchange_fee: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SettlementReport {
+    #[schema(value_type = String, format = Date)]
+    pub date: NaiveDate,
+    /// Ordered by acquirer, then currency.
+    pub totals: Vec<SettlementTotals>,
+    /// When the day was closed at cutover; its totals no longer change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// Totals of `transactions` for `date`, keyed by acquirer and currency,
+/// with interchange fees when the rules have a table.
+pub fn summarize(
+    date: NaiveDate,
+    transactions: &[Transaction],
+    interchange: Option<(&InterchangeConfig, &BinTable)>,
+) -> SettlementReport {
+    let mut totals: BTreeMap<(String, String), SettlementTotals> = BTreeMap::new();
+    for transaction in transactions
+        .iter()
+        .filter(|transaction| transaction.business_day() == date)
+    {
+        let currency = transaction.request_field("de49").unwrap_or("840");
+        let entry = totals
+            .entry((transaction.acquirer_id.clone(), currency.to_string()))
+            .or_insert_with(|| SettlementTotals {
+                acquirer_id: transaction.acquirer_id.clone(),
+                currency: currency.to_string(),
+                currency_alpha: currency::lookup(currency).map(|currency| currency.alpha),
+                approved: Total::default(),
+                reversed: Total::default(),
+                refunded: Total::default(),
+                net: 0,
+                net_display: None,
+                interchange_fee: interchange.map(|_| 0),
+            });
+        let fee = interchange
+            .and_then(|(table, bins)| table.fee(transaction, bins.lookup(&transaction.pan)));
+        if let (Some(total), Some(fee)) = (&mut entry.interchange_fee, fee) {
+            *total = total.saturating_add(fee.amount);
+        }
+
+        let amount = amount::parse_minor_units(transaction.settled_amount()).unwrap_or(0);
+        let reversed = transaction.state == TransactionState::Reversed;
+        let credit = transaction
+            .request_field("de3")
+            .and_then(|code| code.parse::<ProcessingCode>().ok())
+            .is_some_and(|code| code.is_credit());
+        match (credit, reversed) {
+            (false, reversed) => {
+                entry.approved.add(amount);
+                if reversed {
+                    entry.reversed.add(amount);
+                }
+            }
+            (true, false) => entry.refunded.add(amount),
+            (true, true) => {}
+        }
+    }
+
+    let totals = totals
+        .into_values()
+        .map(|mut entry| {
+            entry.net = entry
+                .approved
+                .amount
+                .saturating_sub(entry.reversed.amount)
+                .saturating_sub(entry.refunded.amount);
+            entry.net_display = currency::lookup(&entry.currency)
+                .map(|currency| Amount::new(entry.net, currency).to_string());
+            entry
+        })
+        .collect();
+    SettlementReport {
+        date,
+        totals,
+        closed_at: None,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/settlement",
+    tag = "admin",
+    params(SettlementQuery),
+    responses((status = 200, body = SettlementReport)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn settlement_report(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SettlementQuery>,
+) -> Result<Json<SettlementReport>, (StatusCode, String)> {
+    let date = query
+        .date
+        .or_else(|| state.settlement.open_day())
+        .unwrap_or_else(|| state.clock.now().date_naive());
+    if let Some(mut report) = state.settlement.closed_report(date) {
+        if let Some(acquirer_id) = &query.acquirer_id {
+            report
+                .totals
+                .retain(|totals| &totals.acquirer_id == acquirer_id);
+        }
+        return Ok(Json(report));
+    }
+
+    let mut transactions = state
+        .authorized_transactions
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if let Some(acquirer_id) = &query.acquirer_id {
+        transactions.retain(|transaction| &transaction.acquirer_id == acquirer_id);
+    }
+    let rules = state.rules();
+    let interchange = rules.interchange.as_ref().map(|table| (table, &state.bins));
+    Ok(Json(summarize(date, &transactions, interchange)))
+}
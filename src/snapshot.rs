@@ -0,0 +1,183 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::cards::{CardDeck, RegistrySnapshot};
+use crate::error::ApiJson;
+use crate::ledger::Account;
+use crate::rules::RuleSet;
+use crate::tokens::DeviceToken;
+use crate::{AppState, Transaction};
+
+// ============================================================================
+// State Snapshots
+// ============================================================================
+//
+// A snapshot is one JSON file under SNAPSHOT_DIR holding everything a test
+// can change: stored transactions, accounts, card profiles and counters, and
+// the active rules. Script hook state is not included.
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    pub transactions: Vec<Transaction>,
+    pub deck: CardDeck,
+    pub accounts: Vec<Account>,
+    pub cards: RegistrySnapshot,
+    #[serde(default)]
+    pub tokens: Vec<DeviceToken>,
+    pub rules: RuleSet,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SnapshotRequest {
+    /// File name without extension; letters, digits, `-` and `_`.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SnapshotSummary {
+    pub name: String,
+    pub path: String,
+    pub taken_at: DateTime<Utc>,
+    pub transactions: usize,
+    pub accounts: usize,
+    pub cards: usize,
+}
+
+type SnapshotError = (StatusCode, String);
+
+fn snapshot_path(state: &AppState, name: &str) -> Result<PathBuf, SnapshotError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("invalid snapshot name {:?}", name),
+        ));
+    }
+    Ok(state.snapshot_dir.join(format!("{}.json", name)))
+}
+
+impl Snapshot {
+    pub fn capture(state: &AppState) -> Result<Self, String> {
+        let mut transactions = state.authorized_transactions.list()?;
+        transactions.sort_by_key(|transaction| transaction.recorded_at);
+        let (deck, accounts) = state.ledger.snapshot();
+        Ok(Snapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: Utc::now(),
+            transactions,
+            deck,
+            accounts,
+            cards: state.cards.snapshot(),
+            tokens: state.tokens.list(),
+            rules: (*state.rules()).clone(),
+        })
+    }
+
+    /// Replaces the running state with the snapshot's.
+    pub fn restore(self, state: &AppState) -> Result<(), String> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot version {}", self.version));
+        }
+        state.replace_rules(self.rules)?;
+        state.authorized_transactions.clear()?;
+        for transaction in self.transactions {
+            let key = transaction.key();
+            state.authorized_transactions.insert(&key, transaction)?;
+        }
+        state.ledger.restore(self.deck, self.accounts);
+        state.cards.restore(self.cards);
+        state.tokens.reload(&self.tokens);
+        Ok(())
+    }
+
+    fn summary(&self, name: String, path: &std::path::Path) -> SnapshotSummary {
+        SnapshotSummary {
+            name,
+            path: path.display().to_string(),
+            taken_at: self.taken_at,
+            transactions: self.transactions.len(),
+            accounts: self.accounts.len(),
+            cards: self.cards.profiles.len(),
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/snapshot",
+    tag = "admin",
+    request_body = SnapshotRequest,
+    responses(
+        (status = 200, body = SnapshotSummary),
+        (status = 400, description = "Invalid snapshot name", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn save_snapshot(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<SnapshotRequest>,
+) -> Result<Json<SnapshotSummary>, SnapshotError> {
+    let path = snapshot_path(&state, &body.name)?;
+    let snapshot = Snapshot::capture(&state).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let internal = |e: std::io::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    fs::create_dir_all(&state.snapshot_dir).map_err(internal)?;
+    let json = serde_json::to_vec_pretty(&snapshot)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    fs::write(&path, json).map_err(internal)?;
+
+    info!("Admin: snapshot saved to {}", path.display());
+    Ok(Json(snapshot.summary(body.name, &path)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/restore",
+    tag = "admin",
+    request_body = SnapshotRequest,
+    responses(
+        (status = 200, body = SnapshotSummary),
+        (status = 404, description = "No snapshot with this name", body = String),
+        (status = 422, description = "Snapshot file is invalid", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn restore_snapshot(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<SnapshotRequest>,
+) -> Result<Json<SnapshotSummary>, SnapshotError> {
+    let path = snapshot_path(&state, &body.name)?;
+    let contents = fs::read(&path).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("cannot read {}: {}", path.display(), e),
+        )
+    })?;
+    let snapshot: Snapshot = serde_json::from_slice(&contents).map_err(|e| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("invalid snapshot: {}", e),
+        )
+    })?;
+
+    let summary = snapshot.summary(body.name, &path);
+    snapshot
+        .restore(&state)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    info!("Admin: snapshot restored from {}", path.display());
+    Ok(Json(summary))
+}
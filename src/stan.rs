@@ -0,0 +1,40 @@
+use chrono::{Days, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+// ============================================================================
+// STAN Uniqueness
+// ============================================================================
+//
+// Switches reject an authorization whose STAN (DE11) the same acquirer
+// (DE32) already used on that business day as a duplicate transmission.
+// The business day is the UTC date of DE7. Days before yesterday are
+// dropped as new days arrive, so the registry stays small on soak tests.
+
+/// STANs seen per acquirer and business day.
+#[derive(Debug, Default)]
+pub struct StanRegistry {
+    seen: Mutex<HashMap<(String, NaiveDate), HashSet<String>>>,
+}
+
+impl StanRegistry {
+    pub fn new() -> Self {
+        StanRegistry::default()
+    }
+
+    /// Records `stan` for the acquirer on `day`; false when it was already
+    /// used.
+    pub fn record(&self, acquirer_id: &str, day: NaiveDate, stan: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        if let Some(oldest) = day.checked_sub_days(Days::new(1)) {
+            seen.retain(|(_, date), _| *date >= oldest);
+        }
+        seen.entry((acquirer_id.to_string(), day))
+            .or_default()
+            .insert(stan.to_string())
+    }
+
+    pub fn clear(&self) {
+        self.seen.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,507 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::audit::AuditLog;
+use crate::auth;
+use crate::bins::BinTable;
+use crate::cards::{CardDeck, CardRegistry};
+use crate::clock::Clock;
+use crate::cutover::{self, SettlementCalendar};
+use crate::de7::{self, ClockSkew, SkewMode};
+use crate::deferred::DeferredAuthorizations;
+use crate::dispatch::MessageHandlers;
+use crate::expiry;
+use crate::feed::EventFeed;
+use crate::incidents::Incidents;
+use crate::iso8583::Iso8583Message;
+use crate::ledger::Ledger;
+use crate::matching::MatchLog;
+use crate::monitor::Monitor;
+use crate::originals::{self, OriginalKey};
+use crate::pan::{self, BinRange};
+use crate::pipeline::Pipeline;
+use crate::profiles::{self, Profile, Profiles};
+use crate::random_decline::RandomDecline;
+use crate::rate_limit::{RateLimiter, Verdict};
+use crate::rules::RuleSet;
+use crate::scripting::ScriptHook;
+use crate::server;
+use crate::settings::Settings;
+use crate::stan::StanRegistry;
+use crate::store::{self, TransactionStore};
+use crate::tokens::TokenVault;
+use crate::validation::{self, FieldViolation, ValidationMode};
+use crate::webhooks::{Event, EventKind, Webhooks};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Everything needed to build an `AppState`. `Config::default()` is a plain
+/// in-memory simulator with the built-in rules; the binary fills it from the
+/// command line, environment and config file with `Config::from_settings`.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub rules: RuleSet,
+    /// Path of a Rhai script run on every authorization.
+    pub script_file: Option<String>,
+    /// Overrides `random_decline.seed` from the rules.
+    pub random_seed: Option<u64>,
+    pub cards_file: Option<String>,
+    /// The bundled BIN table, plus BIN_TABLE's entries.
+    pub bins: BinTable,
+    /// `memory`, `sqlite:<path>` or `redis://host:port`.
+    pub storage: String,
+    /// Bounds the memory store (LRU eviction).
+    pub max_transactions: Option<NonZeroUsize>,
+    pub transaction_ttl: Option<Duration>,
+    pub idempotent_reversals: bool,
+    /// Order in which a reversal's original is looked up.
+    pub reversal_match: Vec<OriginalKey>,
+    /// How far DE7 may be from the mock's clock.
+    pub clock_skew: Option<ClockSkew>,
+    /// Strict unless VALIDATION_MODE says lenient.
+    pub validation_mode: ValidationMode,
+    /// BINs accepted in DE2 besides the Mastercard and Maestro ranges.
+    pub test_bins: Vec<BinRange>,
+    /// Decline authorizations reusing a STAN within the acquirer's
+    /// business day with 94.
+    pub unique_stans: bool,
+    pub admin_api_keys: Vec<String>,
+    pub snapshot_dir: PathBuf,
+    pub webhook_max_attempts: Option<u32>,
+    pub audit_log: Option<PathBuf>,
+    pub audit_log_max_bytes: Option<u64>,
+    pub audit_log_files: Option<usize>,
+    pub max_body_bytes: usize,
+    /// Percent the completions of an authorization may together capture
+    /// beyond its amount.
+    pub capture_tolerance: f64,
+    /// Percent a clearing amount may exceed the authorized amount.
+    pub clearing_tolerance: f64,
+    /// UTC time of day the business day closes.
+    pub settlement_cutover: Option<NaiveTime>,
+    /// Built-in simulation profiles and those in PROFILES_DIR.
+    pub profiles: Vec<Profile>,
+    /// Profile to switch to at startup.
+    pub profile: Option<String>,
+    /// Handlers for `/iso8583`, by MTI.
+    pub handlers: MessageHandlers,
+    /// Stages run around every message.
+    pub pipeline: Pipeline,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            rules: RuleSet::builtin(),
+            script_file: None,
+            random_seed: None,
+            cards_file: None,
+            bins: BinTable::bundled(),
+            storage: "memory".to_string(),
+            max_transactions: None,
+            transaction_ttl: None,
+            idempotent_reversals: false,
+            reversal_match: originals::DEFAULT_PRECEDENCE.to_vec(),
+            clock_skew: None,
+            validation_mode: ValidationMode::Strict,
+            test_bins: Vec::new(),
+            unique_stans: false,
+            admin_api_keys: Vec::new(),
+            snapshot_dir: "snapshots".into(),
+            webhook_max_attempts: None,
+            audit_log: None,
+            audit_log_max_bytes: None,
+            audit_log_files: None,
+            max_body_bytes: server::DEFAULT_MAX_BODY_BYTES,
+            capture_tolerance: 0.0,
+            clearing_tolerance: 0.0,
+            settlement_cutover: None,
+            profiles: profiles::builtin(),
+            profile: None,
+            handlers: MessageHandlers::builtin(),
+            pipeline: Pipeline::builtin(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads RULES_FILE, SCRIPT_FILE, RANDOM_SEED, CARDS_FILE, BIN_TABLE, STORAGE,
+    /// MAX_TRANSACTIONS, TRANSACTION_TTL, REVERSAL_MODE, REVERSAL_MATCH, CLOCK_SKEW*,
+    /// TEST_BINS, UNIQUE_STANS, ADMIN_API_KEYS, SNAPSHOT_DIR, WEBHOOK_MAX_ATTEMPTS, AUDIT_LOG*,
+    /// MAX_BODY_BYTES, CAPTURE_TOLERANCE, CLEARING_AMOUNT_TOLERANCE, SETTLEMENT_CUTOVER,
+    /// PROFILES_DIR and PROFILE, panicking on invalid values.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let defaults = Config::default();
+        let var = |name| settings.var(name);
+        let mut rules = match var("RULES_FILE") {
+            Some(path) => RuleSet::load(&path).expect("Failed to load rules file"),
+            None => defaults.rules,
+        };
+        settings.apply_fault_defaults(&mut rules);
+
+        Config {
+            rules,
+            script_file: var("SCRIPT_FILE"),
+            random_seed: var("RANDOM_SEED").map(|seed| {
+                seed.parse::<u64>()
+                    .expect("RANDOM_SEED must be an unsigned integer")
+            }),
+            cards_file: var("CARDS_FILE"),
+            bins: var("BIN_TABLE")
+                .map(|path| BinTable::load(&path).expect("Failed to load BIN table"))
+                .unwrap_or(defaults.bins),
+            storage: var("STORAGE").unwrap_or(defaults.storage),
+            max_transactions: var("MAX_TRANSACTIONS").map(|max| {
+                max.parse::<NonZeroUsize>()
+                    .expect("MAX_TRANSACTIONS must be a positive integer")
+            }),
+            transaction_ttl: var("TRANSACTION_TTL")
+                .map(|ttl| expiry::parse_duration(&ttl).expect("Invalid TRANSACTION_TTL")),
+            idempotent_reversals: match var("REVERSAL_MODE").as_deref() {
+                Some("idempotent") => true,
+                Some("strict") | None => false,
+                Some(other) => panic!("REVERSAL_MODE must be strict or idempotent, got {}", other),
+            },
+            reversal_match: var("REVERSAL_MATCH").map_or(defaults.reversal_match, |keys| {
+                originals::parse_precedence(&keys).expect("Invalid REVERSAL_MATCH")
+            }),
+            clock_skew: var("CLOCK_SKEW").map(|tolerance| ClockSkew {
+                tolerance: expiry::parse_duration(&tolerance).expect("Invalid CLOCK_SKEW"),
+                mode: match var("CLOCK_SKEW_MODE").as_deref() {
+                    Some("reject") | None => SkewMode::Reject,
+                    Some("flag") => SkewMode::Flag,
+                    Some(other) => panic!("CLOCK_SKEW_MODE must be reject or flag, got {}", other),
+                },
+            }),
+            validation_mode: var("VALIDATION_MODE").map_or(defaults.validation_mode, |mode| {
+                mode.parse().expect("Invalid VALIDATION_MODE")
+            }),
+            test_bins: var("TEST_BINS")
+                .map(|bins| pan::parse_bins(&bins).expect("Invalid TEST_BINS"))
+                .unwrap_or_default(),
+            unique_stans: var("UNIQUE_STANS")
+                .is_some_and(|unique| unique.parse().expect("UNIQUE_STANS must be true or false")),
+            admin_api_keys: var("ADMIN_API_KEYS")
+                .map(|keys| auth::parse_keys(&keys))
+                .unwrap_or_default(),
+            snapshot_dir: var("SNAPSHOT_DIR").map_or(defaults.snapshot_dir, PathBuf::from),
+            webhook_max_attempts: var("WEBHOOK_MAX_ATTEMPTS").map(|attempts| {
+                attempts
+                    .parse()
+                    .expect("WEBHOOK_MAX_ATTEMPTS must be a positive integer")
+            }),
+            audit_log: var("AUDIT_LOG").map(PathBuf::from),
+            audit_log_max_bytes: var("AUDIT_LOG_MAX_BYTES").map(|bytes| {
+                bytes
+                    .parse()
+                    .expect("AUDIT_LOG_MAX_BYTES must be a positive integer")
+            }),
+            audit_log_files: var("AUDIT_LOG_FILES").map(|files| {
+                files
+                    .parse()
+                    .expect("AUDIT_LOG_FILES must be an unsigned integer")
+            }),
+            max_body_bytes: var("MAX_BODY_BYTES").map_or(defaults.max_body_bytes, |bytes| {
+                bytes
+                    .parse()
+                    .expect("MAX_BODY_BYTES must be an unsigned integer")
+            }),
+            capture_tolerance: var("CAPTURE_TOLERANCE").map_or(
+                defaults.capture_tolerance,
+                |percent| {
+                    percent
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|percent| *percent >= 0.0)
+                        .expect("CAPTURE_TOLERANCE must be a non-negative percentage")
+                },
+            ),
+            clearing_tolerance: var("CLEARING_AMOUNT_TOLERANCE").map_or(
+                defaults.clearing_tolerance,
+                |percent| {
+                    percent
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|percent| *percent >= 0.0)
+                        .expect("CLEARING_AMOUNT_TOLERANCE must be a non-negative percentage")
+                },
+            ),
+            settlement_cutover: var("SETTLEMENT_CUTOVER")
+                .map(|time| cutover::parse_time(&time).expect("Invalid SETTLEMENT_CUTOVER")),
+            profiles: profiles::load(var("PROFILES_DIR").as_deref())
+                .expect("Failed to load PROFILES_DIR"),
+            profile: var("PROFILE"),
+            handlers: defaults.handlers,
+            pipeline: defaults.pipeline,
+        }
+    }
+}
+
+// ============================================================================
+// Shared State
+// ============================================================================
+
+pub struct AppState {
+    pub authorized_transactions: Box<dyn TransactionStore>,
+    /// Swapped as a whole on snapshot restore; read through `rules()`.
+    rules: RwLock<Arc<RuleSet>>,
+    /// Held while rules are installed, so admin edits don't lose each
+    /// other's changes.
+    rules_update: Mutex<()>,
+    pub script: Option<ScriptHook>,
+    random_decline: RwLock<Option<Arc<RandomDecline>>>,
+    random_seed: Option<u64>,
+    rate_limiter: RwLock<Option<Arc<RateLimiter>>>,
+    pub ledger: Ledger,
+    pub cards: CardRegistry,
+    pub tokens: TokenVault,
+    pub cards_file: Option<String>,
+    pub bins: BinTable,
+    pub transaction_ttl: Option<Duration>,
+    /// Answer a repeated reversal carrying the same DE90 with the original
+    /// `00` instead of `94` (advice semantics).
+    pub idempotent_reversals: bool,
+    pub reversal_match: Vec<OriginalKey>,
+    pub clock_skew: Option<ClockSkew>,
+    /// The default for requests without `X-Validation-Mode`.
+    pub validation_mode: ValidationMode,
+    pub test_bins: Vec<BinRange>,
+    /// Set by UNIQUE_STANS.
+    pub stans: Option<StanRegistry>,
+    /// Keys accepted on /admin/* routes; empty leaves them open.
+    pub admin_api_keys: Vec<String>,
+    pub snapshot_dir: PathBuf,
+    pub webhooks: Webhooks,
+    pub feed: EventFeed,
+    pub monitor: Monitor,
+    pub deferred: DeferredAuthorizations,
+    pub incidents: Incidents,
+    /// Set by AUDIT_LOG.
+    pub audit: Option<AuditLog>,
+    pub max_body_bytes: usize,
+    pub capture_tolerance: f64,
+    pub clearing_matches: MatchLog,
+    pub clearing_tolerance: f64,
+    pub settlement: SettlementCalendar,
+    /// The simulator's time; see `clock`.
+    pub clock: Clock,
+    pub profiles: Profiles,
+    pub handlers: MessageHandlers,
+    pub pipeline: Pipeline,
+    pub started_at: DateTime<Utc>,
+    /// Set once the HTTP listener is bound.
+    pub listening: AtomicBool,
+}
+
+impl AppState {
+    /// Opens storage and loads the card deck, script and audit log named
+    /// in `config`, then switches to its startup profile.
+    pub fn new(config: Config) -> Result<Arc<Self>, String> {
+        let random_decline = config
+            .rules
+            .random_decline
+            .as_ref()
+            .map(|random| RandomDecline::new(random, config.random_seed))
+            .transpose()?;
+        let rate_limiter = RateLimiter::from_rules(&config.rules)?;
+
+        let deck = match &config.cards_file {
+            Some(path) => CardDeck::load(path)?,
+            None => CardDeck::empty(),
+        };
+        let profiles = Profiles::new(config.profiles, config.rules.clone());
+        let profile = config.profile;
+
+        let state = Arc::new(AppState {
+            authorized_transactions: store::open(&config.storage, config.max_transactions)?,
+            rules: RwLock::new(Arc::new(config.rules)),
+            rules_update: Mutex::new(()),
+            script: config
+                .script_file
+                .as_deref()
+                .map(ScriptHook::load)
+                .transpose()?,
+            random_decline: RwLock::new(random_decline.map(Arc::new)),
+            random_seed: config.random_seed,
+            rate_limiter: RwLock::new(rate_limiter.map(Arc::new)),
+            cards: CardRegistry::new(&deck),
+            tokens: TokenVault::new(&deck.tokens),
+            ledger: Ledger::new(deck),
+            cards_file: config.cards_file,
+            bins: config.bins,
+            transaction_ttl: config.transaction_ttl,
+            idempotent_reversals: config.idempotent_reversals,
+            reversal_match: config.reversal_match,
+            clock_skew: config.clock_skew,
+            validation_mode: config.validation_mode,
+            test_bins: config.test_bins,
+            stans: config.unique_stans.then(StanRegistry::new),
+            admin_api_keys: config.admin_api_keys,
+            snapshot_dir: config.snapshot_dir,
+            webhooks: Webhooks::new(config.webhook_max_attempts),
+            feed: EventFeed::default(),
+            monitor: Monitor::default(),
+            deferred: DeferredAuthorizations::default(),
+            incidents: Incidents::new(config.random_seed),
+            audit: config
+                .audit_log
+                .map(|path| {
+                    AuditLog::open(path, config.audit_log_max_bytes, config.audit_log_files)
+                })
+                .transpose()?,
+            max_body_bytes: config.max_body_bytes,
+            capture_tolerance: config.capture_tolerance,
+            clearing_matches: MatchLog::default(),
+            clearing_tolerance: config.clearing_tolerance,
+            settlement: SettlementCalendar::new(config.settlement_cutover, Utc::now()),
+            clock: Clock::default(),
+            profiles,
+            handlers: config.handlers,
+            pipeline: config.pipeline,
+            started_at: Utc::now(),
+            listening: AtomicBool::new(false),
+        });
+        if let Some(name) = &profile {
+            profiles::activate(&state, name)?;
+        }
+        Ok(state)
+    }
+
+    /// CARDS_FILE, read again, or an empty deck.
+    pub fn default_deck(&self) -> Result<CardDeck, String> {
+        match &self.cards_file {
+            Some(path) => CardDeck::load(path),
+            None => Ok(CardDeck::empty()),
+        }
+    }
+
+    pub fn rules(&self) -> Arc<RuleSet> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// When an authorization stored at `at` for merchant category `mcc`
+    /// stops holding funds: TRANSACTION_TTL, else the `hold_expiry` policy.
+    pub(crate) fn hold_expires_at(&self, mcc: &str, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.transaction_ttl
+            .or_else(|| self.rules().hold_expiry.period(mcc))
+            .map(|period| at + period)
+    }
+
+    /// The acquirer's `reversal_match`, or REVERSAL_MATCH.
+    pub(crate) fn reversal_match(&self, de32: &str) -> Vec<OriginalKey> {
+        self.rules()
+            .acquirers
+            .get(de32)
+            .and_then(|acquirer| acquirer.reversal_match.clone())
+            .unwrap_or_else(|| self.reversal_match.clone())
+    }
+
+    /// Sends `message`, masked, to the webhooks and the live feed.
+    pub(crate) fn publish(&self, kind: EventKind, message: &impl Serialize) {
+        self.publish_event(Event::new(kind, message));
+    }
+
+    pub(crate) fn publish_event(&self, event: Event) {
+        self.webhooks.emit(&event);
+        self.feed.publish(&event);
+    }
+
+    pub(crate) fn random_decline(&self) -> Option<Arc<RandomDecline>> {
+        self.random_decline.read().unwrap().clone()
+    }
+
+    /// Takes a rate limit token for the message.
+    pub(crate) fn throttle(&self, peer: SocketAddr, de32: &str) -> Verdict {
+        let limiter = self.rate_limiter.read().unwrap().clone();
+        let verdict = limiter.map_or(Verdict::Allow, |limiter| {
+            limiter.check(peer.ip(), de32, self.clock.now())
+        });
+        match &verdict {
+            Verdict::Allow => {}
+            Verdict::Decline(code) => info!("Rate limit exceeded, declining with {}", code),
+            Verdict::Reject(_) => info!("Rate limit exceeded, rejecting with 429"),
+        }
+        verdict
+    }
+
+    /// Field validation plus, in strict mode, the DE7 clock skew check: in
+    /// `reject` mode a skewed DE7 is a violation, in `flag` mode it is only
+    /// logged.
+    pub(crate) fn validate(
+        &self,
+        message: &Iso8583Message,
+        mode: ValidationMode,
+    ) -> Result<(), FieldViolation> {
+        let now = self.clock.now();
+        validation::validate(message, mode, now)?;
+        let (Some(skew), Some(value), ValidationMode::Strict) =
+            (self.clock_skew, message.get(7), mode)
+        else {
+            return Ok(());
+        };
+        let Some(offset) =
+            de7::parse(value.as_str(), now).and_then(|time| skew.exceeded(time, now))
+        else {
+            return Ok(());
+        };
+        let issue = format!(
+            "transmission time {} is {}s from the clock (tolerance {}s)",
+            value.as_str(),
+            offset.num_seconds(),
+            skew.tolerance.num_seconds()
+        );
+        match skew.mode {
+            SkewMode::Reject => Err(FieldViolation { number: 7, issue }),
+            SkewMode::Flag => {
+                warn!("DE7: {}", issue);
+                Ok(())
+            }
+        }
+    }
+
+    /// Installs a new rule set, rebuilding the random decline generator
+    /// (RANDOM_SEED still applies) and rate limiter from their configs.
+    pub fn replace_rules(&self, rules: RuleSet) -> Result<(), String> {
+        let _update = self.rules_update.lock().unwrap();
+        self.install_rules(rules)
+    }
+
+    /// Applies `change` to a copy of the active rules and installs it, with
+    /// no other replacement or update in between. The outer `Err` is a rule
+    /// set that can't be installed; nothing is installed when `change`
+    /// fails.
+    pub fn update_rules<T, E>(
+        &self,
+        change: impl FnOnce(&mut RuleSet) -> Result<T, E>,
+    ) -> Result<Result<T, E>, String> {
+        let _update = self.rules_update.lock().unwrap();
+        let mut rules = self.rules().as_ref().clone();
+        let changed = match change(&mut rules) {
+            Ok(changed) => changed,
+            Err(e) => return Ok(Err(e)),
+        };
+        self.install_rules(rules)?;
+        Ok(Ok(changed))
+    }
+
+    fn install_rules(&self, rules: RuleSet) -> Result<(), String> {
+        let random_decline = rules
+            .random_decline
+            .as_ref()
+            .map(|config| RandomDecline::new(config, self.random_seed))
+            .transpose()?;
+        let rate_limiter = RateLimiter::from_rules(&rules)?;
+        *self.random_decline.write().unwrap() = random_decline.map(Arc::new);
+        *self.rate_limiter.write().unwrap() = rate_limiter.map(Arc::new);
+        *self.rules.write().unwrap() = Arc::new(rules);
+        Ok(())
+    }
+}
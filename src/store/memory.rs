@@ -0,0 +1,328 @@
+use lru::LruCache;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+use super::{StoreStats, TransactionStore};
+use crate::Transaction;
+
+/// Upper bound on shards; each is an independent lock.
+const MAX_SHARDS: usize = 64;
+
+/// A stored transaction, and whether it was read since it last moved to
+/// the front of its shard.
+struct Entry {
+    transaction: Arc<Transaction>,
+    read: AtomicBool,
+}
+
+impl Entry {
+    fn new(transaction: Transaction) -> Self {
+        Entry {
+            transaction: Arc::new(transaction),
+            read: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Keys of a shard's transactions by some other value of theirs.
+#[derive(Default)]
+struct Index(HashMap<String, Vec<String>>);
+
+impl Index {
+    fn add(&mut self, value: Option<&str>, key: &str) {
+        if let Some(value) = value {
+            self.0
+                .entry(value.to_string())
+                .or_default()
+                .push(key.to_string());
+        }
+    }
+
+    fn remove(&mut self, value: Option<&str>, key: &str) {
+        let Some(value) = value else {
+            return;
+        };
+        if let Some(keys) = self.0.get_mut(value) {
+            keys.retain(|indexed| indexed != key);
+            if keys.is_empty() {
+                self.0.remove(value);
+            }
+        }
+    }
+
+    fn keys(&self, value: &str) -> &[String] {
+        self.0.get(value).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// A shard's transactions, most recently used first, indexed by RRN,
+/// approval code and stored-credential trace ID.
+struct Shard {
+    entries: LruCache<String, Entry>,
+    by_rrn: Index,
+    by_approval_code: Index,
+    by_trace_id: Index,
+}
+
+impl Shard {
+    fn new() -> Self {
+        Shard {
+            entries: LruCache::unbounded(),
+            by_rrn: Index::default(),
+            by_approval_code: Index::default(),
+            by_trace_id: Index::default(),
+        }
+    }
+
+    /// Stores `transaction` at the front; whether it replaced one.
+    fn put(&mut self, key: &str, transaction: Transaction) -> bool {
+        if let Some(old) = self.entries.peek(key) {
+            let old = Arc::clone(&old.transaction);
+            self.unindex(key, &old);
+        }
+        self.by_rrn.add(Some(&transaction.rrn), key);
+        self.by_approval_code.add(transaction.approval_code(), key);
+        self.by_trace_id.add(transaction.trace_id.as_deref(), key);
+        self.entries
+            .put(key.to_string(), Entry::new(transaction))
+            .is_some()
+    }
+
+    fn pop(&mut self, key: &str) -> bool {
+        let Some(entry) = self.entries.pop(key) else {
+            return false;
+        };
+        self.unindex(key, &entry.transaction);
+        true
+    }
+
+    fn unindex(&mut self, key: &str, transaction: &Transaction) {
+        self.by_rrn.remove(Some(&transaction.rrn), key);
+        self.by_approval_code
+            .remove(transaction.approval_code(), key);
+        self.by_trace_id
+            .remove(transaction.trace_id.as_deref(), key);
+    }
+}
+
+/// In-process store, split into shards by key hash so concurrent
+/// authorizations only contend when their keys land on the same shard.
+/// Lookups take a shard's read lock just long enough to mark the entry read
+/// and clone an `Arc`, so reversal and completion lookups never wait on each
+/// other. With a capacity, a transaction is evicted once the store as a
+/// whole holds `capacity`: the least recently used one of the shard the new
+/// transaction lands in, or of the next shard holding any other. Writes move
+/// a transaction to the front of its shard, and reads are caught up on at
+/// eviction time, when a transaction read since it last moved goes back to
+/// the front instead. Lookups by RRN, approval code or trace ID ask each
+/// shard's index rather than scanning the transactions.
+pub struct MemoryStore {
+    shards: Vec<RwLock<Shard>>,
+    /// Transactions in all shards; only changed under a shard's write lock.
+    entries: AtomicUsize,
+    capacity: Option<NonZeroUsize>,
+    evictions: AtomicU64,
+}
+
+impl MemoryStore {
+    pub fn new(capacity: Option<NonZeroUsize>) -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let shard_count = (cores * 4).next_power_of_two().min(MAX_SHARDS);
+        MemoryStore {
+            shards: (0..shard_count)
+                .map(|_| RwLock::new(Shard::new()))
+                .collect(),
+            entries: AtomicUsize::new(0),
+            capacity,
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn shard_index(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.shards.len()
+    }
+
+    fn shard(&self, key: &str) -> &RwLock<Shard> {
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// The transactions `index` lists under `value`, across all shards.
+    fn find_indexed(&self, index: fn(&Shard) -> &Index, value: &str) -> Vec<Transaction> {
+        let found: Vec<_> = self
+            .shards
+            .iter()
+            .flat_map(|shard| {
+                let shard = shard.read().unwrap();
+                index(&shard)
+                    .keys(value)
+                    .iter()
+                    .filter_map(|key| shard.entries.peek(key))
+                    .map(|entry| {
+                        entry.read.store(true, Ordering::Relaxed);
+                        Arc::clone(&entry.transaction)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        found
+            .into_iter()
+            .map(|transaction| (*transaction).clone())
+            .collect()
+    }
+}
+
+/// Removes the shard's least recently used transaction other than `keep`,
+/// moving those read since they last moved to the front on the way.
+fn evict_one(shard: &mut Shard, keep: &str) -> Option<String> {
+    // Each entry moves at most twice: once if it was read, once more if
+    // `keep` went round ahead of it.
+    for _ in 0..=2 * shard.entries.len() {
+        let (key, entry) = shard.entries.peek_lru()?;
+        let key = key.clone();
+        if key == keep {
+            if shard.entries.len() == 1 {
+                return None;
+            }
+        } else if !entry.read.swap(false, Ordering::Relaxed) {
+            shard.pop(&key);
+            return Some(key);
+        }
+        shard.entries.promote(&key);
+    }
+    None
+}
+
+impl TransactionStore for MemoryStore {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn insert(&self, key: &str, transaction: Transaction) -> Result<(), String> {
+        let index = self.shard_index(key);
+        let entries = {
+            let mut shard = self.shards[index].write().unwrap();
+            if shard.put(key, transaction) {
+                return Ok(());
+            }
+            self.entries.fetch_add(1, Ordering::Relaxed) + 1
+        };
+        if self
+            .capacity
+            .is_none_or(|capacity| entries <= capacity.get())
+        {
+            return Ok(());
+        }
+        // One shard lock at a time, so concurrent evictions can't deadlock.
+        for offset in 0..self.shards.len() {
+            let mut shard = self.shards[(index + offset) % self.shards.len()]
+                .write()
+                .unwrap();
+            if let Some(evicted) = evict_one(&mut shard, key) {
+                self.entries.fetch_sub(1, Ordering::Relaxed);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+                info!("Store full, evicted transaction {}", evicted);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Transaction>, String> {
+        // `peek` leaves the order alone, so a read lock is enough: the read
+        // is only marked, and eviction catches up on it. The transaction
+        // itself is cloned after the lock is released.
+        let found = self
+            .shard(key)
+            .read()
+            .unwrap()
+            .entries
+            .peek(key)
+            .map(|entry| {
+                entry.read.store(true, Ordering::Relaxed);
+                Arc::clone(&entry.transaction)
+            });
+        Ok(found.map(|transaction| (*transaction).clone()))
+    }
+
+    fn update(
+        &self,
+        key: &str,
+        change: &mut dyn FnMut(&mut Transaction) -> bool,
+    ) -> Result<bool, String> {
+        let mut shard = self.shard(key).write().unwrap();
+        let Some(entry) = shard.entries.peek(key) else {
+            return Ok(false);
+        };
+        let mut transaction = (*entry.transaction).clone();
+        if change(&mut transaction) {
+            shard.put(key, transaction);
+        }
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<Transaction>, String> {
+        Ok(self
+            .shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .entries
+                    .iter()
+                    .map(|(_, entry)| Arc::clone(&entry.transaction))
+                    .collect::<Vec<_>>()
+            })
+            .map(|transaction| (*transaction).clone())
+            .collect())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        let mut shard = self.shard(key).write().unwrap();
+        if shard.pop(key) {
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        for shard in &self.shards {
+            let mut shard = shard.write().unwrap();
+            self.entries
+                .fetch_sub(shard.entries.len(), Ordering::Relaxed);
+            *shard = Shard::new();
+        }
+        Ok(())
+    }
+
+    fn find_by_rrn(&self, rrn: &str) -> Result<Option<Transaction>, String> {
+        Ok(self
+            .find_indexed(|shard| &shard.by_rrn, rrn)
+            .into_iter()
+            .next())
+    }
+
+    fn find_by_approval_code(&self, code: &str) -> Result<Vec<Transaction>, String> {
+        Ok(self.find_indexed(|shard| &shard.by_approval_code, code))
+    }
+
+    fn find_by_trace_id(&self, trace_id: &str) -> Result<Vec<Transaction>, String> {
+        Ok(self.find_indexed(|shard| &shard.by_trace_id, trace_id))
+    }
+
+    fn stats(&self) -> StoreStats {
+        StoreStats {
+            backend: self.backend(),
+            entries: Some(self.entries.load(Ordering::Relaxed)),
+            capacity: self.capacity.map(NonZeroUsize::get),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::num::NonZeroUsize;
+#[cfg(any(feature = "sqlite", feature = "redis"))]
+use tokio::runtime::{Handle, RuntimeFlavor};
+use utoipa::ToSchema;
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod transaction;
+
+pub use memory::MemoryStore;
+#[cfg(feature = "redis")]
+pub use redis::RedisStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+pub use transaction::{generate_rrn, transaction_key, Capture, Transaction, TransactionState};
+
+// ============================================================================
+// Transaction Store
+// ============================================================================
+//
+// Selected with `--storage <spec>` (or the STORAGE env var):
+//
+//   memory            in-process map, lost on restart (default)
+//   sqlite:<path>     SQLite database file, survives restarts
+//   redis://host:port shared between mock instances behind a load balancer
+
+/// Authorized transactions, keyed by `Transaction::key()` (DE32:STAN).
+pub trait TransactionStore: Send + Sync {
+    fn backend(&self) -> &'static str;
+
+    fn insert(&self, key: &str, transaction: Transaction) -> Result<(), String>;
+
+    fn get(&self, key: &str) -> Result<Option<Transaction>, String>;
+
+    /// Replaces the transaction under `key` with what `change` makes of a
+    /// copy of it, with no other write to it in between; `change` returns
+    /// false to leave it as it was. Backends that retry on conflict may run
+    /// `change` more than once. Whether there was a transaction under `key`.
+    fn update(
+        &self,
+        key: &str,
+        change: &mut dyn FnMut(&mut Transaction) -> bool,
+    ) -> Result<bool, String>;
+
+    fn list(&self) -> Result<Vec<Transaction>, String>;
+
+    fn remove(&self, key: &str) -> Result<(), String>;
+
+    fn clear(&self) -> Result<(), String>;
+
+    fn find_by_rrn(&self, rrn: &str) -> Result<Option<Transaction>, String> {
+        Ok(self.list()?.into_iter().find(|t| t.rrn == rrn))
+    }
+
+    /// Transactions whose authorization returned `code` in DE38.
+    fn find_by_approval_code(&self, code: &str) -> Result<Vec<Transaction>, String> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|t| t.approval_code() == Some(code))
+            .collect())
+    }
+
+    /// Transactions carrying stored-credential trace ID `trace_id`.
+    fn find_by_trace_id(&self, trace_id: &str) -> Result<Vec<Transaction>, String> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|t| t.trace_id.as_deref() == Some(trace_id))
+            .collect())
+    }
+
+    /// Cheap round trip to the backend, for readiness probes.
+    fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn stats(&self) -> StoreStats {
+        StoreStats {
+            backend: self.backend(),
+            entries: None,
+            capacity: None,
+            evictions: 0,
+        }
+    }
+}
+
+/// What `transition` did with a transaction.
+#[derive(Debug)]
+pub enum Transition<T, E> {
+    NotFound,
+    /// Its state can't move to the one asked for; carries it as stored.
+    Refused(Box<Transaction>),
+    /// `change` turned it down.
+    Declined(E),
+    Moved(T),
+}
+
+impl dyn TransactionStore + '_ {
+    /// Moves the transaction under `key` to `next`, making `change` to it
+    /// on the way, if its state at `now` can move there. Nothing is written
+    /// unless both the state machine and `change` allow it.
+    pub fn transition<T, E>(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        next: TransactionState,
+        mut change: impl FnMut(&mut Transaction) -> Result<T, E>,
+    ) -> Result<Transition<T, E>, String> {
+        let mut outcome = Transition::NotFound;
+        self.update(key, &mut |transaction| {
+            if !transaction.effective_state(now).can_transition_to(next) {
+                outcome = Transition::Refused(Box::new(transaction.clone()));
+                return false;
+            }
+            match change(transaction) {
+                Ok(moved) => {
+                    transaction.state = next;
+                    outcome = Transition::Moved(moved);
+                    true
+                }
+                Err(declined) => {
+                    outcome = Transition::Declined(declined);
+                    false
+                }
+            }
+        })?;
+        Ok(outcome)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoreStats {
+    pub backend: &'static str,
+    /// Not reported by backends where counting is expensive.
+    pub entries: Option<usize>,
+    pub capacity: Option<usize>,
+    pub evictions: u64,
+}
+
+/// Runs a call into a blocking backend from async handlers without holding
+/// up the other tasks of the worker it runs on; outside a multi-thread
+/// runtime it just runs.
+#[cfg(any(feature = "sqlite", feature = "redis"))]
+fn blocking<T>(call: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(call)
+        }
+        _ => call(),
+    }
+}
+
+/// `capacity` bounds the memory backend (LRU eviction); other backends
+/// are unbounded.
+pub fn open(
+    spec: &str,
+    capacity: Option<NonZeroUsize>,
+) -> Result<Box<dyn TransactionStore>, String> {
+    if spec == "memory" {
+        return Ok(Box::new(MemoryStore::new(capacity)));
+    }
+
+    if let Some(path) = spec.strip_prefix("sqlite:") {
+        #[cfg(feature = "sqlite")]
+        return Ok(Box::new(SqliteStore::open(path)?));
+        #[cfg(not(feature = "sqlite"))]
+        return Err(format!(
+            "cannot open {}: built without the `sqlite` feature",
+            path
+        ));
+    }
+
+    if spec.starts_with("redis://") || spec.starts_with("rediss://") {
+        #[cfg(feature = "redis")]
+        return Ok(Box::new(RedisStore::open(spec)?));
+        #[cfg(not(feature = "redis"))]
+        return Err(format!(
+            "cannot open {}: built without the `redis` feature",
+            spec
+        ));
+    }
+
+    Err(format!("unknown storage backend: {}", spec))
+}
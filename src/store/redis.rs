@@ -0,0 +1,137 @@
+use ::redis::{Client, Commands, Connection, ErrorKind, RedisError, RedisResult};
+use std::sync::Mutex;
+use tracing::warn;
+
+use super::TransactionStore;
+use crate::Transaction;
+
+const KEY_PREFIX: &str = "iso8583:txn:";
+
+/// Transactions are stored as JSON strings under `iso8583:txn:<key>`.
+pub struct RedisStore {
+    client: Client,
+    connection: Mutex<Connection>,
+}
+
+impl RedisStore {
+    pub fn open(url: &str) -> Result<Self, String> {
+        let client = Client::open(url).map_err(|e| format!("invalid redis url {}: {}", url, e))?;
+        let connection = client
+            .get_connection()
+            .map_err(|e| format!("cannot connect to {}: {}", url, e))?;
+        Ok(RedisStore {
+            client,
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Runs `call` on the connection, reconnecting and running it once more
+    /// if the connection was lost (a Redis restart, an idle timeout).
+    fn with_connection<T>(
+        &self,
+        mut call: impl FnMut(&mut Connection) -> RedisResult<T>,
+    ) -> Result<T, String> {
+        super::blocking(|| {
+            let mut connection = self.connection.lock().unwrap();
+            match call(&mut connection) {
+                Err(e) if e.is_io_error() || e.is_connection_dropped() => {
+                    warn!("Redis connection lost ({}), reconnecting", e);
+                    *connection = self.client.get_connection().map_err(|e| e.to_string())?;
+                    call(&mut connection)
+                }
+                result => result,
+            }
+            .map_err(|e| e.to_string())
+        })
+    }
+}
+
+fn invalid(e: serde_json::Error) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, "invalid transaction", e.to_string()))
+}
+
+fn keys(connection: &mut Connection) -> RedisResult<Vec<String>> {
+    Ok(connection
+        .scan_match::<_, String>(format!("{}*", KEY_PREFIX))?
+        .collect())
+}
+
+impl TransactionStore for RedisStore {
+    fn backend(&self) -> &'static str {
+        "redis"
+    }
+
+    fn ping(&self) -> Result<(), String> {
+        self.with_connection(|connection| ::redis::cmd("PING").query::<String>(connection))
+            .map(|_| ())
+    }
+
+    fn insert(&self, key: &str, transaction: Transaction) -> Result<(), String> {
+        let data = serde_json::to_string(&transaction).map_err(|e| e.to_string())?;
+        let key = format!("{}{}", KEY_PREFIX, key);
+        self.with_connection(|connection| connection.set::<_, _, ()>(&key, &data))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Transaction>, String> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        let data: Option<String> = self.with_connection(|connection| connection.get(&key))?;
+        data.map(|d| serde_json::from_str(&d).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    fn update(
+        &self,
+        key: &str,
+        change: &mut dyn FnMut(&mut Transaction) -> bool,
+    ) -> Result<bool, String> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        self.with_connection(|connection| {
+            // EXEC fails if another instance wrote the key since WATCH, and
+            // `transaction` then starts over.
+            ::redis::transaction(connection, &[&key], |connection, pipe| {
+                let Some(data) = connection.get::<_, Option<String>>(&key)? else {
+                    return Ok(Some(false));
+                };
+                let mut transaction: Transaction = serde_json::from_str(&data).map_err(invalid)?;
+                if !change(&mut transaction) {
+                    return Ok(Some(true));
+                }
+                let data = serde_json::to_string(&transaction).map_err(invalid)?;
+                pipe.set(&key, data)
+                    .ignore()
+                    .query::<Option<()>>(connection)
+                    .map(|written| written.map(|()| true))
+            })
+        })
+    }
+
+    fn list(&self) -> Result<Vec<Transaction>, String> {
+        let values: Vec<Option<String>> = self.with_connection(|connection| {
+            let keys = keys(connection)?;
+            if keys.is_empty() {
+                return Ok(Vec::new());
+            }
+            connection.mget(&keys)
+        })?;
+        values
+            .into_iter()
+            .flatten()
+            .map(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.with_connection(|connection| {
+            let keys = keys(connection)?;
+            if keys.is_empty() {
+                return Ok(());
+            }
+            connection.del::<_, ()>(keys)
+        })
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        self.with_connection(|connection| connection.del::<_, ()>(&key))
+    }
+}
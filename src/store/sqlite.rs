@@ -0,0 +1,135 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+
+use super::TransactionStore;
+use crate::Transaction;
+
+/// Transactions are stored as JSON so new fields need no migration.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let connection =
+            Connection::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        connection
+            // `stan` predates per-acquirer keys; it holds `Transaction::key()`.
+            .execute(
+                "CREATE TABLE IF NOT EXISTS transactions (
+                    stan TEXT PRIMARY KEY,
+                    data TEXT NOT NULL
+                )",
+                [],
+            )
+            .map_err(|e| format!("cannot create schema: {}", e))?;
+        Ok(SqliteStore {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Runs `call` with the connection; waiting for the lock and the query
+    /// both block.
+    fn with_connection<T>(
+        &self,
+        call: impl FnOnce(&Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        super::blocking(|| call(&self.connection.lock().unwrap()))
+    }
+}
+
+fn read(connection: &Connection, key: &str) -> Result<Option<Transaction>, String> {
+    let data: Option<String> = connection
+        .query_row(
+            "SELECT data FROM transactions WHERE stan = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    data.map(|d| serde_json::from_str(&d).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+fn write(connection: &Connection, key: &str, transaction: &Transaction) -> Result<(), String> {
+    let data = serde_json::to_string(transaction).map_err(|e| e.to_string())?;
+    connection
+        .execute(
+            "INSERT OR REPLACE INTO transactions (stan, data) VALUES (?1, ?2)",
+            params![key, data],
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+impl TransactionStore for SqliteStore {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn insert(&self, key: &str, transaction: Transaction) -> Result<(), String> {
+        self.with_connection(|connection| write(connection, key, &transaction))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Transaction>, String> {
+        self.with_connection(|connection| read(connection, key))
+    }
+
+    fn update(
+        &self,
+        key: &str,
+        change: &mut dyn FnMut(&mut Transaction) -> bool,
+    ) -> Result<bool, String> {
+        self.with_connection(|connection| {
+            let Some(mut transaction) = read(connection, key)? else {
+                return Ok(false);
+            };
+            if change(&mut transaction) {
+                write(connection, key, &transaction)?;
+            }
+            Ok(true)
+        })
+    }
+
+    fn list(&self) -> Result<Vec<Transaction>, String> {
+        self.with_connection(|connection| {
+            let mut statement = connection
+                .prepare("SELECT data FROM transactions")
+                .map_err(|e| e.to_string())?;
+            let rows = statement
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(|e| e.to_string())?;
+            rows.map(|data| {
+                let data = data.map_err(|e| e.to_string())?;
+                serde_json::from_str(&data).map_err(|e| e.to_string())
+            })
+            .collect()
+        })
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.with_connection(|connection| {
+            connection
+                .execute("DELETE FROM transactions", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn ping(&self) -> Result<(), String> {
+        self.with_connection(|connection| {
+            connection
+                .query_row("SELECT 1", [], |_| Ok(()))
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.with_connection(|connection| {
+            connection
+                .execute("DELETE FROM transactions WHERE stan = ?1", [key])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::amount;
+use crate::codes::ResponseCode;
+use crate::installments::InstallmentTerms;
+use crate::processing;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// Stored Transactions
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionState {
+    #[default]
+    Authorized,
+    Captured,
+    Reversed,
+    Expired,
+}
+
+impl TransactionState {
+    /// Reversed and expired are terminal; a captured transaction can still be
+    /// captured again (partial captures) or reversed.
+    pub fn can_transition_to(self, next: TransactionState) -> bool {
+        use TransactionState::*;
+        matches!(
+            (self, next),
+            (Authorized, Captured)
+                | (Authorized, Reversed)
+                | (Authorized, Expired)
+                | (Captured, Captured)
+                | (Captured, Reversed)
+        )
+    }
+}
+
+/// One 0220 completion captured against an authorization.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Capture {
+    /// DE4 of the completion, minor units.
+    pub amount: String,
+    /// DE7 of the completion as sent.
+    pub de7: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Transaction {
+    pub pan: String,
+    pub amount: String,
+    pub stan: String,
+    /// DE7 as sent.
+    pub timestamp: String,
+    /// DE7 read as a UTC time (see `de7::parse`); the date the
+    /// transaction is counted under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transmitted_at: Option<DateTime<Utc>>,
+    pub response_code: ResponseCode,
+    /// When the mock stored the transaction (DE7 is client-supplied).
+    #[serde(default = "Utc::now")]
+    pub recorded_at: DateTime<Utc>,
+    /// DE37 retrieval reference number, generated when the acquirer sent none.
+    #[serde(default)]
+    pub rrn: String,
+    /// Original messages with DE2 masked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+    #[serde(default)]
+    pub state: TransactionState,
+    /// Total captured, 12 digits like DE4.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captured_amount: Option<String>,
+    /// Each completion captured against the authorization, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub captures: Vec<Capture>,
+    /// Total of the refunds that named this transaction as their original,
+    /// 12 digits like DE4.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refunded_amount: Option<String>,
+    /// Key (DE32:STAN) of the transaction a refund named as its original.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_of: Option<String>,
+    /// DE90 of the reversal that moved this transaction to `Reversed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reversal_de90: Option<String>,
+    /// DE32 of the original authorization.
+    #[serde(default)]
+    pub acquirer_id: String,
+    /// DE41 and DE42 of the authorization, trailing spaces removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_acceptor_id: Option<String>,
+    /// Caller's correlation ID (header or DE48 SE63) on the authorization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Masked MDES token the authorization arrived with; `pan` is the
+    /// funding PAN.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Installment plan and terms of an approved installment purchase.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installments: Option<InstallmentTerms>,
+    /// Trace ID returned in DE63, which MITs on this card can refer to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Business day open when the transaction was authorized, with
+    /// SETTLEMENT_CUTOVER set (DE15 of the response).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = Date)]
+    pub settlement_date: Option<NaiveDate>,
+    /// When an uncaptured authorization's hold lapses (see `expiry`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Transaction {
+    pub fn key(&self) -> String {
+        transaction_key(&self.acquirer_id, &self.stan)
+    }
+
+    /// A non-empty field of the stored (masked) request, e.g. `de49`.
+    pub fn request_field(&self, key: &str) -> Option<&str> {
+        self.request
+            .as_ref()?
+            .get(key)?
+            .as_str()
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The settlement date it was authorized under; otherwise the UTC date
+    /// of DE7, or of when the mock stored the transaction when DE7 could not
+    /// be read.
+    pub fn business_day(&self) -> NaiveDate {
+        self.settlement_date
+            .unwrap_or_else(|| self.transmitted_at.unwrap_or(self.recorded_at).date_naive())
+    }
+
+    /// DE38 of the stored response, if the authorization returned one.
+    pub fn approval_code(&self) -> Option<&str> {
+        self.response.as_ref()?.get("de38")?.as_str()
+    }
+
+    /// A 0200 financial transaction, posted when approved and never
+    /// cleared.
+    pub fn is_single_message(&self) -> bool {
+        self.request_field("mti") == Some(processing::FINANCIAL_REQUEST)
+    }
+
+    /// Minor units captured so far.
+    pub fn captured_total(&self) -> i64 {
+        self.captured_amount
+            .as_deref()
+            .and_then(amount::parse_minor_units)
+            .unwrap_or(0)
+    }
+
+    /// The captured amount once captured, otherwise the approved one.
+    pub fn settled_amount(&self) -> &str {
+        self.captured_amount.as_deref().unwrap_or(&self.amount)
+    }
+}
+
+/// The stored (masked) request's fields.
+impl FieldSource for Transaction {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.request_field(name)
+    }
+}
+
+/// Store and ledger-hold key: the STAN scoped to the acquirer (DE32), so
+/// teams sharing one mock cannot collide on STANs.
+pub fn transaction_key(acquirer_id: &str, stan: &str) -> String {
+    format!("{}:{}", acquirer_id, stan)
+}
+
+/// YDDDhh of `now` + STAN, the conventional RRN layout.
+pub fn generate_rrn(stan: &str, now: DateTime<Utc>) -> String {
+    let now = now.format("%y%j%H").to_string();
+    format!("{}{:0>6.6}", &now[1..], stan)
+}
@@ -0,0 +1,78 @@
+use crate::de48::De48;
+use crate::rules::FieldSource;
+use crate::Transaction;
+
+// ============================================================================
+// Stored Credentials (CIT / MIT)
+// ============================================================================
+//
+// A merchant that keeps a card on file for recurring or unscheduled charges
+// sends the first charge as a cardholder-initiated transaction (CIT) and
+// the later ones as merchant-initiated transactions (MIT), POS entry mode
+// `10` (credential on file) in DE22. Each MIT carries, in DE48 subelement
+// 63, the trace ID of the CIT that set the credential up.
+//
+// Every approved authorization gets a trace ID, returned in DE63 and stored
+// with the transaction: the financial network code `MCC`, a six-character
+// Banknet reference (the STAN) and the DE7 date (MMDD). Mastercard pads
+// trace IDs to 15 with two spaces; the padding is optional here.
+//
+// An MIT without a well-formed trace ID is a format error (DE44 `048`); one
+// whose trace ID does not belong to an approved CIT of the same card is
+// declined with 12.
+
+pub const TRACE_ID_SUBELEMENT: &str = "63";
+pub const NETWORK_CODE: &str = "MCC";
+pub const NO_PRIOR_CIT_MESSAGE: &str = "No Prior Cardholder-Initiated Transaction";
+
+/// POS entry mode `10`: credential on file.
+pub fn is_merchant_initiated(message: &impl FieldSource) -> bool {
+    message.field("de22").and_then(|de22| de22.get(0..2)) == Some("10")
+}
+
+/// The trace ID of an authorization with DE11 `stan` sent at DE7 `de7`.
+pub fn trace_id(stan: &str, de7: &str) -> String {
+    format!(
+        "{}{:0>6.6}{}",
+        NETWORK_CODE,
+        stan,
+        de7.get(0..4).unwrap_or("0000")
+    )
+}
+
+fn well_formed(trace_id: &str) -> bool {
+    let (network, rest) = match (trace_id.get(..3), trace_id.get(3..)) {
+        (Some(network), Some(rest)) => (network, rest),
+        _ => return false,
+    };
+    let date = rest.get(6..).unwrap_or_default();
+    let month: u32 = date.get(..2).and_then(|m| m.parse().ok()).unwrap_or(0);
+    let day: u32 = date.get(2..).and_then(|d| d.parse().ok()).unwrap_or(0);
+    trace_id.len() == 13
+        && network.chars().all(|c| c.is_ascii_alphanumeric())
+        && rest[..6].chars().all(|c| c.is_ascii_alphanumeric())
+        && date.chars().all(|c| c.is_ascii_digit())
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+}
+
+/// The initial trace ID an MIT's DE48 refers to; `None` when it is missing
+/// or malformed.
+pub fn initial_trace_id(de48: &str) -> Option<String> {
+    let de48 = De48::parse(de48).ok()?;
+    let trace_id = de48.get(TRACE_ID_SUBELEMENT)?.trim_end_matches(' ');
+    well_formed(trace_id).then(|| trace_id.to_string())
+}
+
+/// The CIT of card `pan` with `trace_id`, among `transactions`.
+pub fn find_initial<'a>(
+    transactions: &'a [Transaction],
+    pan: &str,
+    trace_id: &str,
+) -> Option<&'a Transaction> {
+    transactions.iter().find(|transaction| {
+        transaction.trace_id.as_deref() == Some(trace_id)
+            && transaction.pan == pan
+            && !is_merchant_initiated(*transaction)
+    })
+}
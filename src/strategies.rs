@@ -0,0 +1,337 @@
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence, Index};
+use proptest::string::string_regex;
+
+use crate::card_generator::luhn_check_digit;
+use crate::codes::{AccountType, ProcessingCode, TransactionType};
+use crate::currency::CURRENCIES;
+use crate::iso8583::{self, FieldDefinition, FieldFormat, FieldLength, Iso8583Message};
+use crate::mcc::MCCS;
+use crate::AuthorizationRequest;
+
+// ============================================================================
+// Property-Based Generators
+// ============================================================================
+//
+// proptest strategies for ISO 8583 messages, for fuzz-style testing of the
+// mock and of clients that talk to it. Valid values satisfy the registry
+// (`FieldDefinition::check`), pass `validation::validate` (coded DEs come
+// from their catalogs) and pack with `wire::pack`; variable lengths
+// favour the boundaries (empty and maximum). Invalid values break exactly
+// one rule: one character over the maximum length, one character outside
+// the field's format, or an odd number of hex digits for binary fields.
+
+/// MTIs the generic message strategy picks from.
+const MTIS: &[&str] = &[
+    "0100", "0110", "0120", "0200", "0220", "0230", "0400", "0410", "0420", "0800", "0810",
+];
+
+/// Character class of a format as a regex fragment; binary fields are
+/// generated from bytes instead.
+fn char_class(format: FieldFormat) -> &'static str {
+    match format {
+        FieldFormat::Numeric => "[0-9]",
+        FieldFormat::Alphanumeric => "[A-Za-z0-9]",
+        FieldFormat::AlphanumericSpecial => "[ -~]",
+        FieldFormat::Track2 => "[0-9=D]",
+        FieldFormat::Binary => "[0-9A-F]",
+    }
+}
+
+/// Characters each format rejects.
+fn foreign_chars(format: FieldFormat) -> &'static [char] {
+    match format {
+        FieldFormat::Numeric => &['A', ' ', '-', '.', 'é'],
+        FieldFormat::Alphanumeric => &[' ', '-', '*', '\n', 'é'],
+        FieldFormat::AlphanumericSpecial => &['\0', '\n', '\t', '\u{7f}', 'é'],
+        FieldFormat::Track2 => &['A', 'd', '^', ' ', 'é'],
+        FieldFormat::Binary => &['G', 'g', ' ', '-', 'é'],
+    }
+}
+
+fn hex(bytes: Vec<u8>) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Exactly `length` characters of `format` (bytes for binary fields, as
+/// hex).
+fn text(format: FieldFormat, length: usize) -> BoxedStrategy<String> {
+    match format {
+        FieldFormat::Binary => proptest::collection::vec(any::<u8>(), length)
+            .prop_map(hex)
+            .boxed(),
+        _ => string_regex(&format!("{}{{{}}}", char_class(format), length))
+            .expect("character class regex")
+            .boxed(),
+    }
+}
+
+/// A valid length: exact for fixed fields, the boundaries or anything in
+/// between for variable ones.
+fn valid_length(length: FieldLength) -> BoxedStrategy<usize> {
+    match length {
+        FieldLength::Fixed(length) => Just(length).boxed(),
+        FieldLength::Llvar(max) | FieldLength::Lllvar(max) => {
+            prop_oneof![1 => Just(0), 1 => Just(max), 3 => 0..=max].boxed()
+        }
+    }
+}
+
+/// Values of the DEs `validation::validate` checks beyond their format:
+/// DE3 from the processing code catalog, DE7 a real MMDDhhmmss (never
+/// 29 February, which is invalid in most years), DE18 a known MCC and
+/// DE49-DE51 known currencies.
+fn coded_value(number: u8) -> Option<BoxedStrategy<String>> {
+    let strategy = match number {
+        3 => (
+            select(TransactionType::ALL),
+            select(AccountType::ALL),
+            select(AccountType::ALL),
+        )
+            .prop_map(|(transaction_type, from_account, to_account)| {
+                ProcessingCode {
+                    transaction_type,
+                    from_account,
+                    to_account,
+                }
+                .to_string()
+            })
+            .boxed(),
+        7 => (1u32..=12, 1u32..=28, 0u32..24, 0u32..60, 0u32..60)
+            .prop_map(|(month, day, hour, minute, second)| {
+                format!(
+                    "{:02}{:02}{:02}{:02}{:02}",
+                    month, day, hour, minute, second
+                )
+            })
+            .boxed(),
+        18 => select(MCCS.iter().map(|mcc| mcc.code).collect::<Vec<_>>())
+            .prop_map(str::to_string)
+            .boxed(),
+        49..=51 => select(
+            CURRENCIES
+                .iter()
+                .map(|currency| currency.numeric)
+                .collect::<Vec<_>>(),
+        )
+        .prop_map(str::to_string)
+        .boxed(),
+        _ => return None,
+    };
+    Some(strategy)
+}
+
+/// Any value the registry and validation accept for `definition`.
+pub fn field_value(definition: &'static FieldDefinition) -> BoxedStrategy<String> {
+    if let Some(coded) = coded_value(definition.number) {
+        return coded;
+    }
+    valid_length(definition.length)
+        .prop_flat_map(move |length| text(definition.format, length))
+        .boxed()
+}
+
+/// A value for `definition` that breaks exactly one of its rules.
+pub fn invalid_field_value(definition: &'static FieldDefinition) -> BoxedStrategy<String> {
+    let format = definition.format;
+    let max = definition.length.max();
+    let too_long = text(format, max + 1);
+    let foreign = (valid_length(definition.length).prop_map(|length| length.max(1)))
+        .prop_flat_map(move |length| text(format, length))
+        .prop_flat_map(move |value| (Just(value), any::<Index>(), select(foreign_chars(format))))
+        .prop_map(|(value, index, foreign)| {
+            let mut chars: Vec<char> = value.chars().collect();
+            let position = index.index(chars.len());
+            chars[position] = foreign;
+            chars.into_iter().collect()
+        });
+    match format {
+        FieldFormat::Binary => {
+            let odd = (0..max).prop_flat_map(move |bytes| {
+                text(format, bytes).prop_map(|value| format!("{}0", value))
+            });
+            prop_oneof![too_long, foreign, odd].boxed()
+        }
+        _ => prop_oneof![too_long, foreign].boxed(),
+    }
+}
+
+/// A message with a common MTI and any subset of registered DEs, each with
+/// a valid value.
+pub fn message() -> BoxedStrategy<Iso8583Message> {
+    let definitions: Vec<&'static FieldDefinition> = iso8583::fields().iter().collect();
+    let count = definitions.len();
+    (select(MTIS), subsequence(definitions, 0..=count))
+        .prop_flat_map(|(mti, definitions)| {
+            let values: Vec<_> = definitions
+                .into_iter()
+                .map(|definition| field_value(definition).prop_map(move |v| (definition.number, v)))
+                .collect();
+            (Just(mti), values)
+        })
+        .prop_map(|(mti, values)| {
+            let mut message = Iso8583Message::new(mti);
+            for (number, value) in values {
+                message
+                    .set(number, value)
+                    .expect("registry fields are settable");
+            }
+            message
+        })
+        .boxed()
+}
+
+/// A valid message with one DE replaced by an invalid value, and that DE's
+/// number.
+pub fn invalid_message() -> BoxedStrategy<(Iso8583Message, u8)> {
+    let definitions: Vec<&'static FieldDefinition> = iso8583::fields().iter().collect();
+    (message(), select(definitions))
+        .prop_flat_map(|(message, definition)| {
+            (
+                Just(message),
+                invalid_field_value(definition),
+                Just(definition.number),
+            )
+        })
+        .prop_map(|(mut message, value, number)| {
+            message
+                .set(number, value)
+                .expect("registry fields are settable");
+            (message, number)
+        })
+        .boxed()
+}
+
+/// A Luhn-valid PAN of 13 to 19 digits with a Mastercard 51-55 BIN.
+pub fn pan() -> BoxedStrategy<String> {
+    (12usize..=18)
+        .prop_flat_map(|length| string_regex(&format!("5[1-5][0-9]{{{}}}", length - 2)).unwrap())
+        .prop_map(|partial| format!("{}{}", partial, luhn_check_digit(&partial)))
+        .boxed()
+}
+
+/// A valid 0100 built with `AuthorizationRequest::builder`: random PAN,
+/// amount, STAN, MCC, acquirer and currency, other fields defaulted.
+pub fn authorization_request() -> BoxedStrategy<AuthorizationRequest> {
+    (
+        pan(),
+        string_regex("[0-9]{12}").unwrap(),
+        (1u32..=999_999).prop_map(|stan| format!("{:06}", stan)),
+        select(MCCS.iter().map(|mcc| mcc.code).collect::<Vec<_>>()),
+        string_regex("[0-9]{1,11}").unwrap(),
+        select(&["840", "978", "826", "392", "124", "036"][..]),
+    )
+        .prop_map(|(pan, amount, stan, mcc, acquirer_id, currency)| {
+            AuthorizationRequest::builder()
+                .pan(pan)
+                .amount(amount)
+                .stan(stan)
+                .merchant_type(mcc)
+                .acquirer_id(acquirer_id)
+                .currency(currency)
+                .build()
+                .expect("generated fields are valid")
+        })
+        .boxed()
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+
+    use super::*;
+    use crate::clearing::{self, FileId};
+    use crate::validation::{self, ValidationMode};
+    use crate::{wire, Transaction};
+
+    /// A captured 0100 as the clearing file reads it.
+    fn captured_transaction() -> impl Strategy<Value = Transaction> {
+        (
+            pan(),
+            string_regex("[0-9]{12}").unwrap(),
+            string_regex("[0-9]{6}").unwrap(),
+            string_regex("[0-9]{1,11}").unwrap(),
+            coded_value(18).unwrap(),
+            coded_value(49).unwrap(),
+            string_regex("[0-9]{12}").unwrap(),
+            string_regex("[A-Z0-9]{6}").unwrap(),
+            (
+                string_regex("[A-Z0-9]{8}").unwrap(),
+                string_regex("[A-Z0-9]{15}").unwrap(),
+            ),
+        )
+            .prop_map(
+                |(
+                    pan,
+                    amount,
+                    stan,
+                    acquirer_id,
+                    mcc,
+                    currency,
+                    rrn,
+                    approval,
+                    (terminal, acceptor),
+                )| {
+                    serde_json::from_value(json!({
+                        "pan": pan,
+                        "amount": amount,
+                        "stan": stan,
+                        "timestamp": "1016120000",
+                        "response_code": "00",
+                        "rrn": rrn,
+                        "state": "captured",
+                        "captured_amount": amount,
+                        "acquirer_id": acquirer_id,
+                        "request": {
+                            "mti": "0100",
+                            "de3": "000000",
+                            "de18": mcc,
+                            "de22": "051",
+                            "de41": terminal,
+                            "de42": acceptor,
+                            "de49": currency,
+                        },
+                        "response": { "de38": approval },
+                    }))
+                    .expect("transaction JSON")
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn valid_messages_pass_validation(message in message()) {
+            prop_assert_eq!(
+                validation::validate(&message, ValidationMode::Strict, Utc::now()),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn invalid_messages_fail_on_the_broken_de((message, number) in invalid_message()) {
+            let violation = validation::validate(&message, ValidationMode::Strict, Utc::now())
+                .expect_err("invalid DE accepted");
+            prop_assert_eq!(violation.number, number);
+        }
+
+        #[test]
+        fn wire_round_trip(message in message()) {
+            let packed = wire::pack(&message).unwrap();
+            prop_assert_eq!(wire::unpack(&packed), Ok(message));
+        }
+
+        #[test]
+        fn ipm_round_trip(
+            transactions in proptest::collection::vec(captured_transaction(), 0..8),
+            blocked in any::<bool>(),
+            mask in any::<bool>(),
+        ) {
+            let file_id = FileId::next("123456", Utc::now());
+            let messages = clearing::clearing_file(&file_id, &transactions, mask);
+            prop_assert_eq!(messages.len(), transactions.len() + 2);
+            let file = clearing::encode_file(&messages, blocked).unwrap();
+            prop_assert_eq!(clearing::decode_file(&file), Ok(messages));
+        }
+    }
+}
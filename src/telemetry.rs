@@ -0,0 +1,70 @@
+use axum::{extract::Request, http::HeaderMap, middleware::Next, response::Response};
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::env;
+use tracing::{info_span, Instrument, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+// ============================================================================
+// OpenTelemetry Export (`otel` feature)
+// ============================================================================
+//
+// With OTEL_EXPORTER_OTLP_ENDPOINT set (e.g. `http://localhost:4318`), spans
+// are exported over OTLP/HTTP. Incoming W3C `traceparent` headers become the
+// parent of the request span, so the mock's handler and phase spans appear
+// inside the caller's trace.
+
+const DEFAULT_SERVICE_NAME: &str = "mastercard-api";
+
+/// The OTLP layer, when an endpoint is configured.
+pub fn layer<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .expect("Failed to build OTLP exporter");
+    let service_name =
+        env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    let tracer = provider.tracer(DEFAULT_SERVICE_NAME);
+    opentelemetry::global::set_tracer_provider(provider);
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Wraps each request in a span parented to the caller's trace context.
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+    let span = info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+    let _ = span.set_parent(parent);
+    next.run(request).instrument(span).await
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cards::CardStatus;
+use crate::codes::ResponseCode;
+use crate::de48::De48;
+use crate::tokens::TOKEN_SUBELEMENT;
+
+// ============================================================================
+// Tokenization Authorization Requests (TAR)
+// ============================================================================
+//
+// Before a wallet provisions a token, the network asks the issuer whether
+// the card is eligible. The request is a 0100 for the card's own PAN whose
+// DE48 SE33 carries subfield 08 `TA`, and optionally the wallet's device
+// score (1, riskiest, to 5) in subfield 09. The answer is the provisioning
+// decision in DE39:
+//
+//   00  approve (green path)
+//   85  approve with additional cardholder authentication (yellow path)
+//   05  decline (red path)
+//
+// Cards that are not active are declined. The `tokenization` rules section
+// sets the score thresholds. A TAR moves no funds and is not stored.
+
+/// SE33 subfields.
+const MESSAGE_TYPE: &str = "08";
+const DEVICE_SCORE: &str = "09";
+
+const TOKENIZATION_AUTHORIZATION: &str = "TA";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approve,
+    StepUp,
+    Decline,
+}
+
+impl Decision {
+    pub fn response_code(self) -> ResponseCode {
+        match self {
+            Decision::Approve => ResponseCode::Approved,
+            Decision::StepUp => ResponseCode::NotDeclined,
+            Decision::Decline => ResponseCode::DoNotHonor,
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Decision::Approve => "Tokenization Approved",
+            Decision::StepUp => "Tokenization Requires Authentication",
+            Decision::Decline => "Tokenization Declined",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizationConfig {
+    /// Device scores at or below this are declined.
+    pub decline_at_or_below: Option<u8>,
+    /// Device scores at or below this (and above the decline threshold)
+    /// take the yellow path.
+    pub step_up_at_or_below: Option<u8>,
+    /// Decision when the wallet sends no device score.
+    #[serde(default = "default_unscored")]
+    pub unscored: Decision,
+}
+
+fn default_unscored() -> Decision {
+    Decision::Approve
+}
+
+impl Default for TokenizationConfig {
+    fn default() -> Self {
+        TokenizationConfig {
+            decline_at_or_below: None,
+            step_up_at_or_below: None,
+            unscored: default_unscored(),
+        }
+    }
+}
+
+/// A tokenization authorization request found in DE48.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenizationRequest {
+    /// `None` when absent or not a digit.
+    pub device_score: Option<u8>,
+}
+
+impl TokenizationRequest {
+    /// `None` for an ordinary authorization.
+    pub fn parse(de48: &str) -> Option<Self> {
+        let se33 = De48::parse(de48).ok()?.get(TOKEN_SUBELEMENT)?.to_string();
+        let subfields = De48::parse(&se33).ok()?;
+        (subfields.get(MESSAGE_TYPE) == Some(TOKENIZATION_AUTHORIZATION)).then(|| {
+            TokenizationRequest {
+                device_score: subfields
+                    .get(DEVICE_SCORE)
+                    .and_then(|score| score.parse().ok()),
+            }
+        })
+    }
+
+    pub fn decide(&self, status: CardStatus, config: &TokenizationConfig) -> Decision {
+        if status != CardStatus::Active {
+            return Decision::Decline;
+        }
+        let Some(score) = self.device_score else {
+            return config.unscored;
+        };
+        let at_or_below = |threshold: Option<u8>| threshold.is_some_and(|t| score <= t);
+        if at_or_below(config.decline_at_or_below) {
+            Decision::Decline
+        } else if at_or_below(config.step_up_at_or_below) {
+            Decision::StepUp
+        } else {
+            Decision::Approve
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::card_generator::luhn_check_digit;
+use crate::de48::{self, De48};
+use crate::dsrp::{self, CryptogramResult};
+use crate::error::ApiJson;
+use crate::masking;
+use crate::messages::AuthorizationResponse;
+use crate::pan;
+use crate::rules::FieldSource;
+use crate::AppState;
+
+// ============================================================================
+// MDES Device Tokens
+// ============================================================================
+//
+// Wallet transactions carry a device token in DE2: a Luhn-valid surrogate
+// from a token BIN range, bound to one funding PAN and one token requestor
+// (the wallet). The acquirer names the requestor in DE48 SE33 subfield 06.
+// The mock de-tokenizes before any card check, so balances, profiles and
+// rules all apply to the funding PAN, and the 0110 carries the token again
+// in DE2 with SE33 holding the account number indicator, token assurance
+// level and requestor ID.
+//
+// A token the vault does not know (with SE33 present), or one presented by
+// another requestor, is declined with 14; a suspended token with 62.
+
+pub const TOKEN_SUBELEMENT: &str = "33";
+
+/// SE33 subfields.
+const ACCOUNT_NUMBER_INDICATOR: &str = "01";
+const ASSURANCE_LEVEL: &str = "05";
+const TOKEN_REQUESTOR_ID: &str = "06";
+
+/// `C`: the account number in DE2 is a Mastercard token.
+const TOKEN_INDICATOR: &str = "C";
+
+/// BIN of tokens minted by the vault.
+const TOKEN_BIN: &str = "520473";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStatus {
+    #[default]
+    Active,
+    /// DE39=62
+    Suspended,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeviceToken {
+    pub token: String,
+    /// Funding PAN the token stands for.
+    pub pan: String,
+    /// 11-digit token requestor ID of the wallet.
+    pub token_requestor_id: String,
+    /// Two-digit token assurance level returned in SE33 subfield 05.
+    #[serde(default = "default_assurance_level")]
+    pub assurance_level: String,
+    #[serde(default)]
+    pub status: TokenStatus,
+    /// The only DSRP cryptogram (DE48 SE43) accepted for this token; any
+    /// well-formed one when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dsrp_cryptogram: Option<String>,
+}
+
+fn default_assurance_level() -> String {
+    "00".to_string()
+}
+
+impl DeviceToken {
+    /// Checks the token's format; the funding PAN is checked on use.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        pan::check(&self.token, &[]).map_err(|e| format!("token {}: {}", self.token, e))?;
+        if self.token_requestor_id.len() != 11
+            || !self.token_requestor_id.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(format!(
+                "token requestor ID {:?} is not 11 digits",
+                self.token_requestor_id
+            ));
+        }
+        if self.assurance_level.len() != 2
+            || !self.assurance_level.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(format!(
+                "assurance level {:?} is not two digits",
+                self.assurance_level
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A random Luhn-valid token in the vault's token BIN.
+pub fn generate_token(rng: &mut impl Rng) -> String {
+    let mut partial = TOKEN_BIN.to_string();
+    while partial.len() < 15 {
+        partial.push(char::from(b'0' + rng.gen_range(0..10u8)));
+    }
+    let check = luhn_check_digit(&partial);
+    format!("{}{}", partial, check)
+}
+
+/// Why a tokenized authorization could not be de-tokenized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenIssue {
+    /// DE39=14
+    Unknown,
+    /// DE39=14
+    RequestorMismatch,
+    /// DE39=62
+    Suspended,
+}
+
+/// The token behind a de-tokenized authorization, for the response.
+#[derive(Debug, Clone)]
+pub struct Detokenized {
+    pub token: String,
+    pub pan: String,
+    pub token_requestor_id: String,
+    pub assurance_level: String,
+    pub dsrp_cryptogram: Option<String>,
+    /// Set once a DSRP cryptogram has been validated.
+    pub dsrp: Option<CryptogramResult>,
+}
+
+impl Detokenized {
+    /// Validates the DSRP cryptogram against the token, if the message
+    /// carries one.
+    pub fn validate_dsrp(&mut self, message: &impl FieldSource) -> Option<CryptogramResult> {
+        let cryptogram = dsrp::cryptogram(message)?;
+        let result = dsrp::validate(&cryptogram, self.dsrp_cryptogram.as_deref());
+        self.dsrp = Some(result);
+        Some(result)
+    }
+
+    /// Puts the token back in DE2 and the token data in DE48 SE33, with the
+    /// DSRP result in SE71.
+    pub fn restore(&self, response: &mut AuthorizationResponse) {
+        response.de2 = self.token.clone();
+        let mut parsed = De48::parse(&response.de48).unwrap_or_default();
+        if let Some(result) = self.dsrp {
+            parsed.set(dsrp::ON_BEHALF_SUBELEMENT, &result.on_behalf());
+        }
+        parsed.set(
+            TOKEN_SUBELEMENT,
+            &de48::encode_subfields(&[
+                (ACCOUNT_NUMBER_INDICATOR, TOKEN_INDICATOR),
+                (ASSURANCE_LEVEL, &self.assurance_level),
+                (TOKEN_REQUESTOR_ID, &self.token_requestor_id),
+            ]),
+        );
+        response.de48 = parsed.encode();
+    }
+}
+
+/// Subfield `id` of an SE33 value; subfields share the subelement layout.
+fn subfield(se33: &str, id: &str) -> Option<String> {
+    De48::parse(se33).ok()?.get(id).map(str::to_string)
+}
+
+/// Token to funding PAN mappings, loaded from the card deck and changed
+/// through the admin API.
+#[derive(Debug, Default)]
+pub struct TokenVault {
+    tokens: Mutex<HashMap<String, DeviceToken>>,
+}
+
+impl TokenVault {
+    pub fn new(tokens: &[DeviceToken]) -> Self {
+        let vault = TokenVault::default();
+        vault.reload(tokens);
+        vault
+    }
+
+    /// Replaces every mapping, dropping runtime changes.
+    pub fn reload(&self, tokens: &[DeviceToken]) {
+        *self.tokens.lock().unwrap() = tokens
+            .iter()
+            .map(|token| (token.token.clone(), token.clone()))
+            .collect();
+    }
+
+    pub fn upsert(&self, token: DeviceToken) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(token.token.clone(), token);
+    }
+
+    pub fn remove(&self, token: &str) -> Option<DeviceToken> {
+        self.tokens.lock().unwrap().remove(token)
+    }
+
+    pub fn list(&self) -> Vec<DeviceToken> {
+        let mut tokens: Vec<_> = self.tokens.lock().unwrap().values().cloned().collect();
+        tokens.sort_by(|a, b| a.token.cmp(&b.token));
+        tokens
+    }
+
+    /// Resolves the token in `de2`. `Ok(None)` is a plain PAN: not in the
+    /// vault and no SE33 in `de48`.
+    pub fn detokenize(&self, de2: &str, de48: &str) -> Result<Option<Detokenized>, TokenIssue> {
+        let se33 = De48::parse(de48)
+            .ok()
+            .and_then(|parsed| parsed.get(TOKEN_SUBELEMENT).map(str::to_string));
+        let Some(token) = self.tokens.lock().unwrap().get(de2).cloned() else {
+            return match se33 {
+                Some(_) => Err(TokenIssue::Unknown),
+                None => Ok(None),
+            };
+        };
+        let requestor = se33
+            .as_deref()
+            .and_then(|se33| subfield(se33, TOKEN_REQUESTOR_ID));
+        if requestor.is_some_and(|requestor| requestor != token.token_requestor_id) {
+            return Err(TokenIssue::RequestorMismatch);
+        }
+        if token.status == TokenStatus::Suspended {
+            return Err(TokenIssue::Suspended);
+        }
+        Ok(Some(Detokenized {
+            token: token.token,
+            pan: token.pan,
+            token_requestor_id: token.token_requestor_id,
+            assurance_level: token.assurance_level,
+            dsrp_cryptogram: token.dsrp_cryptogram,
+            dsrp: None,
+        }))
+    }
+}
+
+// ============================================================================
+// Admin Endpoints
+// ============================================================================
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ProvisionToken {
+    /// Minted in the vault's token BIN when absent.
+    pub token: Option<String>,
+    pub pan: String,
+    pub token_requestor_id: String,
+    #[serde(default = "default_assurance_level")]
+    pub assurance_level: String,
+    #[serde(default)]
+    pub status: TokenStatus,
+    pub dsrp_cryptogram: Option<String>,
+}
+
+fn masked(mut token: DeviceToken) -> DeviceToken {
+    token.pan = masking::mask_pan(&token.pan);
+    token
+}
+
+/// Lists the vault with funding PANs masked.
+#[utoipa::path(
+    get,
+    path = "/admin/tokens",
+    tag = "admin",
+    responses((status = 200, body = [DeviceToken])),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_tokens(State(state): State<Arc<AppState>>) -> Json<Vec<DeviceToken>> {
+    Json(state.tokens.list().into_iter().map(masked).collect())
+}
+
+/// Adds or replaces a token mapping.
+#[utoipa::path(
+    post,
+    path = "/admin/tokens",
+    tag = "admin",
+    request_body = ProvisionToken,
+    responses(
+        (status = 200, body = DeviceToken),
+        (status = 422, description = "Invalid token, PAN or requestor ID", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn provision_token(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<ProvisionToken>,
+) -> Result<Json<DeviceToken>, (StatusCode, String)> {
+    let token = DeviceToken {
+        token: body
+            .token
+            .unwrap_or_else(|| generate_token(&mut rand::thread_rng())),
+        pan: body.pan,
+        token_requestor_id: body.token_requestor_id,
+        assurance_level: body.assurance_level,
+        status: body.status,
+        dsrp_cryptogram: body.dsrp_cryptogram,
+    };
+    token
+        .check()
+        .and_then(|()| pan::check(&token.pan, &state.test_bins))
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    info!(
+        "Admin: token {} provisioned for {}",
+        masking::pan_for_log(&token.token),
+        masking::pan_for_log(&token.pan)
+    );
+    state.tokens.upsert(token.clone());
+    Ok(Json(masked(token)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/tokens/{token}",
+    tag = "admin",
+    params(("token" = String, Path)),
+    responses(
+        (status = 200, body = DeviceToken),
+        (status = 404, description = "Token not in the vault", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn delete_token(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<DeviceToken>, (StatusCode, String)> {
+    let removed = state
+        .tokens
+        .remove(&token)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "token not in the vault".to_string()))?;
+    info!("Admin: token {} deleted", masking::pan_for_log(&token));
+    Ok(Json(masked(removed)))
+}
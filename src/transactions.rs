@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::masking;
+use crate::{AppState, ResponseCode, Transaction, TransactionState};
+
+// ============================================================================
+// Transaction Query API
+// ============================================================================
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct TransactionFilter {
+    pub pan_suffix: Option<String>,
+    pub acquirer_id: Option<String>,
+    pub stan: Option<String>,
+    pub response_code: Option<ResponseCode>,
+    pub state: Option<TransactionState>,
+    pub correlation_id: Option<String>,
+    /// DE41, without trailing spaces.
+    pub terminal_id: Option<String>,
+    /// DE42, without trailing spaces.
+    pub card_acceptor_id: Option<String>,
+    /// Inclusive lower bound on the time the mock recorded the transaction.
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TransactionPage {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub transactions: Vec<Transaction>,
+}
+
+impl TransactionFilter {
+    fn matches(&self, transaction: &Transaction) -> bool {
+        self.pan_suffix
+            .as_deref()
+            .is_none_or(|suffix| transaction.pan.ends_with(suffix))
+            && self
+                .acquirer_id
+                .as_deref()
+                .is_none_or(|id| transaction.acquirer_id == id)
+            && self
+                .stan
+                .as_deref()
+                .is_none_or(|stan| transaction.stan == stan)
+            && self
+                .response_code
+                .is_none_or(|code| transaction.response_code == code)
+            && self.state.is_none_or(|state| transaction.state == state)
+            && self
+                .correlation_id
+                .as_deref()
+                .is_none_or(|id| transaction.correlation_id.as_deref() == Some(id))
+            && self
+                .terminal_id
+                .as_deref()
+                .is_none_or(|id| transaction.terminal_id.as_deref() == Some(id))
+            && self
+                .card_acceptor_id
+                .as_deref()
+                .is_none_or(|id| transaction.card_acceptor_id.as_deref() == Some(id))
+            && self.from.is_none_or(|from| transaction.recorded_at >= from)
+            && self.to.is_none_or(|to| transaction.recorded_at < to)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/transactions",
+    tag = "transactions",
+    params(TransactionFilter),
+    responses((status = 200, description = "Matching transactions, oldest first, PAN masked", body = TransactionPage))
+)]
+pub async fn list_transactions(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<TransactionFilter>,
+) -> Result<Json<TransactionPage>, (StatusCode, String)> {
+    let mut transactions = state
+        .authorized_transactions
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    transactions.retain(|transaction| filter.matches(transaction));
+    transactions.sort_by_key(|transaction| transaction.recorded_at);
+
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let total = transactions.len();
+    let page = transactions
+        .into_iter()
+        .skip(filter.offset)
+        .take(limit)
+        .map(|mut transaction| {
+            transaction.pan = masking::mask_pan(&transaction.pan);
+            transaction
+        })
+        .collect();
+
+    Ok(Json(TransactionPage {
+        total,
+        offset: filter.offset,
+        limit,
+        transactions: page,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/transactions/{rrn}",
+    tag = "transactions",
+    params(("rrn" = String, Path, description = "DE37 retrieval reference number")),
+    responses(
+        (status = 200, body = Transaction),
+        (status = 404, description = "No transaction with this RRN", body = String),
+    )
+)]
+pub async fn get_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(rrn): Path<String>,
+) -> Result<Json<Transaction>, (StatusCode, String)> {
+    match state.authorized_transactions.find_by_rrn(&rrn) {
+        Ok(Some(mut transaction)) => {
+            transaction.pan = masking::mask_pan(&transaction.pan);
+            Ok(Json(transaction))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("no transaction with RRN {}", rrn),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
@@ -0,0 +1,62 @@
+use crate::codes::ResponseCode;
+use crate::de48::De48;
+use crate::rules::FieldSource;
+
+// ============================================================================
+// UCAF / 3-D Secure Authentication Data
+// ============================================================================
+//
+// DE48 SE42 carries the Security Level Indicator (subfield 01, three digits:
+// security protocol, cardholder authentication, UCAF collection indicator).
+// DE48 SE43 carries the UCAF/AAV data itself.
+
+pub const SLI_SUBELEMENT: &str = "42";
+pub const UCAF_SUBELEMENT: &str = "43";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Authentication {
+    /// UCAF collection indicator 0: merchant does not support UCAF.
+    None,
+    /// Indicator 1: attempted, no AAV issued by the ACS.
+    Attempted,
+    /// Indicator 2 (or 7 for DSRP/MIT): fully authenticated.
+    Authenticated,
+}
+
+/// E-commerce if DE22 starts with 81 or DE61 subfield 10 (CAT level) is 6.
+pub fn is_ecommerce(message: &impl FieldSource) -> bool {
+    let pan_entry = message.field("de22").and_then(|d| d.get(0..2));
+    let cat_level = message.field("de61").and_then(|d| d.get(9..10));
+    pan_entry == Some("81") || cat_level == Some("6")
+}
+
+/// Validates SE42/SE43 and returns the authentication level, or the DE39 to
+/// decline with when the data is malformed.
+pub fn validate(de48: &De48) -> Result<Authentication, ResponseCode> {
+    let sli = match de48.get(SLI_SUBELEMENT) {
+        Some(sli) => sli,
+        None => return Ok(Authentication::None),
+    };
+
+    let indicator = sli
+        .strip_prefix("0103")
+        .filter(|value| value.len() == 3 && value.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|value| value.chars().nth(2))
+        .ok_or(ResponseCode::FormatError)?;
+
+    let authentication = match indicator {
+        '0' => Authentication::None,
+        '1' => Authentication::Attempted,
+        '2' | '7' => Authentication::Authenticated,
+        _ => return Err(ResponseCode::FormatError),
+    };
+
+    let has_ucaf = de48
+        .get(UCAF_SUBELEMENT)
+        .is_some_and(|ucaf| !ucaf.is_empty());
+    if authentication == Authentication::Authenticated && !has_ucaf {
+        return Err(ResponseCode::FormatError);
+    }
+
+    Ok(authentication)
+}
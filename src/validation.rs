@@ -0,0 +1,160 @@
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::codes::ProcessingCode;
+use crate::currency;
+use crate::de7;
+use crate::iso8583::{self, Iso8583Message};
+use crate::mcc;
+
+// ============================================================================
+// Field Validation
+// ============================================================================
+//
+// Mandatory DEs per MTI, then every DE against the registry and, for coded
+// DEs, their catalogs; the first failure is answered with DE39 30 and the DE
+// in DE44. Lenient mode only logs what the processing doesn't interpret.
+
+/// Per-request override of VALIDATION_MODE.
+pub const MODE_HEADER: &str = "x-validation-mode";
+
+/// DEs whose values the processing interprets, checked in either mode.
+const INTERPRETED: [u8; 5] = [3, 7, 49, 50, 51];
+
+pub const FORMAT_ERROR_MESSAGE: &str = "Format Error";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+impl FromStr for ValidationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict" => Ok(ValidationMode::Strict),
+            "lenient" => Ok(ValidationMode::Lenient),
+            other => Err(format!("expected strict or lenient, got {:?}", other)),
+        }
+    }
+}
+
+impl ValidationMode {
+    /// The mode the request's `X-Validation-Mode` asks for, else `default`.
+    pub fn requested(headers: &HeaderMap, default: Self) -> Self {
+        let Some(value) = headers.get(MODE_HEADER) else {
+            return default;
+        };
+        match value
+            .to_str()
+            .map_err(|e| e.to_string())
+            .and_then(str::parse)
+        {
+            Ok(mode) => mode,
+            Err(e) => {
+                warn!("Ignoring {}: {}", MODE_HEADER, e);
+                default
+            }
+        }
+    }
+}
+
+/// The first data element of a message that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldViolation {
+    pub number: u8,
+    pub issue: String,
+}
+
+impl FieldViolation {
+    /// The DE44 value that reports this violation, e.g. `004`.
+    pub fn de44(&self) -> String {
+        format!("{:03}", self.number)
+    }
+}
+
+impl fmt::Display for FieldViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DE{}: {}", self.number, self.issue)
+    }
+}
+
+/// The DEs a message of type `mti` must carry unless the rules file says
+/// otherwise; none for MTIs the mock doesn't process itself.
+pub fn default_mandatory_fields(mti: &str) -> &'static [u8] {
+    match mti {
+        "0100" | "0200" => &[2, 3, 4, 7, 11, 18, 32, 48, 49, 61],
+        "0220" => &[2, 3, 4, 7, 11, 32, 49],
+        "0400" => &[2, 3, 4, 7, 11, 18, 22, 32, 39, 48, 49, 61, 90],
+        _ => &[],
+    }
+}
+
+/// Checks that `message` carries each of `mandatory`, in that order.
+pub fn check_mandatory(message: &Iso8583Message, mandatory: &[u8]) -> Result<(), FieldViolation> {
+    match mandatory
+        .iter()
+        .find(|&&number| message.get(number).is_none())
+    {
+        Some(&number) => Err(FieldViolation {
+            number,
+            issue: format!("mandatory for MTI {} but missing", message.mti),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks the values of a coded field beyond its format; DE7 is read in
+/// the year nearest `now`.
+fn check_value(number: u8, value: &str, now: DateTime<Utc>) -> Result<(), String> {
+    match number {
+        7 if de7::parse(value, now).is_none() => Err(format!(
+            "transmission time {:?} is not a valid MMDDhhmmss",
+            value
+        )),
+        11 if value.len() != 6 => Err(format!("STAN {:?} is not exactly six digits", value)),
+        3 => value.parse::<ProcessingCode>().map(drop),
+        18 if mcc::lookup(value).is_none() => {
+            Err(format!("unknown merchant category code {:?}", value))
+        }
+        49..=51 if currency::lookup(value).is_none() => {
+            Err(format!("unknown currency code {:?}", value))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks every data element of `message`, in DE order, against the
+/// simulator's time `now`; in lenient mode only the interpreted ones fail
+/// it.
+pub fn validate(
+    message: &Iso8583Message,
+    mode: ValidationMode,
+    now: DateTime<Utc>,
+) -> Result<(), FieldViolation> {
+    for (number, value) in message.fields.iter() {
+        let Some(definition) = iso8583::field(number) else {
+            continue;
+        };
+        let checked = definition
+            .check(value.as_str())
+            .and_then(|()| definition.check_full_length(value.as_str()))
+            .and_then(|()| check_value(number, value.as_str(), now));
+        match checked {
+            Err(issue) if mode == ValidationMode::Lenient && !INTERPRETED.contains(&number) => {
+                warn!("Lenient validation, ignoring DE{}: {}", number, issue);
+            }
+            Err(issue) => return Err(FieldViolation { number, issue }),
+            Ok(()) => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::ApiJson;
+use crate::masking;
+use crate::AppState;
+
+// ============================================================================
+// Webhook Notifications
+// ============================================================================
+//
+// Each event is POSTed as JSON to every webhook subscribed to it. Deliveries
+// run in the background and are retried with exponential backoff (1s, 2s,
+// 4s, ...) until a 2xx or `max_attempts`. With a secret, the body is signed:
+//
+//   X-Webhook-Signature: sha256=<hex HMAC-SHA256 of the body>
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum EventKind {
+    #[serde(rename = "authorization.approved")]
+    AuthorizationApproved,
+    #[serde(rename = "authorization.declined")]
+    AuthorizationDeclined,
+    #[serde(rename = "reversal.processed")]
+    ReversalProcessed,
+    /// A 0220 completion advice was processed.
+    #[serde(rename = "advice.processed")]
+    AdviceProcessed,
+    /// The business day was closed at SETTLEMENT_CUTOVER.
+    #[serde(rename = "settlement.cutover")]
+    SettlementCutover,
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::AuthorizationApproved => "authorization.approved",
+            EventKind::AuthorizationDeclined => "authorization.declined",
+            EventKind::ReversalProcessed => "reversal.processed",
+            EventKind::AdviceProcessed => "advice.processed",
+            EventKind::SettlementCutover => "settlement.cutover",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    /// Events to deliver; all when empty.
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    #[serde(default, skip_serializing)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub id: Uuid,
+    pub event: EventKind,
+    pub occurred_at: DateTime<Utc>,
+    /// The response message, with DE2 masked.
+    pub data: serde_json::Value,
+    /// The reference a deferred authorization was accepted with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+impl Event {
+    /// An event carrying `message` with DE2 masked.
+    pub fn new(kind: EventKind, message: &impl Serialize) -> Self {
+        Event {
+            id: Uuid::new_v4(),
+            event: kind,
+            occurred_at: Utc::now(),
+            data: serde_json::to_value(message)
+                .map(masking::mask_payload)
+                .unwrap_or_default(),
+            reference: None,
+        }
+    }
+
+    pub fn with_reference(mut self, reference: Option<&str>) -> Self {
+        self.reference = reference.map(str::to_string);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RegisterWebhook {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    pub secret: Option<String>,
+}
+
+pub struct Webhooks {
+    hooks: RwLock<Vec<Webhook>>,
+    next_id: AtomicU64,
+    client: reqwest::Client,
+    max_attempts: u32,
+}
+
+impl Webhooks {
+    pub fn new(max_attempts: Option<u32>) -> Self {
+        Webhooks {
+            hooks: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build webhook HTTP client"),
+            max_attempts: max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+        }
+    }
+
+    pub fn register(&self, request: RegisterWebhook) -> Webhook {
+        let webhook = Webhook {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            url: request.url,
+            events: request.events,
+            secret: request.secret,
+        };
+        self.hooks.write().unwrap().push(webhook.clone());
+        webhook
+    }
+
+    pub fn list(&self) -> Vec<Webhook> {
+        self.hooks.read().unwrap().clone()
+    }
+
+    pub fn remove(&self, id: u64) -> bool {
+        let mut hooks = self.hooks.write().unwrap();
+        let before = hooks.len();
+        hooks.retain(|hook| hook.id != id);
+        hooks.len() != before
+    }
+
+    /// Queues delivery of `event` to every subscribed webhook.
+    pub fn emit(&self, event: &Event) {
+        let targets: Vec<Webhook> = self
+            .hooks
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|hook| hook.events.is_empty() || hook.events.contains(&event.event))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Webhook event not serializable: {}", e);
+                return;
+            }
+        };
+
+        for hook in targets {
+            tokio::spawn(deliver(
+                self.client.clone(),
+                hook,
+                event.clone(),
+                body.clone(),
+                self.max_attempts,
+            ));
+        }
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body`.
+pub(crate) fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    hook: Webhook,
+    event: Event,
+    body: Vec<u8>,
+    max_attempts: u32,
+) {
+    let signature = hook.secret.as_deref().map(|secret| sign(secret, &body));
+
+    for attempt in 1..=max_attempts {
+        let mut request = client
+            .post(&hook.url)
+            .header("content-type", "application/json")
+            .header("x-webhook-id", event.id.to_string())
+            .header("x-webhook-event", event.event.name())
+            .header("x-webhook-attempt", attempt.to_string())
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header("x-webhook-signature", signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => warn!(
+                "Webhook {} ({}) attempt {}/{}: HTTP {}",
+                hook.id,
+                hook.url,
+                attempt,
+                max_attempts,
+                response.status()
+            ),
+            Err(e) => warn!(
+                "Webhook {} ({}) attempt {}/{}: {}",
+                hook.id, hook.url, attempt, max_attempts, e
+            ),
+        }
+
+        if attempt < max_attempts {
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1).min(6))).await;
+        }
+    }
+    warn!(
+        "Webhook {} gave up on event {} after {} attempts",
+        hook.id, event.id, max_attempts
+    );
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/admin/webhooks",
+    tag = "admin",
+    responses((status = 200, body = Vec<Webhook>)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_webhooks(State(state): State<Arc<AppState>>) -> Json<Vec<Webhook>> {
+    Json(state.webhooks.list())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/webhooks",
+    tag = "admin",
+    request_body = RegisterWebhook,
+    responses(
+        (status = 201, body = Webhook),
+        (status = 422, description = "URL is not http(s)", body = String),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn register_webhook(
+    State(state): State<Arc<AppState>>,
+    ApiJson(body): ApiJson<RegisterWebhook>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, String)> {
+    if !(body.url.starts_with("http://") || body.url.starts_with("https://")) {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("webhook URL must be http(s): {}", body.url),
+        ));
+    }
+    let webhook = state.webhooks.register(body);
+    info!(
+        "Admin: webhook {} registered for {}",
+        webhook.id, webhook.url
+    );
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/webhooks/{id}",
+    tag = "admin",
+    params(("id" = u64, Path)),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 404, description = "No such webhook"),
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn delete_webhook(State(state): State<Arc<AppState>>, Path(id): Path<u64>) -> StatusCode {
+    if state.webhooks.remove(id) {
+        info!("Admin: webhook {} removed", id);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
@@ -0,0 +1,226 @@
+use crate::iso8583::{self, Encoding, FieldDefinition, FieldFormat, FieldLength, Iso8583Message};
+
+// ============================================================================
+// Binary Wire Format
+// ============================================================================
+//
+// Mastercard network layout: the MTI in EBCDIC, a binary primary bitmap
+// (plus a secondary bitmap when any DE above 64 is present, flagged by bit
+// 1), then each present DE in order. Character fields are EBCDIC, binary
+// fields raw bytes; LLVAR/LLLVAR fields carry an EBCDIC length prefix
+// counting characters (bytes for binary fields). The mock itself speaks
+// JSON over HTTP; this is for clients that also need the network bytes.
+// Clearing files use the same framing with their own field layout.
+
+/// Field layout a message is packed with: a DE number's definition.
+pub(crate) type Layout = fn(u8) -> Option<&'static FieldDefinition>;
+
+/// ASCII to EBCDIC (code page 037).
+const ASCII_TO_EBCDIC: [u8; 128] = [
+    0x00, 0x01, 0x02, 0x03, 0x37, 0x2D, 0x2E, 0x2F, //
+    0x16, 0x05, 0x25, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, //
+    0x10, 0x11, 0x12, 0x13, 0x3C, 0x3D, 0x32, 0x26, //
+    0x18, 0x19, 0x3F, 0x27, 0x1C, 0x1D, 0x1E, 0x1F, //
+    0x40, 0x5A, 0x7F, 0x7B, 0x5B, 0x6C, 0x50, 0x7D, //
+    0x4D, 0x5D, 0x5C, 0x4E, 0x6B, 0x60, 0x4B, 0x61, //
+    0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, //
+    0xF8, 0xF9, 0x7A, 0x5E, 0x4C, 0x7E, 0x6E, 0x6F, //
+    0x7C, 0xC1, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, //
+    0xC8, 0xC9, 0xD1, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, //
+    0xD7, 0xD8, 0xD9, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, //
+    0xE7, 0xE8, 0xE9, 0xBA, 0xE0, 0xBB, 0xB0, 0x6D, //
+    0x79, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, //
+    0x88, 0x89, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, //
+    0x97, 0x98, 0x99, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, //
+    0xA7, 0xA8, 0xA9, 0xC0, 0x4F, 0xD0, 0xA1, 0x07, //
+];
+
+/// EBCDIC to ASCII; bytes with no ASCII equivalent map to 0xFF.
+const EBCDIC_TO_ASCII: [u8; 256] = {
+    let mut table = [0xFF; 256];
+    let mut ascii = 0;
+    while ascii < 128 {
+        table[ASCII_TO_EBCDIC[ascii] as usize] = ascii as u8;
+        ascii += 1;
+    }
+    table
+};
+
+fn encode_text(text: &str, out: &mut Vec<u8>) {
+    out.extend(text.bytes().map(|b| ASCII_TO_EBCDIC[b as usize & 0x7F]));
+}
+
+fn decode_text(bytes: &[u8]) -> Result<String, String> {
+    bytes
+        .iter()
+        .map(|&b| match EBCDIC_TO_ASCII[b as usize] {
+            0xFF => Err(format!("byte {:#04x} is not EBCDIC text", b)),
+            ascii => Ok(ascii as char),
+        })
+        .collect()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .filter_map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Packs a message into Mastercard network bytes, validating every field
+/// against the registry first.
+pub fn pack(message: &Iso8583Message) -> Result<Vec<u8>, String> {
+    pack_with(
+        &message.mti,
+        message
+            .fields
+            .iter()
+            .map(|(number, value)| (number, value.as_str())),
+        iso8583::field,
+    )
+}
+
+/// `pack` for fields in DE order under `layout`.
+pub(crate) fn pack_with<'a>(
+    mti: &str,
+    fields: impl IntoIterator<Item = (u8, &'a str)>,
+    layout: Layout,
+) -> Result<Vec<u8>, String> {
+    if mti.len() != 4 || !mti.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("mti: {:?} is not four digits", mti));
+    }
+
+    let mut bitmap = [0u8; 16];
+    let mut body = Vec::new();
+    for (number, raw) in fields {
+        let definition =
+            layout(number).ok_or_else(|| format!("de{}: not in the field registry", number))?;
+        definition
+            .check(raw)
+            .map_err(|e| format!("de{}: {}", number, e))?;
+
+        let index = usize::from(number - 1);
+        bitmap[index / 8] |= 0x80 >> (index % 8);
+
+        let data = match definition.encoding {
+            Encoding::Binary => hex_decode(raw),
+            Encoding::Ebcdic => {
+                let text = match definition.length {
+                    FieldLength::Fixed(length) if definition.format == FieldFormat::Numeric => {
+                        format!("{:0>width$}", raw, width = length)
+                    }
+                    FieldLength::Fixed(length) => format!("{:<width$}", raw, width = length),
+                    _ => raw.to_string(),
+                };
+                let mut data = Vec::with_capacity(text.len());
+                encode_text(&text, &mut data);
+                data
+            }
+        };
+
+        match definition.length {
+            FieldLength::Fixed(length) if data.len() != length => {
+                return Err(format!(
+                    "de{}: binary field needs exactly {} bytes",
+                    number, length
+                ));
+            }
+            FieldLength::Fixed(_) => {}
+            FieldLength::Llvar(_) => encode_text(&format!("{:02}", data.len()), &mut body),
+            FieldLength::Lllvar(_) => encode_text(&format!("{:03}", data.len()), &mut body),
+        }
+        body.extend_from_slice(&data);
+    }
+
+    let secondary = bitmap[8..].iter().any(|&b| b != 0);
+    if secondary {
+        bitmap[0] |= 0x80;
+    }
+
+    let mut out = Vec::with_capacity(4 + 16 + body.len());
+    encode_text(mti, &mut out);
+    out.extend_from_slice(&bitmap[..if secondary { 16 } else { 8 }]);
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Reads a message from Mastercard network bytes. Fixed-length fields keep
+/// their padding.
+pub fn unpack(bytes: &[u8]) -> Result<Iso8583Message, String> {
+    let (mti, fields) = unpack_with(bytes, iso8583::field)?;
+    let mut message = Iso8583Message::new(mti);
+    for (number, value) in fields {
+        message.set(number, value)?;
+    }
+    Ok(message)
+}
+
+/// `unpack` under `layout`: the MTI and the fields in DE order.
+pub(crate) fn unpack_with(
+    bytes: &[u8],
+    layout: Layout,
+) -> Result<(String, Vec<(u8, String)>), String> {
+    let mut reader = Reader(bytes);
+    let mti = decode_text(reader.take(4, "mti")?)?;
+    if !mti.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("mti: {:?} is not four digits", mti));
+    }
+    let mut fields = Vec::new();
+    let mut bitmap = reader.take(8, "bitmap")?.to_vec();
+    if bitmap[0] & 0x80 != 0 {
+        bitmap.extend_from_slice(reader.take(8, "secondary bitmap")?);
+    }
+
+    for index in 1..bitmap.len() * 8 {
+        if bitmap[index / 8] & (0x80 >> (index % 8)) == 0 {
+            continue;
+        }
+        let number = (index + 1) as u8;
+        let definition =
+            layout(number).ok_or_else(|| format!("de{}: not in the field registry", number))?;
+        let what = format!("de{}", number);
+        let length = match definition.length {
+            FieldLength::Fixed(length) => length,
+            FieldLength::Llvar(_) => reader.length(2, &what)?,
+            FieldLength::Lllvar(_) => reader.length(3, &what)?,
+        };
+        let data = reader.take(length, &what)?;
+        let value = match definition.encoding {
+            Encoding::Binary => hex_encode(data),
+            Encoding::Ebcdic => decode_text(data).map_err(|e| format!("{}: {}", what, e))?,
+        };
+        definition
+            .check(&value)
+            .map_err(|e| format!("{}: {}", what, e))?;
+        fields.push((number, value));
+    }
+
+    if !reader.0.is_empty() {
+        return Err(format!("{} trailing bytes", reader.0.len()));
+    }
+    Ok((mti, fields))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize, what: &str) -> Result<&'a [u8], String> {
+        if self.0.len() < count {
+            return Err(format!("{}: truncated", what));
+        }
+        let (head, tail) = self.0.split_at(count);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    /// An LL or LLL length prefix.
+    fn length(&mut self, digits: usize, what: &str) -> Result<usize, String> {
+        decode_text(self.take(digits, what)?)?
+            .parse()
+            .map_err(|_| format!("{}: bad length prefix", what))
+    }
+}